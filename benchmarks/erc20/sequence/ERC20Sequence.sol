@@ -0,0 +1,21 @@
+// SPDX-License-Identifier: GPL-3.0
+pragma solidity ^0.8.17;
+
+import "../ERC20.sol";
+
+contract ERC20Sequence is ERC20 {
+    address private owner;
+
+    constructor() ERC20("ERC20Sequence", "E20S") {}
+
+    function Mint() external {
+        owner = msg.sender;
+        _mint(msg.sender, 1000000000 * 10**decimals());
+    }
+
+    function Spend() external {
+        for (uint256 i = 1; i < 1000; i++) {
+            transferFrom(owner, msg.sender, i);
+        }
+    }
+}
@@ -0,0 +1,12 @@
+// SPDX-License-Identifier: GPL-3.0
+pragma solidity ^0.8.17;
+
+contract SLoad {
+    uint256[16] private slots;
+
+    function Benchmark() external view returns (uint256 sum) {
+        for (uint256 i = 0; i < 16; i++) {
+            sum += slots[i];
+        }
+    }
+}
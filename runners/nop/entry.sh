@@ -0,0 +1,50 @@
+#!/usr/bin/env bash
+# Calibration runner that executes nothing. Each run times an empty region, so its results are the
+# fixed overhead of a runner rather than of any EVM, to compare the fastest benchmarks against.
+set -e
+
+num_runs=
+contract_code_path=
+gas_budget=
+measure_deploy=
+measure_analysis=
+while [ $# -gt 0 ]; do
+    case "$1" in
+        --num-runs) num_runs="$2"; shift 2 ;;
+        --contract-code-path) contract_code_path="$2"; shift 2 ;;
+        --gas-budget) gas_budget="$2"; shift 2 ;;
+        --measure-deploy) measure_deploy=1; shift ;;
+        --measure-analysis) measure_analysis=1; shift ;;
+        # Executing nothing, there is no gas to report, nor warmup to discard.
+        --report-gas | --steady-state) shift ;;
+        --*) shift 2 ;;
+        *) echo "unexpected argument: $1" >&2; exit 1 ;;
+    esac
+done
+
+if [ -z "$num_runs" ] || [ -z "$contract_code_path" ]; then
+    echo "--num-runs and --contract-code-path are required" >&2
+    exit 1
+fi
+# Check the contract like any runner would, but never deploy it.
+[ -s "$contract_code_path" ] || { echo "empty contract code: $contract_code_path" >&2; exit 1; }
+
+if [ -z "$EPOCHREALTIME" ]; then
+    echo "bash 5 or later is required for EPOCHREALTIME" >&2
+    exit 1
+fi
+
+if [ -n "$measure_deploy" ]; then
+    echo "deploy: 0"
+fi
+if [ -n "$measure_analysis" ]; then
+    echo "analysis: 0"
+fi
+for ((i = 0; i < num_runs; i++)); do
+    start=$EPOCHREALTIME
+    end=$EPOCHREALTIME
+    awk -v start="$start" -v end="$end" 'BEGIN { printf "%.6f\n", (end - start) * 1000 }'
+    if [ -n "$gas_budget" ]; then
+        echo "gas: 0"
+    fi
+done
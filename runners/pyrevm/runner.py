@@ -1,8 +1,12 @@
-from typing import Final, cast
+from typing import Any, Final, Optional, cast
 
 import argparse
+import http.server
+import json
 import pathlib
+import threading
 import time
+import urllib.request
 
 import eth.abc
 import eth.consensus.pow
@@ -34,9 +38,76 @@ def _load_contract_data(data_file_path: pathlib.Path) -> bytes:
         return bytes.fromhex(file.read())
 
 
-def _construct_evm() -> pyrevm.EVM:
-    evm = pyrevm.EVM()
-    return evm
+class _CachingRpcProxy:
+    """Local JSON-RPC endpoint forwarding to `url`, caching responses by method and params.
+
+    The forked block is pinned, so cached responses stay valid and runs can be repeated offline.
+    """
+
+    def __init__(self, url: str, cache_path: pathlib.Path) -> None:
+        self.url = url
+        self.cache_path = cache_path
+        self.cache: dict[str, Any] = {}
+        if cache_path.is_file():
+            with open(cache_path, mode="r") as file:
+                self.cache = json.load(file)
+        self.lock = threading.Lock()
+
+        proxy = self
+
+        class Handler(http.server.BaseHTTPRequestHandler):
+            def do_POST(self) -> None:
+                request = json.loads(self.rfile.read(int(self.headers["Content-Length"])))
+                if isinstance(request, list):
+                    response: Any = [proxy.handle(r) for r in request]
+                else:
+                    response = proxy.handle(request)
+                body = json.dumps(response).encode()
+                self.send_response(200)
+                self.send_header("Content-Type", "application/json")
+                self.send_header("Content-Length", str(len(body)))
+                self.end_headers()
+                self.wfile.write(body)
+
+            def log_message(self, *_: Any) -> None:
+                pass
+
+        self.server = http.server.ThreadingHTTPServer(("127.0.0.1", 0), Handler)
+        threading.Thread(target=self.server.serve_forever, daemon=True).start()
+
+    @property
+    def local_url(self) -> str:
+        return f"http://127.0.0.1:{self.server.server_address[1]}"
+
+    def handle(self, request: dict[str, Any]) -> dict[str, Any]:
+        key = json.dumps([request["method"], request.get("params", [])])
+        with self.lock:
+            cached = self.cache.get(key)
+        if cached is None:
+            upstream = urllib.request.Request(
+                self.url,
+                data=json.dumps({**request, "id": 1}).encode(),
+                headers={"Content-Type": "application/json"},
+            )
+            with urllib.request.urlopen(upstream) as response:
+                cached = json.load(response)
+            if "error" not in cached:
+                with self.lock:
+                    self.cache[key] = cached
+        return {**cached, "id": request.get("id")}
+
+    def save(self) -> None:
+        with self.lock:
+            with open(self.cache_path, mode="w") as file:
+                json.dump(self.cache, file)
+
+
+def _construct_evm(
+    fork_url: Optional[str] = None, fork_block: Optional[int] = None
+) -> pyrevm.EVM:
+    if fork_url is None:
+        return pyrevm.EVM()
+    return pyrevm.EVM(fork_url=fork_url, fork_block_number=fork_block)
 
 
 def _benchmark(
@@ -102,6 +173,9 @@ def parse_args() -> argparse.Namespace:
     parser.add_argument("--contract-code-path", type=pathlib.Path)
     parser.add_argument("--calldata", type=str)
     parser.add_argument("--num-runs", type=int)
+    parser.add_argument("--fork-url", type=str, default=None)
+    parser.add_argument("--fork-block", type=int, default=None)
+    parser.add_argument("--fork-cache-path", type=pathlib.Path, default=None)
     return parser.parse_args()
 
 
@@ -109,7 +183,13 @@ def main() -> None:
     args = parse_args()
 
     contract_data = _load_contract_data(args.contract_code_path)
-    evm = _construct_evm()
+    proxy = None
+    if args.fork_url is not None and args.fork_cache_path is not None:
+        proxy = _CachingRpcProxy(args.fork_url, args.fork_cache_path)
+    evm = _construct_evm(
+        fork_url=proxy.local_url if proxy is not None else args.fork_url,
+        fork_block=args.fork_block,
+    )
 
     _benchmark(
         evm,
@@ -119,6 +199,8 @@ def main() -> None:
         call_data=list(bytes.fromhex(args.calldata)),
         num_runs=args.num_runs,
     )
+    if proxy is not None:
+        proxy.save()
 
 
 if __name__ == "__main__":
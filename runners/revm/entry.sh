@@ -3,4 +3,6 @@ set -e
 
 SCRIPT_DIR=$( cd -- "$( dirname -- "${BASH_SOURCE[0]}" )" &> /dev/null && pwd )
 
-cargo run -r --manifest-path $SCRIPT_DIR/Cargo.toml -- $@
+# Set REVM_FEATURES=peak-alloc to also report the peak bytes allocated by each run, or
+# REVM_FEATURES=flamegraph to support --flamegraph-path.
+cargo run -r --manifest-path $SCRIPT_DIR/Cargo.toml ${REVM_FEATURES:+--features "$REVM_FEATURES"} -- $@
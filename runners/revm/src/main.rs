@@ -1,88 +1,915 @@
-use std::{fs, path::PathBuf, str::FromStr, time::Instant};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, Instant},
+};
 
 use bytes::Bytes;
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches, Parser};
 use revm_interpreter::{
     analysis::to_analysed,
-    primitives::{Bytecode, Env, LatestSpec, TransactTo, B160},
-    Contract, DummyHost, InstructionResult, Interpreter,
+    gas::CODEDEPOSIT,
+    primitives::{
+        create2_address, create_address, keccak256, BerlinSpec, Bytecode, ByzantiumSpec,
+        CancunSpec, CreateScheme, Env, FrontierSpec, HomesteadSpec, IstanbulSpec, LatestSpec,
+        LondonSpec, MergeSpec, PetersburgSpec, ShanghaiSpec, Spec, SpecId, SpuriousDragonSpec,
+        TangerineSpec, TransactTo, B160, B256, MAX_CODE_SIZE, U256,
+    },
+    CallInputs, Contract, CreateInputs, DummyHost, Gas, Host, InstructionResult, Interpreter,
+    SelfDestructResult,
 };
 //use revm-interpreter::{}
 
 extern crate alloc;
 
+/// Global allocator counting the bytes currently allocated and their peak, to report the memory
+/// allocated by each run apart from what the process allocated before it.
+#[cfg(feature = "peak-alloc")]
+mod peak_alloc {
+    use std::{
+        alloc::{GlobalAlloc, Layout, System},
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    struct CountingAlloc;
+
+    static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+    static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+    unsafe impl GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc(layout);
+            if !ptr.is_null() {
+                let allocated =
+                    ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+                PEAK.fetch_max(allocated, Ordering::Relaxed);
+            }
+            ptr
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout);
+            ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        }
+    }
+
+    #[global_allocator]
+    static GLOBAL: CountingAlloc = CountingAlloc;
+
+    /// Resets the peak to the bytes currently allocated, returning them as the baseline.
+    pub fn reset() -> usize {
+        let allocated = ALLOCATED.load(Ordering::Relaxed);
+        PEAK.store(allocated, Ordering::Relaxed);
+        allocated
+    }
+
+    /// Peak bytes allocated on top of `baseline` since the last reset.
+    pub fn peak_since(baseline: usize) -> usize {
+        PEAK.load(Ordering::Relaxed).saturating_sub(baseline)
+    }
+}
+
+/// Sampling profiler of the measured runs, written out as a flamegraph.
+#[cfg(feature = "flamegraph")]
+mod flamegraph {
+    use std::{fmt::Write, fs, path::Path};
+
+    use pprof::{ProfilerGuard, ProfilerGuardBuilder};
+
+    /// Samples per second, off from round numbers to avoid sampling in lockstep with periodic work.
+    const FREQUENCY: i32 = 997;
+
+    pub fn start() -> ProfilerGuard<'static> {
+        ProfilerGuardBuilder::default()
+            .frequency(FREQUENCY)
+            .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+            .build()
+            .expect("could not start the profiler")
+    }
+
+    /// Writes the samples as folded stacks, one line per stack with its sample count as taken by
+    /// `inferno` and `flamegraph.pl`, and as an SVG flamegraph.
+    pub fn write(profiler: ProfilerGuard, path: &Path) {
+        let report = profiler
+            .report()
+            .build()
+            .expect("could not build the profile");
+        fs::create_dir_all(path).expect("could not create the flamegraph directory");
+
+        let mut folded = String::new();
+        for (stack, count) in &report.data {
+            let frames = stack
+                .frames
+                .iter()
+                .rev()
+                .flat_map(|frame| frame.iter().rev())
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            writeln!(
+                folded,
+                "{};{} {count}",
+                stack.thread_name_or_id(),
+                frames.join(";")
+            )
+            .unwrap();
+        }
+        fs::write(path.join("stacks.folded"), folded).expect("could not write folded stacks");
+
+        let svg =
+            fs::File::create(path.join("flamegraph.svg")).expect("could not create the flamegraph");
+        report
+            .flamegraph(svg)
+            .expect("could not write the flamegraph");
+    }
+}
+
 /// Revolutionary EVM (revm) runner interface
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
     /// Path to the hex contract code to deploy and run
-    #[arg(long)]
-    contract_code_path: PathBuf,
+    #[arg(long, required_unless_present = "batch_path")]
+    contract_code_path: Option<PathBuf>,
 
-    /// Hex of calldata to use when calling the contract
-    #[arg(long)]
-    calldata: String,
+    /// Hex of calldata to use when calling the contract, with or without 0x. Empty calldata calls the
+    /// contract with no input, which runs its fallback function if it has a dispatcher
+    #[arg(long, required_unless_present_any = ["calldata_path", "batch_path"])]
+    calldata: Option<String>,
+
+    /// Path to the hex calldata to use when calling the contract, for calldata too large to pass
+    #[arg(long, conflicts_with = "calldata")]
+    calldata_path: Option<PathBuf>,
 
     /// Number of times to run the benchmark
     #[arg(short, long, default_value_t = 1)]
     num_runs: u8,
+
+    /// Number of the fastest runs to output, outputting all of them if omitted
+    #[arg(long)]
+    report_runs: Option<usize>,
+
+    /// Access list entry to apply to the call, as `<address>[:<key>,<key>...]`
+    #[arg(long)]
+    access_list: Vec<String>,
+
+    /// Storage slot of the contract to set before each run, as `<slot>=<value>`
+    #[arg(long)]
+    storage: Vec<String>,
+
+    /// Field of the block environment to run in, as `<field>=<value>` with one of `number`,
+    /// `timestamp`, `basefee`, `gas-limit`, `chain-id`, or `prevrandao`
+    #[arg(long)]
+    block: Vec<String>,
+
+    /// Address to deploy the contract from, the default caller if omitted
+    #[arg(long)]
+    deployer: Option<String>,
+
+    /// Nonce of the deployer when deploying the contract, 0 if omitted
+    #[arg(long)]
+    deployer_nonce: Option<u64>,
+
+    /// Call the contract repeatedly in each run until at least this much gas is used
+    #[arg(long)]
+    gas_budget: Option<u64>,
+
+    /// Call to make in each run instead of the single call with the calldata, as
+    /// `<caller>,<value>,<calldata>` with an empty caller or value for the defaults. Calls are made
+    /// in order against the same state
+    #[arg(long = "call")]
+    calls: Vec<String>,
+
+    /// Index of the call to time, timing all calls of each run if omitted
+    #[arg(long, requires = "calls")]
+    measure_step: Option<usize>,
+
+    /// Run the benchmark for at least this many milliseconds before the measured runs, discarding
+    /// the results
+    #[arg(long)]
+    warmup_time_ms: Option<u64>,
+
+    /// Directory to write a flamegraph of the measured runs to, as `stacks.folded` and
+    /// `flamegraph.svg`. Requires the `flamegraph` feature
+    #[arg(long)]
+    flamegraph_path: Option<PathBuf>,
+
+    /// Also report the mean and standard deviation of the runs after the first fifth, as warmup
+    #[arg(long)]
+    steady_state: bool,
+
+    /// Also report the time it took to deploy the contract
+    #[arg(long)]
+    measure_deploy: bool,
+
+    /// Also report the time it took to analyse the deployed contract's bytecode
+    #[arg(long)]
+    measure_analysis: bool,
+
+    /// Also report the gas used by each run, which is always reported with a gas budget
+    #[arg(long)]
+    report_gas: bool,
+
+    /// Also report the number of opcodes executed by a run, counted in an extra, untimed run
+    #[arg(long)]
+    count_opcodes: bool,
+
+    /// Hardfork to run the benchmark under, such as `shanghai` or `cancun`, the latest if omitted
+    #[arg(long)]
+    hardfork: Option<String>,
+
+    /// Path to a JSON array of benchmarks to run one after another, each with its `benchmark` name
+    /// and the `args` to run it with, instead of a single benchmark
+    #[arg(long, conflicts_with = "contract_code_path")]
+    batch_path: Option<PathBuf>,
 }
 
 const CALLER_ADDRESS: &str = "0x1000000000000000000000000000000000000001";
 
+/// Access list address standing in for the deployed contract.
+const CONTRACT_ADDRESS_ALIAS: &str = "contract";
+
+/// Account deployed by a CREATE or CREATE2 during a run.
+struct CreatedAccount {
+    code: Bytecode,
+    code_hash: B256,
+    balance: U256,
+}
+
+/// Host that seeds the contract's storage, tracks which accounts and slots are warm, and deploys the
+/// contracts that CREATE and CREATE2 create, unlike `DummyHost` which treats every slot it has seen
+/// as warm, every other slot as empty, and every account as cold, and creates nothing.
+struct BenchHost<SPEC: Spec> {
+    inner: DummyHost,
+    initial_storage: HashMap<(B160, U256), U256>,
+    initial_warm: HashSet<(B160, U256)>,
+    storage: HashMap<(B160, U256), U256>,
+    /// Storage before the current transaction, which SSTOREs are priced against.
+    original: HashMap<(B160, U256), U256>,
+    warm: HashSet<(B160, U256)>,
+    initial_warm_accounts: HashSet<B160>,
+    warm_accounts: HashSet<B160>,
+    /// Nonces of accounts that created contracts, and of the contracts they created.
+    nonces: HashMap<B160, u64>,
+    created: HashMap<B160, CreatedAccount>,
+    /// Opcodes stepped through, only counted by interpreters run with `run_inspect`.
+    opcodes: u64,
+    spec: PhantomData<SPEC>,
+}
+
+impl<SPEC: Spec> BenchHost<SPEC> {
+    fn new(
+        env: Env,
+        initial_storage: HashMap<(B160, U256), U256>,
+        initial_warm: HashSet<(B160, U256)>,
+        initial_warm_accounts: HashSet<B160>,
+    ) -> Self {
+        Self {
+            inner: DummyHost::new(env),
+            storage: initial_storage.clone(),
+            original: initial_storage.clone(),
+            warm: initial_warm.clone(),
+            initial_storage,
+            initial_warm,
+            warm_accounts: initial_warm_accounts.clone(),
+            initial_warm_accounts,
+            nonces: HashMap::new(),
+            created: HashMap::new(),
+            opcodes: 0,
+            spec: PhantomData,
+        }
+    }
+
+    /// Resets storage, warm accounts and slots, and created contracts to how they were before the
+    /// first run.
+    fn clear(&mut self) {
+        self.inner.clear();
+        self.storage = self.initial_storage.clone();
+        self.original = self.initial_storage.clone();
+        self.warm = self.initial_warm.clone();
+        self.warm_accounts = self.initial_warm_accounts.clone();
+        self.nonces.clear();
+        self.created.clear();
+    }
+
+    /// Starts a call of a run from `caller` as a transaction of its own, in which only the caller
+    /// and access-listed accounts and slots start warm, and SSTOREs are priced against storage as
+    /// the previous calls left it.
+    fn start_transaction(&mut self, caller: B160) {
+        self.original = self.storage.clone();
+        self.warm = self.initial_warm.clone();
+        self.warm_accounts = self.initial_warm_accounts.clone();
+        self.warm_accounts.insert(caller);
+        self.inner.env().tx.caller = caller;
+    }
+
+    /// Takes the next nonce of `address`. Only contracts create contracts, and they start with a
+    /// nonce of 1 since EIP-161.
+    fn next_nonce(&mut self, address: B160) -> u64 {
+        let nonce = self.nonces.entry(address).or_insert(1);
+        *nonce += 1;
+        *nonce - 1
+    }
+}
+
+impl<SPEC: Spec> Host for BenchHost<SPEC> {
+    fn step(&mut self, interpreter: &mut Interpreter, is_static: bool) -> InstructionResult {
+        self.opcodes += 1;
+        self.inner.step(interpreter, is_static)
+    }
+
+    fn step_end(
+        &mut self,
+        interpreter: &mut Interpreter,
+        is_static: bool,
+        ret: InstructionResult,
+    ) -> InstructionResult {
+        self.inner.step_end(interpreter, is_static, ret)
+    }
+
+    fn env(&mut self) -> &mut Env {
+        self.inner.env()
+    }
+
+    fn load_account(&mut self, address: B160) -> Option<(bool, bool)> {
+        let is_cold = self.warm_accounts.insert(address);
+        if self.created.contains_key(&address) {
+            return Some((is_cold, true));
+        }
+        self.inner
+            .load_account(address)
+            .map(|(_, exists)| (is_cold, exists))
+    }
+
+    fn block_hash(&mut self, number: U256) -> Option<B256> {
+        self.inner.block_hash(number)
+    }
+
+    fn balance(&mut self, address: B160) -> Option<(U256, bool)> {
+        let is_cold = self.warm_accounts.insert(address);
+        match self.created.get(&address) {
+            Some(account) => Some((account.balance, is_cold)),
+            None => self
+                .inner
+                .balance(address)
+                .map(|(balance, _)| (balance, is_cold)),
+        }
+    }
+
+    fn code(&mut self, address: B160) -> Option<(Bytecode, bool)> {
+        let is_cold = self.warm_accounts.insert(address);
+        match self.created.get(&address) {
+            Some(account) => Some((account.code.clone(), is_cold)),
+            None => self.inner.code(address).map(|(code, _)| (code, is_cold)),
+        }
+    }
+
+    fn code_hash(&mut self, address: B160) -> Option<(B256, bool)> {
+        let is_cold = self.warm_accounts.insert(address);
+        match self.created.get(&address) {
+            Some(account) => Some((account.code_hash, is_cold)),
+            None => self
+                .inner
+                .code_hash(address)
+                .map(|(code_hash, _)| (code_hash, is_cold)),
+        }
+    }
+
+    fn sload(&mut self, address: B160, index: U256) -> Option<(U256, bool)> {
+        let is_cold = self.warm.insert((address, index));
+        Some((
+            self.storage
+                .get(&(address, index))
+                .copied()
+                .unwrap_or_default(),
+            is_cold,
+        ))
+    }
+
+    fn sstore(
+        &mut self,
+        address: B160,
+        index: U256,
+        value: U256,
+    ) -> Option<(U256, U256, U256, bool)> {
+        let is_cold = self.warm.insert((address, index));
+        let original = self
+            .original
+            .get(&(address, index))
+            .copied()
+            .unwrap_or_default();
+        let present = self
+            .storage
+            .insert((address, index), value)
+            .unwrap_or_default();
+        Some((original, present, value, is_cold))
+    }
+
+    fn log(&mut self, address: B160, topics: Vec<B256>, data: Bytes) {
+        self.inner.log(address, topics, data)
+    }
+
+    fn selfdestruct(&mut self, address: B160, target: B160) -> Option<SelfDestructResult> {
+        self.inner.selfdestruct(address, target)
+    }
+
+    fn create(
+        &mut self,
+        inputs: &mut CreateInputs,
+    ) -> (InstructionResult, Option<B160>, Gas, Bytes) {
+        let nonce = self.next_nonce(inputs.caller);
+        let address = match inputs.scheme {
+            CreateScheme::Create => create_address(inputs.caller, nonce),
+            CreateScheme::Create2 { salt } => {
+                create2_address(inputs.caller, keccak256(&inputs.init_code), salt)
+            }
+        };
+        if self.created.contains_key(&address) {
+            return (
+                InstructionResult::CreateCollision,
+                None,
+                Gas::new(inputs.gas_limit),
+                Bytes::new(),
+            );
+        }
+        if SPEC::enabled(SpecId::SPURIOUS_DRAGON) {
+            self.nonces.insert(address, 1);
+        }
+        self.warm_accounts.insert(address);
+
+        // The init code runs as the new contract, and returns the code to deploy.
+        let contract = Contract::new::<SPEC>(
+            Bytes::new(),
+            to_analysed::<SPEC>(Bytecode::new_raw(inputs.init_code.clone())),
+            address,
+            inputs.caller,
+            inputs.value,
+        );
+        let mut interpreter = Interpreter::new(contract, inputs.gas_limit, false);
+        match interpreter.run::<_, SPEC>(self) {
+            InstructionResult::Stop
+            | InstructionResult::Return
+            | InstructionResult::SelfDestruct => {}
+            InstructionResult::Revert => {
+                return (
+                    InstructionResult::Revert,
+                    None,
+                    interpreter.gas,
+                    interpreter.return_value(),
+                )
+            }
+            reason => return (reason, None, interpreter.gas, Bytes::new()),
+        }
+
+        let code = interpreter.return_value();
+        if SPEC::enabled(SpecId::LONDON) && code.first() == Some(&0xef) {
+            return (
+                InstructionResult::CreateContractStartingWithEF,
+                None,
+                interpreter.gas,
+                Bytes::new(),
+            );
+        }
+        if SPEC::enabled(SpecId::SPURIOUS_DRAGON) && code.len() > MAX_CODE_SIZE {
+            return (
+                InstructionResult::CreateContractSizeLimit,
+                None,
+                interpreter.gas,
+                Bytes::new(),
+            );
+        }
+        if !interpreter.gas.record_cost(CODEDEPOSIT * code.len() as u64) {
+            return (
+                InstructionResult::OutOfGas,
+                None,
+                interpreter.gas,
+                Bytes::new(),
+            );
+        }
+        self.created.insert(
+            address,
+            CreatedAccount {
+                code_hash: keccak256(&code),
+                code: to_analysed::<SPEC>(Bytecode::new_raw(code)),
+                balance: inputs.value,
+            },
+        );
+        (
+            InstructionResult::Return,
+            Some(address),
+            interpreter.gas,
+            Bytes::new(),
+        )
+    }
+
+    fn call(&mut self, input: &mut CallInputs) -> (InstructionResult, Gas, Bytes) {
+        self.inner.call(input)
+    }
+}
+
 fn main() {
     let args = Args::parse();
+    match &args.batch_path {
+        Some(batch_path) => run_batch(batch_path),
+        None => run_benchmark(args),
+    }
+}
+
+/// Runs each benchmark of a batch as if invoked with its arguments, after a line naming it, sparing
+/// a process start per benchmark. The arguments of every benchmark are parsed before any is run,
+/// so that a bad one fails the batch before it spends time running the others.
+fn run_batch(batch_path: &Path) {
+    let batch: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(batch_path).expect("unable to open batch file"))
+            .expect("could not parse batch file as json");
+    let mut command = Args::command();
+    let benchmarks = batch
+        .as_array()
+        .expect("could not parse batch as array")
+        .iter()
+        .map(|benchmark| {
+            let name = benchmark["benchmark"]
+                .as_str()
+                .expect("could not parse batch benchmark name as string");
+            let args = benchmark["args"]
+                .as_array()
+                .expect("could not parse batch benchmark args as array")
+                .iter()
+                .map(|arg| arg.as_str().expect("could not parse batch arg as string"));
+            let args = command
+                .try_get_matches_from_mut(std::iter::once("runner-revm").chain(args))
+                .and_then(|matches| Args::from_arg_matches(&matches))
+                .unwrap_or_else(|e| panic!("could not parse args of batch benchmark {name}: {e}"));
+            assert!(
+                args.batch_path.is_none(),
+                "batch benchmark {name} can't itself be a batch"
+            );
+            (name, args)
+        })
+        .collect::<Vec<_>>();
+    for (name, args) in benchmarks {
+        println!("benchmark: {name}");
+        run_benchmark(args);
+    }
+}
+
+/// Parses the name of a hardfork the runner declares in its metadata.
+fn parse_hardfork(hardfork: &str) -> SpecId {
+    match hardfork {
+        "frontier" => SpecId::FRONTIER,
+        "homestead" => SpecId::HOMESTEAD,
+        "tangerine" => SpecId::TANGERINE,
+        "spurious-dragon" => SpecId::SPURIOUS_DRAGON,
+        "byzantium" => SpecId::BYZANTIUM,
+        "petersburg" => SpecId::PETERSBURG,
+        "istanbul" => SpecId::ISTANBUL,
+        "berlin" => SpecId::BERLIN,
+        "london" => SpecId::LONDON,
+        "merge" => SpecId::MERGE,
+        "shanghai" => SpecId::SHANGHAI,
+        "cancun" => SpecId::CANCUN,
+        hardfork => panic!("unknown hardfork {hardfork}"),
+    }
+}
 
+/// Runs a benchmark under the hardfork it was given, as the interpreter takes it as a type.
+fn run_benchmark(args: Args) {
+    match args
+        .hardfork
+        .as_deref()
+        .map_or(SpecId::LATEST, parse_hardfork)
+    {
+        SpecId::FRONTIER => run_benchmark_under::<FrontierSpec>(args),
+        SpecId::HOMESTEAD => run_benchmark_under::<HomesteadSpec>(args),
+        SpecId::TANGERINE => run_benchmark_under::<TangerineSpec>(args),
+        SpecId::SPURIOUS_DRAGON => run_benchmark_under::<SpuriousDragonSpec>(args),
+        SpecId::BYZANTIUM => run_benchmark_under::<ByzantiumSpec>(args),
+        SpecId::PETERSBURG => run_benchmark_under::<PetersburgSpec>(args),
+        SpecId::ISTANBUL => run_benchmark_under::<IstanbulSpec>(args),
+        SpecId::BERLIN => run_benchmark_under::<BerlinSpec>(args),
+        SpecId::LONDON => run_benchmark_under::<LondonSpec>(args),
+        SpecId::MERGE => run_benchmark_under::<MergeSpec>(args),
+        SpecId::SHANGHAI => run_benchmark_under::<ShanghaiSpec>(args),
+        SpecId::CANCUN => run_benchmark_under::<CancunSpec>(args),
+        SpecId::LATEST => run_benchmark_under::<LatestSpec>(args),
+        spec_id => unreachable!("hardforks aren't parsed to {spec_id:?}"),
+    }
+}
+
+fn run_benchmark_under<SPEC: Spec>(args: Args) {
     let caller_address = B160::from_str(CALLER_ADDRESS).unwrap();
 
+    let contract_code_path = args
+        .contract_code_path
+        .expect("clap requires the contract code path outside a batch");
     let contract_code: Bytes =
-        hex::decode(fs::read_to_string(args.contract_code_path).expect("unable to open file"))
+        hex::decode(fs::read_to_string(contract_code_path).expect("unable to open file"))
             .expect("could not hex decode contract code")
             .into();
-    let calldata: Bytes = hex::decode(args.calldata)
+    let calldata = match (args.calldata, args.calldata_path) {
+        (Some(calldata), _) => calldata,
+        (None, Some(path)) => fs::read_to_string(path).expect("unable to open calldata file"),
+        (None, None) => unreachable!("clap requires one of the calldata options"),
+    };
+    let calldata = calldata.trim();
+    let calldata: Bytes = hex::decode(calldata.strip_prefix("0x").unwrap_or(calldata))
         .expect("could not hex decode calldata")
         .into();
 
     // Set up the EVM with a database and create the contract
     let mut env = Env::default();
-    env.tx.caller = caller_address;
+    for field in &args.block {
+        let (field, value) = field
+            .split_once('=')
+            .expect("could not parse block field as `<field>=<value>`");
+        match field {
+            "number" => env.block.number = value.parse().expect("could not parse block number"),
+            "timestamp" => {
+                env.block.timestamp = value.parse().expect("could not parse block timestamp")
+            }
+            "basefee" => env.block.basefee = value.parse().expect("could not parse block basefee"),
+            "gas-limit" => {
+                env.block.gas_limit = value.parse().expect("could not parse block gas limit")
+            }
+            "chain-id" => env.cfg.chain_id = value.parse().expect("could not parse chain id"),
+            "prevrandao" => {
+                env.block.prevrandao =
+                    Some(B256::from_str(value).expect("could not parse block prevrandao"))
+            }
+            field => panic!("unknown block field {field}"),
+        }
+    }
+    // Without a deployer or nonce the contract is deployed from the default caller at the zero
+    // address, and otherwise where a CREATE from the deployer would deploy it.
+    let deployer_address = args.deployer.as_deref().map_or(caller_address, |deployer| {
+        B160::from_str(deployer).expect("could not parse deployer address")
+    });
+    let contract_address = if args.deployer.is_some() || args.deployer_nonce.is_some() {
+        create_address(deployer_address, args.deployer_nonce.unwrap_or_default())
+    } else {
+        B160::zero()
+    };
+    env.tx.caller = deployer_address;
     env.tx.transact_to = TransactTo::create();
     env.tx.data = calldata.clone();
 
-    let bytecode = to_analysed::<LatestSpec>(Bytecode::new_raw(contract_code));
+    let bytecode = to_analysed::<SPEC>(Bytecode::new_raw(contract_code));
 
     // revm interpreter. (rakita note: should be simplified in one of next version.)
-    let contract = Contract::new_env::<LatestSpec>(&env, bytecode);
+    let contract = Contract::new::<SPEC>(
+        env.tx.data.clone(),
+        bytecode,
+        contract_address,
+        deployer_address,
+        env.tx.value,
+    );
     let mut host = DummyHost::new(env.clone());
     let mut interpreter = Interpreter::new(contract, u64::MAX, false);
-    let reason = interpreter.run::<_, LatestSpec>(&mut host);
+    let timer = Instant::now();
+    let reason = interpreter.run::<_, SPEC>(&mut host);
+    let deploy_dur = timer.elapsed();
 
     match reason {
         InstructionResult::Stop | InstructionResult::Return => {}
         reason => panic!("unexpected exit reason while creating: {:?}", reason),
     }
     let created_contract = interpreter.return_value();
+    if args.measure_deploy {
+        println!("deploy: {}", deploy_dur.as_micros() as f64 / 1e3);
+    }
 
     env.tx.caller = caller_address;
+    env.tx.transact_to = TransactTo::Call(contract_address);
     env.tx.data = calldata;
 
-    let created_bytecode = to_analysed::<LatestSpec>(Bytecode::new_raw(created_contract));
-    let contract = Contract::new_env::<LatestSpec>(&env, created_bytecode);
+    // Analysis finds the valid jump destinations once, and is reused by every run.
+    let timer = Instant::now();
+    let created_bytecode = to_analysed::<SPEC>(Bytecode::new_raw(created_contract));
+    let contract = Contract::new_env::<SPEC>(&env, created_bytecode.clone());
+    let analysis_dur = timer.elapsed();
+    if args.measure_analysis {
+        println!("analysis: {}", analysis_dur.as_secs_f64() * 1e3);
+    }
+
+    env.tx.access_list = args
+        .access_list
+        .iter()
+        .map(|item| {
+            let (address, keys) = item.split_once(':').unwrap_or((item, ""));
+            (
+                match address {
+                    CONTRACT_ADDRESS_ALIAS => contract.address,
+                    address => {
+                        B160::from_str(address).expect("could not parse access list address")
+                    }
+                },
+                keys.split(',')
+                    .filter(|key| !key.is_empty())
+                    .map(|key| U256::from_str(key).expect("could not parse access list key"))
+                    .collect(),
+            )
+        })
+        .collect();
+    // Access-listed accounts and slots start warm, as do the called contract and the precompiles,
+    // and every other account and slot starts cold. Warmth only matters from Berlin, by when there
+    // were 9 precompiles.
+    let warm = env
+        .tx
+        .access_list
+        .iter()
+        .flat_map(|(address, keys)| keys.iter().map(|key| (*address, *key)))
+        .collect();
+    let num_precompiles = if SPEC::enabled(SpecId::CANCUN) { 10 } else { 9 };
+    let warm_accounts = env
+        .tx
+        .access_list
+        .iter()
+        .map(|(address, _)| *address)
+        .chain([contract_address])
+        .chain((1..=num_precompiles).map(B160::from_low_u64_be))
+        .collect();
+    let storage = args
+        .storage
+        .iter()
+        .map(|item| {
+            let (slot, value) = item
+                .split_once('=')
+                .expect("could not parse storage as `<slot>=<value>`");
+            (
+                (
+                    contract_address,
+                    U256::from_str(slot).expect("could not parse storage slot"),
+                ),
+                U256::from_str(value).expect("could not parse storage value"),
+            )
+        })
+        .collect();
+    // Each call is a caller and the contract called with its input and value.
+    let calls: Vec<(B160, Contract)> = if args.calls.is_empty() {
+        vec![(caller_address, contract)]
+    } else {
+        args.calls
+            .iter()
+            .map(|call| {
+                let mut parts = call.splitn(3, ',');
+                let (Some(caller), Some(value), Some(calldata)) =
+                    (parts.next(), parts.next(), parts.next())
+                else {
+                    panic!("could not parse call as `<caller>,<value>,<calldata>`");
+                };
+                let mut env = env.clone();
+                if !caller.is_empty() {
+                    env.tx.caller = B160::from_str(caller).expect("could not parse call caller");
+                }
+                if !value.is_empty() {
+                    env.tx.value = U256::from_str(value).expect("could not parse call value");
+                }
+                env.tx.data = hex::decode(calldata)
+                    .expect("could not hex decode call calldata")
+                    .into();
+                (
+                    env.tx.caller,
+                    Contract::new_env::<SPEC>(&env, created_bytecode.clone()),
+                )
+            })
+            .collect()
+    };
+    let mut host = BenchHost::<SPEC>::new(env, storage, warm, warm_accounts);
 
-    for _ in 0..args.num_runs {
-        let mut interpreter = revm_interpreter::Interpreter::new(contract.clone(), u64::MAX, false);
-        let timer = Instant::now();
-        let reason = interpreter.run::<_, LatestSpec>(&mut host);
-        let dur = timer.elapsed();
-        host.clear();
-
-        match reason {
-            InstructionResult::Return | InstructionResult::Stop => (),
-            reason => {
-                panic!("unexpected exit reason while benchmarking: {:?}", reason)
+    // Runs the benchmark once, returning the time measured, the gas used, and the output. Opcodes
+    // are only counted into the host when asked to, as stepping through them slows the run down.
+    let run = |host: &mut BenchHost<SPEC>, count_opcodes: bool| {
+        let mut output = Bytes::new();
+        // Without a gas budget, each run is a single pass over the calls.
+        let mut dur = Duration::ZERO;
+        let mut gas_used = 0;
+        loop {
+            for (step, (caller, contract)) in calls.iter().enumerate() {
+                host.start_transaction(*caller);
+                let mut interpreter =
+                    revm_interpreter::Interpreter::new(contract.clone(), u64::MAX, false);
+                let timer = Instant::now();
+                let reason = if count_opcodes {
+                    interpreter.run_inspect::<_, SPEC>(host)
+                } else {
+                    interpreter.run::<_, SPEC>(host)
+                };
+                let elapsed = timer.elapsed();
+
+                match reason {
+                    InstructionResult::Return | InstructionResult::Stop => (),
+                    reason => {
+                        panic!("unexpected exit reason while benchmarking: {:?}", reason)
+                    }
+                }
+
+                let spent = interpreter.gas.spend();
+                assert!(spent > 0, "benchmark call used no gas");
+                gas_used += spent;
+                if args.measure_step.map_or(true, |measured| measured == step) {
+                    dur += elapsed;
+                    output = interpreter.return_value();
+                }
             }
+            // State persists across the calls of a run, but not across runs.
+            host.clear();
+
+            if args
+                .gas_budget
+                .map_or(true, |gas_budget| gas_used >= gas_budget)
+            {
+                break;
+            }
+        }
+        (dur, gas_used, output)
+    };
+
+    // An extra, untimed run counts the opcodes that each run executes.
+    if args.count_opcodes {
+        run(&mut host, true);
+        println!("opcodes: {}", host.opcodes);
+    }
+
+    if let Some(warmup_time_ms) = args.warmup_time_ms {
+        let warmup_time = Duration::from_millis(warmup_time_ms);
+        let timer = Instant::now();
+        let mut warmup_runs = 0;
+        while timer.elapsed() < warmup_time {
+            run(&mut host, false);
+            warmup_runs += 1;
+        }
+        println!(
+            "warmup: {{\"runs\": {warmup_runs}, \"ms\": {}}}",
+            timer.elapsed().as_micros() as f64 / 1e3
+        );
+    }
+
+    #[cfg(not(feature = "flamegraph"))]
+    assert!(
+        args.flamegraph_path.is_none(),
+        "--flamegraph-path requires building with the flamegraph feature, e.g. by setting \
+         REVM_FEATURES=flamegraph"
+    );
+    // Only the measured runs are sampled, not deployment or warmup.
+    #[cfg(feature = "flamegraph")]
+    let profiler = args.flamegraph_path.as_ref().map(|_| flamegraph::start());
+
+    let mut output = Bytes::new();
+    let mut run_times = Vec::new();
+    // Lines output for each run, held back until all runs are done to output only the fastest.
+    let mut run_lines = Vec::new();
+    for _ in 0..args.num_runs {
+        #[cfg(feature = "peak-alloc")]
+        let baseline = peak_alloc::reset();
+        let (dur, gas_used, run_output) = run(&mut host, false);
+        output = run_output;
+
+        let mut lines = vec![format!("{}", dur.as_secs_f64() * 1e3)];
+        if args.gas_budget.is_some() || args.report_gas {
+            lines.push(format!("gas: {gas_used}"));
+        }
+        #[cfg(feature = "peak-alloc")]
+        lines.push(format!(
+            "metrics: {{\"peak_alloc_bytes\": {}}}",
+            peak_alloc::peak_since(baseline)
+        ));
+        run_lines.push(lines);
+        run_times.push(dur.as_secs_f64() * 1e3);
+    }
+
+    #[cfg(feature = "flamegraph")]
+    if let (Some(profiler), Some(path)) = (profiler, &args.flamegraph_path) {
+        flamegraph::write(profiler, path);
+    }
+
+    let mut fastest = (0..run_times.len()).collect::<Vec<_>>();
+    fastest.sort_by(|a, b| run_times[*a].total_cmp(&run_times[*b]));
+    fastest.truncate(args.report_runs.unwrap_or(run_times.len()));
+    fastest.sort();
+    for run in fastest {
+        for line in &run_lines[run] {
+            println!("{line}");
         }
+    }
 
-        println!("{}", dur.as_micros() as f64 / 1e3)
+    if args.steady_state {
+        // Keep at least one run, so that the statistics are defined.
+        let warmup_runs = (run_times.len() / 5).min(run_times.len().saturating_sub(1));
+        let steady = &run_times[warmup_runs..];
+        let mean = steady.iter().sum::<f64>() / steady.len() as f64;
+        let stddev = if steady.len() > 1 {
+            (steady.iter().map(|time| (time - mean).powi(2)).sum::<f64>()
+                / (steady.len() - 1) as f64)
+                .sqrt()
+        } else {
+            0.0
+        };
+        println!(
+            "steady-state: {{\"warmup_runs\": {warmup_runs}, \"mean_ms\": {mean}, \"stddev_ms\": {stddev}}}"
+        );
     }
+
+    println!("output: {}", hex::encode(output));
 }
@@ -1,21 +1,106 @@
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     error,
-    fs::create_dir_all,
+    fs::{self, create_dir_all},
     path::{Path, PathBuf},
-    process::Command,
+    process::{self, Command, Output},
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
 };
 
 use users::{get_current_gid, get_current_uid};
 
-use crate::metadata::Benchmark;
+use crate::{
+    events::{millis, Event, EventSink},
+    exec::{map_in_order, output_with_timeout, CommandScript, Semaphore},
+    metadata::{Benchmark, MetadataHash},
+};
+
+/// Tool that benchmarks are built with.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum BuildBackend {
+    /// solc in the `ethereum/solc` Docker image of each benchmark's solc version.
+    #[default]
+    SolcDocker,
+    /// `forge build`, treating each benchmark's build context as a Foundry project.
+    Forge,
+}
 
 #[derive(Clone, Debug)]
-struct BuildContext {
-    docker_executable: PathBuf,
+pub struct BuildContext {
+    /// Docker, or forge for the forge backend.
+    executable: PathBuf,
+    backend: BuildBackend,
     contract_path: PathBuf,
     contract_context_path: PathBuf,
+    contract_name: String,
+    /// File stem of the artifact that the benchmarked contract's bytecode ends up in.
+    artifact_name: String,
     build_path: PathBuf,
+    timeout: Option<Duration>,
+    /// Limits concurrent `docker run`s across builds sharing it, unlimited if `None`.
+    docker_slots: Option<Arc<Semaphore>>,
+    /// Script to record the build commands in, if any.
+    commands: Option<Arc<CommandScript>>,
+}
+
+impl BuildContext {
+    /// Context for building `benchmark` into its own directory under `builds_path`, with solc in
+    /// Docker at `executable`.
+    pub fn new(
+        benchmark: &Benchmark,
+        executable: &Path,
+        builds_path: &Path,
+        timeout: Option<Duration>,
+    ) -> Self {
+        let contract_name = benchmark.contract_name.clone().unwrap_or_else(|| {
+            benchmark
+                .contract
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string()
+        });
+        Self {
+            executable: executable.to_path_buf(),
+            backend: BuildBackend::SolcDocker,
+            contract_path: benchmark.contract.clone(),
+            contract_context_path: benchmark.build_context.clone(),
+            artifact_name: contract_name.clone(),
+            contract_name,
+            build_path: builds_path.join(&benchmark.name),
+            timeout,
+            docker_slots: None,
+            commands: None,
+        }
+    }
+
+    /// Names the artifact after `benchmark` rather than its contract, so that it can't be confused
+    /// with artifacts of other contracts compiled along with it.
+    pub fn with_artifact_named_by(mut self, benchmark: &Benchmark) -> Self {
+        self.artifact_name = benchmark.name.clone();
+        self
+    }
+
+    /// Builds with `backend` rather than solc in Docker, with the executable being that of the
+    /// backend.
+    pub fn with_backend(mut self, backend: BuildBackend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Shares `docker_slots` with other builds, to bound their concurrent containers.
+    pub fn with_docker_slots(mut self, docker_slots: Arc<Semaphore>) -> Self {
+        self.docker_slots = Some(docker_slots);
+        self
+    }
+
+    /// Records the commands run for the build in `commands`.
+    pub fn with_command_script(mut self, commands: Arc<CommandScript>) -> Self {
+        self.commands = Some(commands);
+        self
+    }
 }
 
 #[derive(Debug)]
@@ -29,35 +114,99 @@ pub struct BuiltBenchmark {
     pub result: BuildResult,
 }
 
-fn build_benchmark(
-    benchmark: &Benchmark,
-    build_context: &BuildContext,
-) -> Result<BuiltBenchmark, Box<dyn error::Error>> {
-    let contract_name = benchmark
-        .contract
-        .file_name()
-        .unwrap()
-        .to_string_lossy()
-        .to_string();
+const DOCKER_CONTRACT_CONTEXT_PATH: &str = "/benchmark";
+const DOCKER_BUILD_PATH: &str = "/build";
 
-    log::info!(
-        "building benchmark {} ({contract_name} w/ solc@{})...",
-        benchmark.name,
-        benchmark.solc_version
-    );
+impl BuildContext {
+    /// Path of the contract relative to the build context, which is also its path relative to the
+    /// working directory inside the solc container.
+    fn relative_contract_path(&self) -> Result<&Path, Box<dyn error::Error>> {
+        Ok(self
+            .contract_path
+            .strip_prefix(&self.contract_context_path)?)
+    }
 
-    let relative_contract_path = build_context
-        .contract_path
-        .strip_prefix(&build_context.contract_context_path)?;
+    /// Path that solc writes the bytecode of the benchmarked contract to.
+    fn solc_bin_path(&self) -> PathBuf {
+        self.build_path.join(format!("{}.bin", self.contract_name))
+    }
 
-    let docker_contract_context_path = PathBuf::from("/benchmark");
-    let docker_contract_path = docker_contract_context_path.join(relative_contract_path);
-    let docker_build_path = PathBuf::from("/build");
+    /// Path of the artifact with the bytecode of the benchmarked contract.
+    fn contract_bin_path(&self) -> PathBuf {
+        self.build_path.join(format!("{}.bin", self.artifact_name))
+    }
 
-    create_dir_all(&build_context.build_path)?;
+    /// Absolute path of the contract inside the solc container.
+    fn docker_contract_path(&self) -> Result<String, Box<dyn error::Error>> {
+        Ok(Path::new(DOCKER_CONTRACT_CONTEXT_PATH)
+            .join(self.relative_contract_path()?)
+            .to_string_lossy()
+            .to_string())
+    }
+}
 
-    let out = Command::new(&build_context.docker_executable)
+/// Error for a build by `tool` that produced the contracts in `produced` but not the benchmarked
+/// one, which is usually because the contract isn't named after its file.
+fn missing_contract_error(
+    tool: &str,
+    build_context: &BuildContext,
+    mut produced: Vec<String>,
+) -> Box<dyn error::Error> {
+    produced.sort();
+    format!(
+        "{tool} did not produce contract {} from {}, {}. Set `contract-name` in the benchmark \
+         metadata to the name of the contract to benchmark",
+        build_context.contract_name,
+        build_context
+            .contract_path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy(),
+        if produced.is_empty() {
+            "nor any other contract".to_string()
+        } else {
+            format!("only {}", produced.join(", "))
+        }
+    )
+    .into()
+}
+
+/// Names of the files in `dir` with the extension `extension`, without it.
+fn file_stems_with_extension(dir: &Path, extension: &str) -> Vec<String> {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            (path.extension()? == extension)
+                .then(|| path.file_stem().map(|s| s.to_string_lossy().to_string()))?
+        })
+        .collect()
+}
+
+/// Runs solc in Docker with the given solc arguments, returning its output. The build context is
+/// mounted as the working directory, and the build directory is only mounted if `mount_build_path`
+/// is set.
+fn run_solc(
+    benchmark: &Benchmark,
+    build_context: &BuildContext,
+    mount_build_path: bool,
+    solc_args: &[String],
+) -> Result<Output, Box<dyn error::Error>> {
+    // Name the container so that it can be cleaned up if the build times out.
+    let container_name = format!(
+        "evm-bench-build-{}-{}",
+        benchmark.name.replace(
+            |c: char| !c.is_ascii_alphanumeric() && c != '.' && c != '-',
+            "_"
+        ),
+        process::id()
+    );
+
+    let mut command = Command::new(&build_context.executable);
+    command
         .arg("run")
+        .args(["--rm", "--name", &container_name])
         .args([
             "-u",
             &format!("{}:{}", get_current_uid(), get_current_gid()),
@@ -65,53 +214,630 @@ fn build_benchmark(
         .args([
             "-v",
             &format!(
-                "{}:{}",
+                "{}:{DOCKER_CONTRACT_CONTEXT_PATH}",
                 build_context.contract_context_path.to_string_lossy(),
-                docker_contract_context_path.to_string_lossy()
             ),
         ])
-        .args([
+        .args(["-w", DOCKER_CONTRACT_CONTEXT_PATH]);
+    if mount_build_path {
+        command.args([
             "-v",
             &format!(
-                "{}:{}",
+                "{}:{DOCKER_BUILD_PATH}",
                 build_context.build_path.to_string_lossy(),
-                docker_build_path.to_string_lossy()
             ),
-        ])
+        ]);
+    }
+    command
         .arg(format!("ethereum/solc:{}", benchmark.solc_version))
-        .args(["-o", &docker_build_path.to_string_lossy()])
-        .args(["--abi", "--bin", "--optimize", "--overwrite"])
-        .arg(docker_contract_path)
-        .output()?;
+        .args(solc_args);
+    if let Some(commands) = &build_context.commands {
+        commands.record(&format!("build benchmark {}", benchmark.name), &command);
+    }
+
+    // Held until the container is gone, including when removing it after a timeout.
+    let _docker_slot = build_context.docker_slots.as_ref().map(|s| s.acquire());
+    let Some(out) = output_with_timeout(&mut command, build_context.timeout)? else {
+        log::debug!("removing timed out container {container_name}");
+        if let Err(e) = Command::new(&build_context.executable)
+            .args(["rm", "--force", &container_name])
+            .output()
+        {
+            log::warn!("could not remove container {container_name}: {e}");
+        }
+        return Err(format!(
+            "timed out after {:?}",
+            build_context.timeout.unwrap_or_default()
+        )
+        .into());
+    };
+
+    log::trace!("stdout: {}", String::from_utf8_lossy(&out.stdout));
+    log::trace!("stderr: {}", String::from_utf8_lossy(&out.stderr));
+    Ok(out)
+}
+
+pub fn build_benchmark(
+    benchmark: &Benchmark,
+    build_context: &BuildContext,
+) -> Result<BuiltBenchmark, Box<dyn error::Error>> {
+    log::info!(
+        "building benchmark {} ({} in {} w/ solc@{})...",
+        benchmark.name,
+        build_context.contract_name,
+        benchmark
+            .contract
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy(),
+        benchmark.solc_version
+    );
+
+    create_dir_all(&build_context.build_path)?;
+
+    let contract_bin_path = build_context.contract_bin_path();
+
+    match (build_context.backend, &benchmark.solc_settings) {
+        (BuildBackend::Forge, solc_settings) => {
+            // Forge builds with the project's own compiler settings.
+            if solc_settings.is_some() {
+                log::warn!(
+                    "ignoring the solc-settings of benchmark {}, which forge builds with its \
+                     project's settings",
+                    benchmark.name
+                );
+            }
+            let bytecode = build_forge(benchmark, build_context)?;
+            fs::write(&contract_bin_path, bytecode)?;
+        }
+        (BuildBackend::SolcDocker, None) => {
+            let mut solc_args = vec![
+                "-o".to_string(),
+                DOCKER_BUILD_PATH.to_string(),
+                "--abi".to_string(),
+                "--bin".to_string(),
+                "--optimize".to_string(),
+                "--overwrite".to_string(),
+            ];
+            if let Some(metadata_hash) = benchmark.metadata_hash {
+                solc_args.extend([
+                    "--metadata-hash".to_string(),
+                    metadata_hash.as_str().to_string(),
+                ]);
+            }
+            solc_args.push(build_context.docker_contract_path()?);
+            // A stale artifact from an earlier build would hide that solc didn't produce it.
+            let solc_bin_path = build_context.solc_bin_path();
+            let _ = fs::remove_file(&solc_bin_path);
+            let out = run_solc(benchmark, build_context, true, &solc_args)?;
+            if !out.status.success() {
+                return Err(format!("{}", out.status).into());
+            }
+            if !solc_bin_path.is_file() {
+                // Other benchmarks' artifacts aren't in this benchmark's build directory, but a
+                // renamed artifact of its own may be.
+                let produced = file_stems_with_extension(&build_context.build_path, "bin")
+                    .into_iter()
+                    .filter(|name| *name != build_context.artifact_name)
+                    .collect();
+                return Err(missing_contract_error("solc", build_context, produced));
+            }
+            if solc_bin_path != contract_bin_path {
+                fs::copy(&solc_bin_path, &contract_bin_path)?;
+            }
+        }
+        (BuildBackend::SolcDocker, Some(settings)) => {
+            let bytecode = build_standard_json(benchmark, build_context, settings)?;
+            fs::write(&contract_bin_path, bytecode)?;
+        }
+    }
+
+    log::debug!("built benchmark {}", benchmark.name);
+    Ok(BuiltBenchmark {
+        benchmark: benchmark.clone(),
+        result: BuildResult { contract_bin_path },
+    })
+}
+
+/// Compiles the benchmark with `solc --standard-json` using the given settings, returning the hex
+/// bytecode of the benchmarked contract.
+fn build_standard_json(
+    benchmark: &Benchmark,
+    build_context: &BuildContext,
+    settings: &str,
+) -> Result<String, Box<dyn error::Error>> {
+    let source_name = build_context
+        .relative_contract_path()?
+        .to_string_lossy()
+        .to_string();
+    let contract_name = &build_context.contract_name;
+
+    let mut settings: serde_json::Value = serde_json::from_str(settings)?;
+    let settings_object = settings
+        .as_object_mut()
+        .ok_or("solc-settings is not an object")?;
+    // A hash set in the settings themselves takes precedence.
+    if let Some(metadata_hash) = benchmark.metadata_hash {
+        settings_object
+            .entry("metadata")
+            .or_insert_with(|| serde_json::json!({}))
+            .as_object_mut()
+            .ok_or("solc-settings metadata is not an object")?
+            .entry("bytecodeHash")
+            .or_insert_with(|| metadata_hash.as_str().into());
+    }
+    settings_object.insert(
+        "outputSelection".to_string(),
+        // The ABI of every contract is also selected, so that they can be listed if the
+        // benchmarked one is missing.
+        serde_json::json!({ &source_name: { "*": ["abi"], contract_name: ["evm.bytecode.object"] } }),
+    );
+    let input = serde_json::json!({
+        "language": "Solidity",
+        "sources": { &source_name: { "urls": [&source_name] } },
+        "settings": settings,
+    });
+    let input_path = build_context.build_path.join("standard-json-input.json");
+    fs::write(&input_path, serde_json::to_string_pretty(&input)?)?;
+
+    let out = run_solc(
+        benchmark,
+        build_context,
+        true,
+        &[
+            "--allow-paths".to_string(),
+            DOCKER_CONTRACT_CONTEXT_PATH.to_string(),
+            "--standard-json".to_string(),
+            format!("{DOCKER_BUILD_PATH}/standard-json-input.json"),
+        ],
+    )?;
+    if !out.status.success() {
+        return Err(format!("{}", out.status).into());
+    }
+
+    // solc reports compilation failures in the output JSON rather than through its exit status.
+    let output: serde_json::Value = serde_json::from_slice(&out.stdout)?;
+    let errors = output
+        .get("errors")
+        .and_then(|errors| errors.as_array())
+        .into_iter()
+        .flatten()
+        .filter(|error| error.get("severity").and_then(|s| s.as_str()) == Some("error"))
+        .filter_map(|error| error.get("formattedMessage").and_then(|m| m.as_str()))
+        .collect::<Vec<_>>();
+    if !errors.is_empty() {
+        return Err(errors.join("\n").into());
+    }
+
+    let contracts = output.pointer(&format!(
+        "/contracts/{}",
+        source_name.replace('~', "~0").replace('/', "~1")
+    ));
+    match contracts
+        .and_then(|contracts| contracts.pointer(&format!("/{contract_name}/evm/bytecode/object")))
+        .and_then(|object| object.as_str())
+    {
+        Some(object) => Ok(object.to_string()),
+        None => Err(missing_contract_error(
+            "solc",
+            build_context,
+            contracts
+                .and_then(|contracts| contracts.as_object())
+                .into_iter()
+                .flat_map(|contracts| contracts.keys().cloned())
+                .collect(),
+        )),
+    }
+}
+
+/// Builds the benchmark's build context as a Foundry project with `forge build`, returning the hex
+/// bytecode of the benchmarked contract. Artifacts and the cache go in the build directory rather
+/// than the project.
+fn build_forge(
+    benchmark: &Benchmark,
+    build_context: &BuildContext,
+) -> Result<String, Box<dyn error::Error>> {
+    let out_path = build_context.build_path.join("forge-out");
+    let mut command = Command::new(&build_context.executable);
+    command
+        .arg("build")
+        .arg("--root")
+        .arg(&build_context.contract_context_path)
+        .arg("--out")
+        .arg(&out_path)
+        .arg("--cache-path")
+        .arg(build_context.build_path.join("forge-cache"));
+    if let Some(metadata_hash) = benchmark.metadata_hash {
+        // forge build has no flag for it, but takes any config from the environment.
+        command.env("FOUNDRY_BYTECODE_HASH", metadata_hash.as_str());
+    }
+    if let Some(commands) = &build_context.commands {
+        commands.record(&format!("build benchmark {}", benchmark.name), &command);
+    }
+    let out = output_with_timeout(&mut command, build_context.timeout)?.ok_or_else(|| {
+        format!(
+            "timed out after {:?}",
+            build_context.timeout.unwrap_or_default()
+        )
+    })?;
+    log::trace!("stdout: {}", String::from_utf8_lossy(&out.stdout));
+    log::trace!("stderr: {}", String::from_utf8_lossy(&out.stderr));
+    if !out.status.success() {
+        return Err(format!(
+            "{}: {}",
+            out.status,
+            String::from_utf8_lossy(&out.stderr).trim()
+        )
+        .into());
+    }
+
+    // Forge writes an artifact per contract under a directory named after its source file.
+    let contract_name = &build_context.contract_name;
+    let artifacts_path = out_path.join(build_context.contract_path.file_name().unwrap_or_default());
+    let artifact_path = artifacts_path.join(format!("{contract_name}.json"));
+    if !artifact_path.is_file() {
+        return Err(missing_contract_error(
+            "forge",
+            build_context,
+            file_stems_with_extension(&artifacts_path, "json"),
+        ));
+    }
+    let artifact: serde_json::Value = serde_json::from_slice(
+        &fs::read(&artifact_path)
+            .map_err(|e| format!("could not read {}: {e}", artifact_path.display()))?,
+    )?;
+    Ok(artifact
+        .pointer("/bytecode/object")
+        .and_then(|object| object.as_str())
+        .ok_or(format!(
+            "forge did not produce bytecode for contract {contract_name}"
+        ))?
+        .trim_start_matches("0x")
+        .to_string())
+}
+
+/// Context for building `benchmark` with `options`.
+fn build_context(
+    benchmark: &Benchmark,
+    executable: &Path,
+    builds_path: &Path,
+    options: &BuildOptions,
+) -> BuildContext {
+    let mut build_context = BuildContext::new(benchmark, executable, builds_path, options.timeout)
+        .with_backend(options.backend);
+    if options.artifacts_by_benchmark_name {
+        build_context = build_context.with_artifact_named_by(benchmark);
+    }
+    if let Some(commands) = &options.commands {
+        build_context = build_context.with_command_script(commands.clone());
+    }
+    build_context
+}
+
+/// Resolves already built artifacts for all benchmarks without building them, erroring if any
+/// artifact is missing.
+pub fn find_built_benchmarks(
+    benchmarks: &Vec<Benchmark>,
+    builds_path: &Path,
+    options: &BuildOptions,
+) -> Result<Vec<BuiltBenchmark>, Box<dyn error::Error>> {
+    log::info!(
+        "using existing artifacts for {} benchmarks...",
+        benchmarks.len()
+    );
 
-    log::trace!("stdout: {}", String::from_utf8(out.stdout).unwrap());
-    log::trace!("stderr: {}", String::from_utf8(out.stderr).unwrap());
+    let mut results = Vec::<BuiltBenchmark>::new();
+    let mut missing = Vec::new();
+    for benchmark in benchmarks {
+        let contract_bin_path =
+            build_context(benchmark, Path::new(""), builds_path, options).contract_bin_path();
+        if contract_bin_path.is_file() {
+            log::debug!(
+                "found artifact for benchmark {}: {}",
+                benchmark.name,
+                contract_bin_path.display()
+            );
+            results.push(BuiltBenchmark {
+                benchmark: benchmark.clone(),
+                result: BuildResult { contract_bin_path },
+            });
+        } else {
+            missing.push(format!(
+                "{} ({})",
+                benchmark.name,
+                contract_bin_path.display()
+            ));
+        }
+    }
 
+    if missing.is_empty() {
+        Ok(results)
+    } else {
+        Err(format!(
+            "missing build artifacts, build without --no-build first: {}",
+            missing.join(", ")
+        )
+        .into())
+    }
+}
+
+/// Compiles a benchmark without requesting or persisting any artifacts.
+pub fn check_benchmark(
+    benchmark: &Benchmark,
+    build_context: &BuildContext,
+) -> Result<(), Box<dyn error::Error>> {
+    log::info!(
+        "checking benchmark {} compiles w/ solc@{}...",
+        benchmark.name,
+        benchmark.solc_version
+    );
+
+    // Compiled with the same settings as the build, but without writing its artifacts.
+    if let Some(settings) = &benchmark.solc_settings {
+        create_dir_all(&build_context.build_path)?;
+        build_standard_json(benchmark, build_context, settings)?;
+        log::debug!("checked benchmark {}", benchmark.name);
+        return Ok(());
+    }
+    let mut solc_args = vec!["--optimize".to_string()];
+    if let Some(metadata_hash) = benchmark.metadata_hash {
+        solc_args.extend([
+            "--metadata-hash".to_string(),
+            metadata_hash.as_str().to_string(),
+        ]);
+    }
+    solc_args.push(build_context.docker_contract_path()?);
+    let out = run_solc(benchmark, build_context, false, &solc_args)?;
     if out.status.success() {
-        let mut contract_bin_path = build_context.build_path.join(&contract_name);
-        contract_bin_path.set_extension("bin");
+        log::debug!("checked benchmark {}", benchmark.name);
+        Ok(())
+    } else {
+        Err(format!(
+            "{}: {}",
+            out.status,
+            String::from_utf8_lossy(&out.stderr).trim()
+        )
+        .into())
+    }
+}
 
-        log::debug!("built benchmark {}", benchmark.name);
-        Ok(BuiltBenchmark {
-            benchmark: benchmark.clone(),
-            result: BuildResult { contract_bin_path },
-        })
+/// Checks that all benchmarks compile, erroring with the names of those that do not.
+pub fn check_benchmarks(
+    benchmarks: &Vec<Benchmark>,
+    docker_executable: &Path,
+    builds_path: &Path,
+    options: &BuildOptions,
+) -> Result<(), Box<dyn error::Error>> {
+    log::info!("checking {} benchmarks compile...", benchmarks.len());
+
+    let mut failed = Vec::new();
+    for benchmark in benchmarks {
+        match check_benchmark(
+            benchmark,
+            &build_context(benchmark, docker_executable, builds_path, options),
+        ) {
+            Ok(()) => println!("pass: {}", benchmark.name),
+            Err(e) => {
+                log::warn!("could not compile benchmark {}: {e}", benchmark.name);
+                println!("FAIL: {}", benchmark.name);
+                failed.push(benchmark.name.clone());
+            }
+        }
+    }
+
+    if failed.is_empty() {
+        Ok(())
     } else {
-        Err(format!("{}", out.status).into())
+        Err(format!(
+            "{} of {} benchmarks failed to compile: {}",
+            failed.len(),
+            benchmarks.len(),
+            failed.join(", ")
+        )
+        .into())
     }
 }
 
-pub fn build_benchmarks(
+/// Builds each benchmark twice into separate directories, erroring with the names of those whose
+/// bytecode differs between the builds or that fail to build.
+pub fn check_reproducible(
     benchmarks: &Vec<Benchmark>,
     docker_executable: &Path,
     builds_path: &Path,
+    timeout: Option<Duration>,
+) -> Result<(), Box<dyn error::Error>> {
+    log::info!(
+        "checking {} benchmarks build reproducibly...",
+        benchmarks.len()
+    );
+
+    // Docker needs absolute paths to mount the build directories.
+    let reproducible_path = builds_path.join("reproducible");
+    create_dir_all(&reproducible_path)?;
+    let reproducible_path = reproducible_path.canonicalize()?;
+
+    let mut failed = Vec::new();
+    for benchmark in benchmarks {
+        let build = |attempt: &str| -> Result<Vec<u8>, Box<dyn error::Error>> {
+            let built = build_benchmark(
+                benchmark,
+                &BuildContext::new(
+                    benchmark,
+                    docker_executable,
+                    &reproducible_path.join(attempt),
+                    timeout,
+                ),
+            )?;
+            Ok(fs::read(built.result.contract_bin_path)?)
+        };
+        match build("a").and_then(|a| Ok((a, build("b")?))) {
+            Ok((a, b)) if a == b => println!("pass: {}", benchmark.name),
+            Ok(_) => {
+                log::warn!(
+                    "bytecode of benchmark {} differs between builds",
+                    benchmark.name
+                );
+                println!("FAIL: {}", benchmark.name);
+                failed.push(benchmark.name.clone());
+            }
+            Err(e) => {
+                log::warn!("could not build benchmark {}: {e}", benchmark.name);
+                println!("FAIL: {}", benchmark.name);
+                failed.push(benchmark.name.clone());
+            }
+        }
+    }
+
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} of {} benchmarks did not build reproducibly: {} (embedded metadata hashes are a \
+            common cause, try \"metadata-hash\": \"none\" or --solc-metadata-hash none)",
+            failed.len(),
+            benchmarks.len(),
+            failed.join(", ")
+        )
+        .into())
+    }
+}
+
+/// Options for building benchmarks.
+#[derive(Clone, Debug)]
+pub struct BuildOptions {
+    /// Time to allow each build before killing it, unbounded if `None`.
+    pub timeout: Option<Duration>,
+    /// Number of benchmarks to build at once.
+    pub jobs: usize,
+    /// Number of solc containers to run at once across all builds.
+    pub max_concurrent_docker: usize,
+    /// Name artifacts after benchmarks rather than their contracts.
+    pub artifacts_by_benchmark_name: bool,
+    /// Script to record the build commands in, if any.
+    pub commands: Option<Arc<CommandScript>>,
+    /// Tool to build with.
+    pub backend: BuildBackend,
+    /// Number of times to try pulling each solc image before leaving its builds to fail.
+    pub pull_attempts: u32,
+    /// Delay before retrying a failed pull, doubled after each further failure.
+    pub pull_backoff: Duration,
+    /// Build benchmarks that would build the same bytecode only once, copying the artifact to the
+    /// others.
+    pub dedup_builds: bool,
+}
+
+/// Everything that determines the bytecode a benchmark builds to, so that benchmarks with equal
+/// keys can share a build.
+#[derive(PartialEq, Eq, Hash)]
+struct BuildKey<'a> {
+    backend: BuildBackend,
+    contract: &'a Path,
+    build_context: &'a Path,
+    contract_name: String,
+    solc_version: &'a str,
+    solc_settings: Option<&'a str>,
+    metadata_hash: Option<MetadataHash>,
+}
+
+impl<'a> BuildKey<'a> {
+    fn new(benchmark: &'a Benchmark, build_context: &BuildContext) -> Self {
+        Self {
+            backend: build_context.backend,
+            contract: &benchmark.contract,
+            build_context: &benchmark.build_context,
+            contract_name: build_context.contract_name.clone(),
+            solc_version: &benchmark.solc_version,
+            solc_settings: benchmark.solc_settings.as_deref(),
+            metadata_hash: benchmark.metadata_hash,
+        }
+    }
+}
+
+/// Copies the artifact of `built` to the build directory of `benchmark`, which builds the same.
+fn copy_build(
+    benchmark: &Benchmark,
+    build_context: &BuildContext,
+    built: &BuiltBenchmark,
+) -> Result<BuiltBenchmark, Box<dyn error::Error>> {
+    log::info!(
+        "copying build of benchmark {} for benchmark {}, which builds the same",
+        built.benchmark.name,
+        benchmark.name
+    );
+    create_dir_all(&build_context.build_path)?;
+    let contract_bin_path = build_context.contract_bin_path();
+    fs::copy(&built.result.contract_bin_path, &contract_bin_path)?;
+    Ok(BuiltBenchmark {
+        benchmark: benchmark.clone(),
+        result: BuildResult { contract_bin_path },
+    })
+}
+
+/// Pulls the solc images of all benchmarks that are not present yet, one at a time, so that
+/// concurrent builds don't race to pull the same image. Each pull is attempted up to
+/// `options.pull_attempts` times, doubling the delay between attempts from
+/// `options.pull_backoff`. Failures are only warned about, leaving the builds using the image to
+/// fail.
+fn pull_solc_images(benchmarks: &[Benchmark], docker_executable: &Path, options: &BuildOptions) {
+    let mut solc_versions = benchmarks
+        .iter()
+        .map(|b| b.solc_version.as_str())
+        .collect::<Vec<_>>();
+    solc_versions.sort();
+    solc_versions.dedup();
+
+    for solc_version in solc_versions {
+        let image = format!("ethereum/solc:{solc_version}");
+        let present = Command::new(docker_executable)
+            .args(["image", "inspect", &image])
+            .output()
+            .is_ok_and(|out| out.status.success());
+        if present {
+            continue;
+        }
+        let mut backoff = options.pull_backoff;
+        for attempt in 1..=options.pull_attempts {
+            log::info!(
+                "pulling {image} (attempt {attempt} of {})...",
+                options.pull_attempts
+            );
+            let error = match Command::new(docker_executable)
+                .args(["pull", &image])
+                .output()
+            {
+                Ok(out) if out.status.success() => break,
+                Ok(out) => String::from_utf8_lossy(&out.stderr).trim_end().to_string(),
+                Err(e) => e.to_string(),
+            };
+            if attempt == options.pull_attempts {
+                log::warn!("could not pull {image}: {error}");
+            } else {
+                log::warn!("could not pull {image}, retrying in {backoff:?}: {error}");
+                thread::sleep(backoff);
+                backoff *= 2;
+            }
+        }
+    }
+}
+
+/// Builds benchmarks on `options.jobs` threads with the executable of `options.backend`, running
+/// at most `options.max_concurrent_docker` solc containers at once. Results are in the order of
+/// `benchmarks` whatever order the builds finish in, skipping those that failed to build. With
+/// `options.dedup_builds`, benchmarks that build the same as an earlier one copy its artifact.
+pub fn build_benchmarks(
+    benchmarks: &[Benchmark],
+    executable: &Path,
+    builds_path: &Path,
+    options: &BuildOptions,
+    events: &EventSink,
 ) -> Result<Vec<BuiltBenchmark>, Box<dyn error::Error>> {
+    let jobs = options.jobs;
     let benchmark_names = benchmarks
         .iter()
         .map(|b| b.name.clone())
         .collect::<HashSet<_>>();
 
-    log::info!("building {} benchmarks...", benchmarks.len());
+    log::info!("building {} benchmarks on {jobs} jobs...", benchmarks.len());
     log::debug!(
         "benchmarks: {}",
         benchmark_names
@@ -121,27 +847,95 @@ pub fn build_benchmarks(
             .join(", ")
     );
 
-    let mut results = Vec::<BuiltBenchmark>::new();
-    for benchmark in benchmarks {
-        results.push(
-            match build_benchmark(
-                benchmark,
-                &BuildContext {
-                    docker_executable: docker_executable.to_path_buf(),
-                    contract_path: benchmark.contract.clone(),
-                    contract_context_path: benchmark.build_context.clone(),
-                    build_path: builds_path.join(&benchmark.name),
-                },
-            ) {
-                Ok(res) => res,
-                Err(e) => {
-                    log::warn!("could not build benchmark {}: {e}", benchmark.name);
-                    continue;
-                }
-            },
+    if options.backend == BuildBackend::SolcDocker {
+        pull_solc_images(benchmarks, executable, options);
+    }
+
+    let build_contexts = benchmarks
+        .iter()
+        .map(|benchmark| build_context(benchmark, executable, builds_path, options))
+        .collect::<Vec<_>>();
+    // Index of the benchmark whose build each benchmark uses, which is its own unless deduplicated.
+    let mut first_with_key = HashMap::new();
+    let sources = benchmarks
+        .iter()
+        .zip(&build_contexts)
+        .enumerate()
+        .map(|(i, (benchmark, build_context))| {
+            if options.dedup_builds {
+                *first_with_key
+                    .entry(BuildKey::new(benchmark, build_context))
+                    .or_insert(i)
+            } else {
+                i
+            }
+        })
+        .collect::<Vec<_>>();
+    let to_build = (0..benchmarks.len())
+        .filter(|i| sources[*i] == *i)
+        .collect::<Vec<_>>();
+    if to_build.len() < benchmarks.len() {
+        log::info!(
+            "deduplicated {} builds of benchmarks that build the same as others",
+            benchmarks.len() - to_build.len()
         );
     }
 
+    let docker_slots = Arc::new(Semaphore::new(options.max_concurrent_docker));
+    let mut results = (0..benchmarks.len()).map(|_| None).collect::<Vec<_>>();
+    let builds = map_in_order(&to_build, jobs, |i| {
+        let benchmark = &benchmarks[*i];
+        events.emit(Event::BuildStarted {
+            benchmark: &benchmark.name,
+        });
+        let timer = Instant::now();
+        let result = build_benchmark(
+            benchmark,
+            &build_contexts[*i]
+                .clone()
+                .with_docker_slots(docker_slots.clone()),
+        );
+        events.emit(Event::BuildFinished {
+            benchmark: &benchmark.name,
+            duration_ms: millis(timer.elapsed()),
+            error: result.as_ref().err().map(|e| e.to_string()),
+        });
+        result
+            .map_err(|e| log::warn!("could not build benchmark {}: {e}", benchmark.name))
+            .ok()
+    });
+    for (i, built) in to_build.into_iter().zip(builds) {
+        results[i] = built;
+    }
+    for (i, benchmark) in benchmarks.iter().enumerate() {
+        let source = sources[i];
+        if source == i {
+            continue;
+        }
+        let Some(built) = &results[source] else {
+            log::warn!(
+                "could not build benchmark {}: benchmark {}, which builds the same, failed to build",
+                benchmark.name,
+                benchmarks[source].name
+            );
+            continue;
+        };
+        events.emit(Event::BuildStarted {
+            benchmark: &benchmark.name,
+        });
+        let timer = Instant::now();
+        let result = copy_build(benchmark, &build_contexts[i], built);
+        events.emit(Event::BuildFinished {
+            benchmark: &benchmark.name,
+            duration_ms: millis(timer.elapsed()),
+            error: result.as_ref().err().map(|e| e.to_string()),
+        });
+        results[i] = result
+            .map_err(|e| log::warn!("could not build benchmark {}: {e}", benchmark.name))
+            .ok();
+    }
+    let results = results.into_iter().flatten().collect::<Vec<_>>();
+
     log::debug!(
         "built {} benchmarks ({} successful)",
         benchmarks.len(),
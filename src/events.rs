@@ -0,0 +1,121 @@
+use std::{
+    error,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    sync::Mutex,
+    time::Duration,
+};
+
+use serde::Serialize;
+
+/// A progress event, for wrappers that render live progress without parsing the human logs.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum Event<'a> {
+    BuildStarted {
+        benchmark: &'a str,
+    },
+    BuildFinished {
+        benchmark: &'a str,
+        duration_ms: f64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    RunStarted {
+        benchmark: &'a str,
+        runner: &'a str,
+    },
+    RunFinished {
+        benchmark: &'a str,
+        runner: &'a str,
+        duration_ms: f64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// The result of a benchmark on a runner, as shown in a cell of the results table.
+    CellResult {
+        benchmark: &'a str,
+        runner: &'a str,
+        mean_ms: f64,
+        num_runs: usize,
+    },
+}
+
+/// Converts a duration to the fractional milliseconds used in events.
+pub fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1e3
+}
+
+#[derive(Serialize)]
+struct Envelope<'a> {
+    time: String,
+    #[serde(flatten)]
+    event: &'a Event<'a>,
+}
+
+/// Sink that writes events as newline-delimited JSON, separately from the logs. Disabled sinks
+/// drop all events.
+#[derive(Default)]
+pub struct EventSink {
+    writer: Option<Mutex<Box<dyn Write + Send>>>,
+}
+
+impl EventSink {
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    fn new(writer: impl Write + Send + 'static) -> Self {
+        Self {
+            writer: Some(Mutex::new(Box::new(writer))),
+        }
+    }
+
+    /// Sink writing to the file at `path`, truncating it if it exists.
+    pub fn to_file(path: &Path) -> Result<Self, Box<dyn error::Error>> {
+        let file = File::create(path)
+            .map_err(|e| format!("could not create events file {}: {e}", path.display()))?;
+        Ok(Self::new(BufWriter::new(file)))
+    }
+
+    /// Sink writing to the already open file descriptor `fd`, e.g. a pipe set up by a wrapper.
+    #[cfg(unix)]
+    pub fn to_fd(fd: i32) -> Result<Self, Box<dyn error::Error>> {
+        use std::os::fd::FromRawFd;
+
+        if fd <= 2 {
+            return Err(format!("events fd {fd} would interleave with stdio").into());
+        }
+        // SAFETY: the fd is handed to us by whoever invoked us, and is not used anywhere else.
+        Ok(Self::new(unsafe { File::from_raw_fd(fd) }))
+    }
+
+    #[cfg(not(unix))]
+    pub fn to_fd(_: i32) -> Result<Self, Box<dyn error::Error>> {
+        Err("events fds are only supported on unix".into())
+    }
+
+    /// Writes an event as a single line, flushing so that readers see it immediately. Failing to
+    /// write is only warned about, so that a closed side channel doesn't abort benchmarking.
+    pub fn emit(&self, event: Event) {
+        let Some(writer) = &self.writer else {
+            return;
+        };
+        let envelope = Envelope {
+            time: chrono::offset::Utc::now().to_rfc3339(),
+            event: &event,
+        };
+        let mut writer = writer.lock().expect("events writer poisoned");
+        let res = serde_json::to_string(&envelope)
+            .map_err(Into::<Box<dyn error::Error>>::into)
+            .and_then(|line| {
+                writeln!(writer, "{line}")?;
+                writer.flush()?;
+                Ok(())
+            });
+        if let Err(e) = res {
+            log::warn!("could not write event {event:?}: {e}");
+        }
+    }
+}
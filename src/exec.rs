@@ -1,30 +1,455 @@
 use std::{
     error,
+    fs::{self, File},
+    io::{self, Read, Write},
     path::{Path, PathBuf},
-    process::Command,
+    process::{exit, Child, Command, Output, Stdio},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Condvar, Mutex, PoisonError,
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Cleanups registered with [`on_forced_quit`].
+static QUIT_CLEANUPS: Mutex<Vec<Box<dyn Fn() + Send>>> = Mutex::new(Vec::new());
+
+/// Handles Ctrl-C by flagging that evm-bench was [`interrupted`] rather than quitting, so that it
+/// stops starting new work, kills the processes in flight, and still records the results so far.
+/// A second Ctrl-C quits immediately.
+pub fn handle_interrupts() -> Result<(), Box<dyn error::Error>> {
+    ctrlc::set_handler(|| {
+        if INTERRUPTED.swap(true, Ordering::SeqCst) {
+            log::error!("interrupted again, quitting without saving results");
+            for cleanup in QUIT_CLEANUPS
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .iter()
+            {
+                cleanup();
+            }
+            exit(130);
+        }
+        log::warn!(
+            "interrupted, saving the results so far before quitting (press Ctrl-C again to quit \
+             immediately)"
+        );
+    })?;
+    Ok(())
+}
+
+/// Runs `cleanup` if evm-bench quits on a second Ctrl-C, which skips destructors, e.g. to restore
+/// state that is otherwise restored on drop. It may run after the state was already restored.
+pub fn on_forced_quit(cleanup: impl Fn() + Send + 'static) {
+    QUIT_CLEANUPS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(Box::new(cleanup));
+}
+
+/// Whether Ctrl-C was pressed since [`handle_interrupts`].
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Checks that an executable can be run, returning the path it was found at.
+///
+/// `executable` is the path the user passed explicitly through `flag`, if any. Errors for explicit
+/// paths name the flag so that a typo is easy to track down, otherwise `name` is looked up in `PATH`.
 pub fn validate_executable(
     name: &str,
-    executable: &Path,
+    flag: Option<&str>,
+    executable: Option<&Path>,
 ) -> Result<PathBuf, Box<dyn error::Error>> {
-    log::trace!("validating executable {} ({name})", executable.display());
-    match Command::new(&executable).arg("--version").output() {
+    let path = executable.map_or_else(|| PathBuf::from(name), Path::to_path_buf);
+    log::trace!("validating executable {} ({name})", path.display());
+    match Command::new(&path).arg("--version").output() {
         Ok(out) => {
             log::debug!(
                 "found {name} ({}): {}",
-                executable.display(),
+                path.display(),
                 String::from_utf8(out.stdout)
                     .expect("could not decode program stdout")
-                    .trim_end_matches("\n")
+                    .trim_end_matches('\n')
             );
-            Ok(executable.to_path_buf())
+            Ok(path)
         }
-        Err(e) => match e.kind() {
-            std::io::ErrorKind::NotFound => {
-                Err(format!("{name} not found, tried {}", executable.display()).into())
-            }
-            _ => Err(format!("unknown error: {e}").into()),
+        Err(e) => match (executable.zip(flag), e.kind()) {
+            (Some((path, flag)), std::io::ErrorKind::NotFound) => Err(format!(
+                "{name} not found at {} (given by --{flag}), check the path or omit --{flag} to search PATH",
+                path.display()
+            )
+            .into()),
+            (Some((path, flag)), _) => Err(format!(
+                "could not run {name} at {} (given by --{flag}): {e}, check the path or omit --{flag} to search PATH",
+                path.display()
+            )
+            .into()),
+            (None, std::io::ErrorKind::NotFound) => match flag {
+                Some(flag) => {
+                    Err(format!("{name} not found in PATH, use --{flag} to point at it").into())
+                }
+                None => Err(format!("{name} not found in PATH").into()),
+            },
+            (None, _) => Err(format!("could not run {name}: {e}").into()),
         },
     }
 }
+
+/// Kills `child`, along with anything it spawned if it leads its own process group.
+fn kill(child: &mut Child, own_group: bool) -> io::Result<()> {
+    #[cfg(unix)]
+    if own_group {
+        // SAFETY: killpg only sends a signal, to the group the child leads.
+        if unsafe { libc::killpg(child.id() as libc::pid_t, libc::SIGKILL) } != 0 {
+            let e = io::Error::last_os_error();
+            // The group is already gone if the child and everything it spawned have exited.
+            if e.raw_os_error() != Some(libc::ESRCH) {
+                log::warn!(
+                    "could not kill the process group of process {}, processes it spawned may \
+                     still be running: {e}",
+                    child.id()
+                );
+            }
+        }
+    }
+    child.kill()?;
+    child.wait()?;
+    Ok(())
+}
+
+/// Runs `command` to completion like [`Command::output`], but kills it once `timeout` elapses, or
+/// if evm-bench is [`interrupted`], in which case it errors.
+///
+/// Returns `None` if the command was killed for running too long.
+pub fn output_with_timeout(
+    command: &mut Command,
+    timeout: Option<Duration>,
+) -> io::Result<Option<Output>> {
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    // Run the child in its own process group, so that anything it spawns is killed with it. Without
+    // a timeout it stays in ours, so that a Ctrl-C from the terminal reaches it directly too.
+    #[cfg(unix)]
+    if timeout.is_some() {
+        std::os::unix::process::CommandExt::process_group(command, 0);
+    }
+    let mut child = command.spawn()?;
+
+    // Drain the pipes while waiting so that a chatty child can't block on a full buffer.
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+    let stdout_reader = thread::spawn(move || {
+        let mut buf = Vec::new();
+        stdout.read_to_end(&mut buf).map(|_| buf)
+    });
+    let stderr_reader = thread::spawn(move || {
+        let mut buf = Vec::new();
+        stderr.read_to_end(&mut buf).map(|_| buf)
+    });
+
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if interrupted() {
+            log::debug!("killing process {} after an interrupt", child.id());
+            kill(&mut child, timeout.is_some())?;
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "interrupted"));
+        }
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            log::debug!("killing process {} after {:?}", child.id(), timeout);
+            kill(&mut child, true)?;
+            return Ok(None);
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    Ok(Some(Output {
+        status,
+        stdout: stdout_reader.join().expect("stdout reader panicked")?,
+        stderr: stderr_reader.join().expect("stderr reader panicked")?,
+    }))
+}
+
+/// Whether [`limit_memory`] is enforced on this platform.
+pub const MEMORY_LIMIT_SUPPORTED: bool = cfg!(target_os = "linux");
+
+/// Limits the address space of the process spawned by `command` to `bytes`, so that allocating
+/// past it fails in that process rather than exhausting the machine. A no-op where
+/// [`MEMORY_LIMIT_SUPPORTED`] is unset.
+#[cfg(target_os = "linux")]
+pub fn limit_memory(command: &mut Command, bytes: u64) {
+    use std::os::unix::process::CommandExt;
+
+    let limit = libc::rlimit {
+        rlim_cur: bytes,
+        rlim_max: bytes,
+    };
+    // SAFETY: setrlimit is async-signal-safe, and nothing else is done between fork and exec.
+    unsafe {
+        command.pre_exec(move || {
+            if libc::setrlimit(libc::RLIMIT_AS, &limit) == 0 {
+                Ok(())
+            } else {
+                Err(io::Error::last_os_error())
+            }
+        });
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn limit_memory(_: &mut Command, _: u64) {}
+
+/// Whether runner processes can be limited to a CPU quota, which needs systemd on Linux.
+pub const CPU_QUOTA_SUPPORTED: bool = cfg!(target_os = "linux");
+
+/// Command running `program` in a transient systemd scope limited to `pct` percent of a CPU, e.g.
+/// 50 for half a core, as a throttled container would be. Needs `systemd-run`, and a systemd user
+/// session unless running as root.
+pub fn cpu_quota_command(program: &Path, pct: u32) -> Command {
+    let mut command = Command::new("systemd-run");
+    if users::get_current_uid() != 0 {
+        command.arg("--user");
+    }
+    command
+        .args(["--scope", "--quiet", "--collect"])
+        .arg(format!("--property=CPUQuota={pct}%"))
+        .arg("--")
+        .arg(program);
+    command
+}
+
+/// Directory for the files of a single runner invocation, such as its logs or inputs too large to
+/// pass as arguments. It lives under the output path rather than the system temp directory, so
+/// that it can be inspected when kept.
+#[derive(Debug)]
+pub struct RunDir {
+    path: PathBuf,
+}
+
+impl RunDir {
+    /// Creates the directory for running `benchmark_name` on `runner_name` under `root`, clearing
+    /// out any files left from a previous invocation.
+    pub fn create(
+        root: &Path,
+        benchmark_name: &str,
+        runner_name: &str,
+    ) -> Result<Self, Box<dyn error::Error>> {
+        let path = root.join(benchmark_name).join(runner_name);
+        if path.exists() {
+            fs::remove_dir_all(&path)?;
+        }
+        fs::create_dir_all(&path)?;
+        Ok(Self { path })
+    }
+
+    /// Writes a file into the directory, returning its path.
+    pub fn write(
+        &self,
+        file_name: &str,
+        contents: impl AsRef<[u8]>,
+    ) -> Result<PathBuf, Box<dyn error::Error>> {
+        let path = self.path.join(file_name);
+        fs::write(&path, contents)?;
+        Ok(path)
+    }
+
+    /// Removes the directory, or keeps it for debugging if `keep` is set.
+    pub fn finish(self, keep: bool) {
+        if keep {
+            log::info!("kept run files in {}", self.path.display());
+        } else if let Err(e) = fs::remove_dir_all(&self.path) {
+            log::warn!("could not remove run files in {}: {e}", self.path.display());
+        }
+    }
+}
+
+/// Empty directory under the system temp directory for the files of a test, removed when dropped
+/// so that tests don't leave their files behind, even when they fail.
+#[cfg(test)]
+#[derive(Debug)]
+pub struct TempDir(PathBuf);
+
+#[cfg(test)]
+impl TempDir {
+    /// Creates the directory named `name` and the process id, clearing out any left behind.
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("evm-bench-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+}
+
+#[cfg(test)]
+impl std::ops::Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Counting semaphore bounding how many threads hold a permit at once.
+#[derive(Debug)]
+pub struct Semaphore {
+    permits: Mutex<usize>,
+    released: Condvar,
+}
+
+/// Permit of a [`Semaphore`], released when dropped.
+pub struct SemaphorePermit<'a>(&'a Semaphore);
+
+impl Semaphore {
+    pub fn new(permits: usize) -> Self {
+        Self {
+            permits: Mutex::new(permits),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Blocks until a permit is available, and takes it.
+    pub fn acquire(&self) -> SemaphorePermit<'_> {
+        let mut permits = self.permits.lock().expect("semaphore poisoned");
+        while *permits == 0 {
+            permits = self.released.wait(permits).expect("semaphore poisoned");
+        }
+        *permits -= 1;
+        SemaphorePermit(self)
+    }
+}
+
+impl Drop for SemaphorePermit<'_> {
+    fn drop(&mut self) {
+        *self.0.permits.lock().expect("semaphore poisoned") += 1;
+        self.0.released.notify_one();
+    }
+}
+
+/// Shell script that the commands run for builds and runs are written to, so that any single step
+/// can be reproduced by hand.
+#[derive(Debug)]
+pub struct CommandScript {
+    file: Mutex<File>,
+}
+
+impl CommandScript {
+    /// Creates the script at `path`, truncating it if it exists.
+    pub fn create(path: &Path) -> Result<Self, Box<dyn error::Error>> {
+        let mut file = File::create(path)
+            .map_err(|e| format!("could not create command script {}: {e}", path.display()))?;
+        writeln!(file, "#!/bin/sh")?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Appends `command` with its working directory and environment, preceded by `comment`.
+    /// Failing to write is only warned about, so that it doesn't abort benchmarking.
+    pub fn record(&self, comment: &str, command: &Command) {
+        let mut line = command
+            .get_envs()
+            .filter_map(|(key, value)| {
+                Some(format!(
+                    "{}={}",
+                    key.to_string_lossy(),
+                    shell_quote(&value?.to_string_lossy())
+                ))
+            })
+            .chain(
+                [command.get_program()]
+                    .into_iter()
+                    .chain(command.get_args())
+                    .map(|arg| shell_quote(&arg.to_string_lossy())),
+            )
+            .collect::<Vec<_>>()
+            .join(" ");
+        if let Some(dir) = command.get_current_dir() {
+            line = format!("(cd {} && {line})", shell_quote(&dir.to_string_lossy()));
+        }
+        let mut file = self.file.lock().expect("command script poisoned");
+        if let Err(e) = writeln!(file, "\n# {comment}\n{line}") {
+            log::warn!("could not write command for {comment}: {e}");
+        }
+    }
+}
+
+/// Quotes `arg` for a POSIX shell, leaving it bare if that is unambiguous.
+fn shell_quote(arg: &str) -> String {
+    if !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=,@%+".contains(c))
+    {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// Calls `f` on every item on `jobs` threads, returning the results in the order of `items`
+/// regardless of the order the calls finish in.
+pub fn map_in_order<T: Sync, R: Send>(
+    items: &[T],
+    jobs: usize,
+    f: impl Fn(&T) -> R + Sync,
+) -> Vec<R> {
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new((0..items.len()).map(|_| None).collect::<Vec<_>>());
+    thread::scope(|scope| {
+        for _ in 0..jobs.min(items.len()) {
+            scope.spawn(|| {
+                // Each job takes the next item not taken by any other job, and fills in its slot.
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(item) = items.get(i) else {
+                        break;
+                    };
+                    let result = f(item);
+                    results.lock().expect("results poisoned")[i] = Some(result);
+                }
+            });
+        }
+    });
+    results
+        .into_inner()
+        .expect("results poisoned")
+        .into_iter()
+        .map(|result| result.expect("every item is taken by a job"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_in_order_keeps_input_order_when_finishing_out_of_order() {
+        // Earlier items take longer, so that they finish after later ones.
+        let items = [4u64, 3, 2, 1, 0];
+        let finished = Mutex::new(Vec::new());
+        let results = map_in_order(&items, items.len(), |item| {
+            thread::sleep(Duration::from_millis(item * 50));
+            finished.lock().unwrap().push(*item);
+            item * 10
+        });
+        assert_eq!(results, vec![40, 30, 20, 10, 0]);
+        assert_ne!(finished.into_inner().unwrap(), items);
+    }
+}
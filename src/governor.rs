@@ -0,0 +1,131 @@
+use std::{
+    collections::BTreeSet,
+    error, fs, mem,
+    path::PathBuf,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use crate::exec::on_forced_quit;
+
+/// Pattern of the files holding the frequency scaling governor of each CPU on Linux.
+const GOVERNOR_PATHS: &str = "/sys/devices/system/cpu/cpu[0-9]*/cpufreq/scaling_governor";
+
+/// Governor that keeps CPUs at their highest frequency, so that run times don't depend on how
+/// busy a CPU was before each run.
+const PERFORMANCE: &str = "performance";
+
+/// Frequency scaling governor of every CPU with one, along with the file holding it. Empty where
+/// frequency scaling can't be read, such as outside Linux and in many VMs.
+fn governors() -> Vec<(PathBuf, String)> {
+    let mut governors = glob::glob(GOVERNOR_PATHS)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|path| {
+            let governor = fs::read_to_string(&path).ok()?.trim().to_string();
+            Some((path, governor))
+        })
+        .collect::<Vec<_>>();
+    governors.sort();
+    governors
+}
+
+/// Warns if any CPU's frequency scaling governor isn't `performance`, which makes results noisy.
+pub fn warn_unless_performance_governor() {
+    let governors = governors();
+    let others = governors
+        .iter()
+        .map(|(_, governor)| governor.as_str())
+        .filter(|governor| *governor != PERFORMANCE)
+        .collect::<Vec<_>>();
+    if others.is_empty() {
+        return;
+    }
+    log::warn!(
+        "the CPU frequency scaling governor is {} rather than {PERFORMANCE} on {} of {} CPUs, so \
+         results may vary with CPU frequency. Pass --performance-governor to set it for the run, \
+         or set it with `cpupower frequency-set -g {PERFORMANCE}`",
+        others
+            .iter()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .copied()
+            .collect::<Vec<_>>()
+            .join(", "),
+        others.len(),
+        governors.len()
+    );
+}
+
+/// CPU frequency scaling governors set to `performance`, which are restored to what they were
+/// when dropped, or when evm-bench quits on a second Ctrl-C.
+#[derive(Debug)]
+pub struct PerformanceGovernor {
+    /// Governors to restore, taken by whichever restores them first.
+    previous: Arc<Mutex<Vec<(PathBuf, String)>>>,
+}
+
+impl PerformanceGovernor {
+    /// Sets the governor of every CPU to `performance`, which usually needs root. Governors set
+    /// before failing to set one are restored.
+    pub fn set() -> Result<Self, Box<dyn error::Error>> {
+        let governors = governors();
+        if governors.is_empty() {
+            log::warn!(
+                "found no CPU frequency scaling governors to set, ignoring --performance-governor"
+            );
+        }
+        let set = Self {
+            previous: Arc::default(),
+        };
+        let previous = set.previous.clone();
+        on_forced_quit(move || restore(&previous));
+        for (path, governor) in governors {
+            if governor == PERFORMANCE {
+                continue;
+            }
+            fs::write(&path, PERFORMANCE).map_err(|e| {
+                format!(
+                    "could not set the CPU frequency scaling governor in {} to {PERFORMANCE}, \
+                     which usually needs root: {e}",
+                    path.display()
+                )
+            })?;
+            set.previous
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push((path, governor));
+        }
+        let count = set
+            .previous
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len();
+        if count > 0 {
+            log::info!("set the CPU frequency scaling governor of {count} CPUs to {PERFORMANCE}");
+        }
+        Ok(set)
+    }
+}
+
+/// Restores the governors in `previous`, leaving nothing to restore for later calls.
+fn restore(previous: &Mutex<Vec<(PathBuf, String)>>) {
+    let previous = mem::take(&mut *previous.lock().unwrap_or_else(PoisonError::into_inner));
+    for (path, governor) in &previous {
+        if let Err(e) = fs::write(path, governor) {
+            log::error!(
+                "could not restore the CPU frequency scaling governor in {} to {governor}: {e}",
+                path.display()
+            );
+        }
+    }
+    if !previous.is_empty() {
+        log::info!("restored the CPU frequency scaling governors");
+    }
+}
+
+impl Drop for PerformanceGovernor {
+    fn drop(&mut self) {
+        restore(&self.previous);
+    }
+}
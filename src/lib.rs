@@ -0,0 +1,40 @@
+use std::{error, path::Path};
+
+extern crate glob;
+
+pub mod build;
+pub mod events;
+pub mod exec;
+pub mod governor;
+pub mod manifest;
+pub mod metadata;
+pub mod results;
+pub mod run;
+pub mod scaffold;
+pub mod selftest;
+pub mod stats;
+#[cfg(feature = "tui")]
+pub mod tui;
+
+use crate::{
+    build::{build_benchmark, BuildContext},
+    metadata::{Benchmark, Runner},
+    run::{run_benchmark_on_runner, RunOptions, RunResult},
+};
+
+/// Builds a single benchmark under `builds_path` and runs it on a single runner.
+///
+/// This skips discovery and the rest of the orchestration, for driving one benchmark and runner
+/// pair directly. Use [`run_benchmark_on_runner`] instead to run an already built benchmark.
+pub fn run_one(
+    benchmark: &Benchmark,
+    runner: &Runner,
+    docker_executable: &Path,
+    builds_path: &Path,
+) -> Result<RunResult, Box<dyn error::Error>> {
+    let built_benchmark = build_benchmark(
+        benchmark,
+        &BuildContext::new(benchmark, docker_executable, builds_path, None),
+    )?;
+    run_benchmark_on_runner(&built_benchmark, runner, &RunOptions::default())
+}
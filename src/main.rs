@@ -1,43 +1,201 @@
-use std::{error, fs, path::PathBuf, process::exit};
-
-extern crate glob;
-
-use clap::Parser;
-use results::{print_results, record_results};
+use std::{
+    env, error, fs,
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+    process::exit,
+    sync::Arc,
+    thread,
+    time::Duration,
+};
 
-mod build;
-mod exec;
-mod metadata;
-mod results;
-mod run;
+use clap::{parser::ValueSource, ArgAction, ArgMatches, CommandFactory, FromArgMatches, Parser};
 
-use crate::{
-    build::build_benchmarks,
-    exec::validate_executable,
-    metadata::{find_benchmarks, find_runners, BenchmarkDefaults},
-    run::run_benchmarks_on_runners,
+use evm_bench::{
+    build::{
+        build_benchmarks, check_benchmarks, check_reproducible, find_built_benchmarks,
+        BuildBackend, BuildOptions,
+    },
+    events::EventSink,
+    exec::{
+        handle_interrupts, interrupted, validate_executable, CommandScript, CPU_QUOTA_SUPPORTED,
+        MEMORY_LIMIT_SUPPORTED,
+    },
+    governor::{warn_unless_performance_governor, PerformanceGovernor},
+    manifest::{ArtifactKind, Manifest, MANIFEST_FILE_NAME},
+    metadata::{
+        apply_overrides, find_benchmarks, find_runners, parse_hex, read_benchmark_manifest,
+        sweep_hardforks, BenchmarkDefaults, BenchmarkOverride, MetadataHash,
+    },
+    results::{
+        find_results_since, load_results, print_results, print_runner_diff, print_trend,
+        record_results, Format, FormatOutput, NormalizeBy, OnCollision, PrintOptions, Since,
+        SortRunnersBy,
+    },
+    run::{
+        assert_runners_ran, check_expected_outputs, check_missing_cells, check_num_runs,
+        check_output_consensus, check_run_inputs, prepare_runners, run_benchmarks_on_runners,
+        run_missing_on_runners, write_expected_outputs, RunOptions, BATCH_RUN_DIR,
+    },
+    scaffold::{new_benchmark, new_runner},
+    selftest::self_test,
 };
 
 /// Ethereum Virtual Machine Benchmark (evm-bench)
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// Path to use as the base for benchmarks searching
-    #[arg(long, default_value = "./benchmarks")]
-    benchmark_search_path: PathBuf,
+    /// Paths to use as the bases for benchmarks searching. Can be repeated or comma-separated to
+    /// search several, with benchmark names unique across all of them
+    #[arg(long, value_delimiter = ',', default_value = "./benchmarks")]
+    benchmark_search_path: Vec<PathBuf>,
+
+    /// Path to a manifest listing all benchmarks, used instead of searching for benchmark metadata
+    #[arg(long, default_value = None)]
+    benchmark_manifest: Option<PathBuf>,
 
     /// Names of benchmarks to run.
     #[arg(long, default_value = None)]
     benchmarks: Option<Vec<String>>,
 
-    /// Path to use as the base for runners searching
-    #[arg(short, long, default_value = "./runners")]
-    runner_search_path: PathBuf,
+    /// Paths to use as the bases for runners searching. Can be repeated or comma-separated to
+    /// search several, with runner names unique across all of them
+    #[arg(short, long, value_delimiter = ',', default_value = "./runners")]
+    runner_search_path: Vec<PathBuf>,
 
     /// Names of runners to use.
     #[arg(long, default_value = None)]
     runners: Option<Vec<String>>,
 
+    /// Hardforks to run each runner under, as a column per runner and hardfork named
+    /// `<runner>@<hardfork>`. Runners are skipped for hardforks they don't support
+    #[arg(long, value_delimiter = ',', default_value = None)]
+    hardforks: Option<Vec<String>>,
+
+    /// Names of runners that must produce results, failing otherwise. A runner swept across
+    /// `--hardforks` must produce results under each hardfork, unless named as `<runner>@<hardfork>`
+    #[arg(long, value_delimiter = ',', default_value = None)]
+    assert_runners: Option<Vec<String>>,
+
+    /// Fail after reporting if any benchmark lacks results from any runner, e.g. because it failed
+    /// to build or run, listing the missing pairs
+    #[arg(long)]
+    fail_on_missing_cells: bool,
+
+    /// Fail if runners disagree on the output of a benchmark, rather than only warning
+    #[arg(long)]
+    strict: bool,
+
+    /// Skip building and run against artifacts already in the output path from a previous build
+    #[arg(long, conflicts_with = "compile_check")]
+    no_build: bool,
+
+    /// Tool to build benchmarks with. With forge, the build context of each benchmark is built as
+    /// a Foundry project with its own remappings and compiler settings
+    #[arg(
+        long,
+        value_enum,
+        default_value_t,
+        conflicts_with_all = ["compile_check", "check_reproducible", "self_test"]
+    )]
+    build_backend: BuildBackend,
+
+    /// Only check that benchmarks compile, without producing artifacts or running them
+    #[arg(long)]
+    compile_check: bool,
+
+    /// Only check that building each benchmark twice produces identical bytecode
+    #[arg(long, conflicts_with_all = ["no_build", "compile_check"])]
+    check_reproducible: bool,
+
+    /// Only check that the environment works end to end, by building a tiny embedded benchmark,
+    /// running it on every runner, and reporting the results
+    #[arg(long, conflicts_with_all = ["compile_check", "check_reproducible"])]
+    self_test: bool,
+
+    /// Only print the mean time of a benchmark on a runner, as benchmark/runner, across all results
+    /// files under the output path, oldest first
+    #[arg(long, value_parser = parse_trend)]
+    trend: Option<(String, String)>,
+
+    /// Print the trend as CSV for plotting rather than as a table
+    #[arg(long, requires = "trend")]
+    trend_csv: bool,
+
+    /// Print the trend per git commit the results were recorded at rather than per results file,
+    /// skipping results without one
+    #[arg(long, requires = "trend")]
+    trend_by_commit: bool,
+
+    /// Also print a mean of each point's runs and those of earlier points, weighted to halve every
+    /// this many days older a point is, for a smoothed but current trend
+    #[arg(long, requires = "trend", value_parser = parse_half_life_days)]
+    trend_half_life_days: Option<f64>,
+
+    /// Only print how each benchmark's mean time on one runner compares to another's, with the
+    /// geometric mean of the ratios, from the latest results file or --diff-results
+    #[arg(long, num_args = 2, value_names = ["BASE", "OTHER"])]
+    diff_runners: Option<Vec<String>>,
+
+    /// Results file to compare runners from with --diff-runners
+    #[arg(long, value_name = "RESULTS_FILE", requires = "diff_runners")]
+    diff_results: Option<PathBuf>,
+
+    /// Git commit to record the results at, detected from the working tree (with a `-dirty`
+    /// suffix for uncommitted changes) if omitted
+    #[arg(long, default_value = None)]
+    git_rev: Option<String>,
+
+    /// Record each benchmark's output reported by runners to this file
+    #[arg(long, default_value = None)]
+    write_expected: Option<PathBuf>,
+
+    /// Fail if any benchmark's output differs from those recorded with --write-expected
+    #[arg(long, default_value = None)]
+    check_expected: Option<PathBuf>,
+
+    /// Browse results in an interactive terminal viewer, either those of this run once it is done,
+    /// or those of the given results file without running anything. Needs evm-bench to be built
+    /// with the tui feature
+    #[arg(long, value_name = "RESULTS_FILE", num_args = 0..=1)]
+    tui: Option<Option<PathBuf>>,
+
+    /// Create a new benchmark with this name under the first benchmark search path, with a
+    /// placeholder contract and valid metadata to fill in, without running anything
+    #[arg(long, value_name = "NAME")]
+    new_benchmark: Option<String>,
+
+    /// Create a new runner with this name under the first runner search path, with a placeholder
+    /// entry point and valid metadata to fill in, without running anything
+    #[arg(long, value_name = "NAME")]
+    new_runner: Option<String>,
+
+    /// Write logs as newline-delimited JSON objects with the time, level, target and message,
+    /// rather than in the human format
+    #[arg(long)]
+    json_logs: bool,
+
+    /// Print the value of every flag and whether it was given or left at its default as JSON, then
+    /// exit without doing anything else
+    #[arg(long)]
+    print_config: bool,
+
+    /// Write progress events as newline-delimited JSON to this file
+    #[arg(long, default_value = None, conflicts_with = "events_fd")]
+    events_file: Option<PathBuf>,
+
+    /// Write progress events as newline-delimited JSON to this already open file descriptor
+    #[arg(long, default_value = None)]
+    events_fd: Option<i32>,
+
+    /// Results file of a previous run to retry the failed or missing runs of, writing the merged
+    /// results back to it
+    #[arg(
+        long,
+        default_value = None,
+        conflicts_with_all = ["output_file_name", "overwrite", "append_suffix"]
+    )]
+    retry_from: Option<PathBuf>,
+
     /// Output path for build artifacts and other things
     #[arg(short, long, default_value = "./outputs")]
     output_path: PathBuf,
@@ -47,21 +205,163 @@ struct Args {
     #[arg(long, default_value = None)]
     output_file_name: Option<String>,
 
-    /// Path to a Docker executable (this is used for solc)
-    #[arg(long, default_value = "docker")]
-    docker_executable: PathBuf,
+    /// Write results files as MessagePack with a `.msgpack` extension rather than as JSON, which
+    /// is smaller and faster to load for large suites. Results files are read in either encoding,
+    /// by their extension
+    #[arg(long)]
+    binary_results: bool,
+
+    /// Overwrite the output file if it already exists
+    #[arg(long, conflicts_with = "append_suffix")]
+    overwrite: bool,
+
+    /// Add a numeric suffix to the output file name if it already exists
+    #[arg(long)]
+    append_suffix: bool,
+
+    /// Seconds to allow each benchmark build before killing it, unbounded if omitted
+    #[arg(long, default_value = None)]
+    build_timeout_secs: Option<u64>,
+
+    /// Number of benchmark and runner pairs to run at once. Pairs contend for the CPU when run at
+    /// once, so pass 1 for the most precise times
+    #[arg(long, default_value_t = default_jobs(), value_parser = clap::value_parser!(u64).range(1..))]
+    jobs: u64,
+
+    /// Invoke runners once per benchmark, even those that accept batches of benchmarks
+    #[arg(long)]
+    no_batch: bool,
+
+    /// Number of benchmarks to build at once
+    #[arg(long, default_value = "1", value_parser = clap::value_parser!(u64).range(1..))]
+    build_jobs: u64,
+
+    /// Number of solc containers to run at once across parallel builds, to avoid saturating the
+    /// Docker daemon
+    #[arg(long, default_value = "2", value_parser = clap::value_parser!(u64).range(1..))]
+    max_concurrent_docker: u64,
+
+    /// Number of times to try pulling each missing solc image before its builds are left to fail,
+    /// to ride out transient registry errors
+    #[arg(long, default_value = "3", value_parser = clap::value_parser!(u32).range(1..))]
+    pull_attempts: u32,
+
+    /// Seconds to wait before retrying a failed image pull, doubled after each further failure
+    #[arg(long, default_value = "2", value_parser = parse_secs)]
+    pull_backoff_secs: Duration,
+
+    /// Build benchmarks that compile the same contract with the same solc version and settings
+    /// only once, copying the artifact to the others
+    #[arg(long)]
+    dedup_builds: bool,
+
+    /// Name build artifacts after benchmarks rather than their contracts, so that benchmarks of
+    /// contracts with the same name can't be confused. Must match between building and --no-build
+    #[arg(long)]
+    contract_name_from_metadata_name: bool,
+
+    /// Label to record with the results, as key=value. Can be repeated.
+    #[arg(long = "label", value_parser = parse_label)]
+    labels: Vec<(String, String)>,
+
+    /// Number of runs to use for a specific runner, as runner=num_runs. Overrides both the runner
+    /// and benchmark metadata, and applies to each hardfork of a runner swept across `--hardforks`
+    /// unless given for one as `<runner>@<hardfork>=num_runs`. Can be repeated or comma-separated
+    #[arg(long, value_delimiter = ',', value_parser = parse_runner_num_runs)]
+    runner_num_runs: Vec<(String, u64)>,
+
+    /// Override a field of a discovered benchmark for this invocation only, as
+    /// benchmark.field=value (e.g. snailtracer.num-runs=3). Can be repeated
+    #[arg(long = "set", value_name = "BENCHMARK.FIELD=VALUE")]
+    overrides: Vec<BenchmarkOverride>,
+
+    /// Keep the logs and input files of every runner invocation under the output path, rather than
+    /// only those of failed ones
+    #[arg(long)]
+    save_logs: bool,
+
+    /// Write the commands run for each build, run and hook to this file as a shell script, so that
+    /// any single step can be reproduced by hand
+    #[arg(long, default_value = None)]
+    emit_commands: Option<PathBuf>,
+
+    /// Refuse to run if any benchmark would be run more times than this on a runner
+    #[arg(long, default_value = "1000")]
+    max_num_runs: u64,
+
+    /// Ask runners to also report contract deployment times, for runners that support it
+    #[arg(long)]
+    measure_deploy: bool,
+
+    /// Ask runners to also report how long analysing the contract's bytecode (e.g. finding jump
+    /// destinations) took, for runners that support it
+    #[arg(long)]
+    measure_analysis: bool,
+
+    /// Ask runners to also report the mean and standard deviation of their runs after their own
+    /// warmup, for runners that support it, to compare against the raw times of all runs
+    #[arg(long)]
+    steady_state: bool,
 
-    /// Path to a CPython executable (this is used for runners)
-    #[arg(long, default_value = "python3")]
-    cpython_executable: PathBuf,
+    /// Ask the runner with this name to write a flamegraph of the measured runs of each benchmark
+    /// under `<output path>/flamegraphs/<benchmark>/<runner>`, if it supports it. Its times include
+    /// the overhead of profiling
+    #[arg(long, default_value = None)]
+    flamegraph: Option<String>,
+
+    /// Ask runners to run each benchmark for at least this many milliseconds before its measured
+    /// runs, discarding the results, for runners that support it. Unlike a number of warmup runs,
+    /// this warms up JITs regardless of how long each run takes
+    #[arg(long, default_value = None, value_parser = clap::value_parser!(u64).range(1..))]
+    warmup_time_ms: Option<u64>,
+
+    /// Seconds to allow per run of a benchmark, multiplied by its number of runs to bound each
+    /// runner invocation. Unbounded if omitted
+    #[arg(long, default_value = None, value_parser = parse_secs)]
+    timeout_per_run_secs: Option<Duration>,
+
+    /// Seconds added to the scaled runner timeout to allow for runner startup (e.g. compilation)
+    #[arg(long, default_value = "300", value_parser = parse_secs)]
+    runner_startup_secs: Duration,
+
+    /// Set the CPU frequency scaling governor of every CPU to performance while running, restoring
+    /// it afterwards, so that results don't vary with CPU frequency. Linux only, and usually needs
+    /// root. Without it, a warning is logged if any CPU uses another governor
+    #[arg(long)]
+    performance_governor: bool,
+
+    /// Limit the address space of each runner invocation, in bytes with an optional K, M or G
+    /// suffix (e.g. 4G), so that a runaway runner fails its own runs rather than exhausting memory.
+    /// Only supported on Linux
+    #[arg(long, default_value = None, value_parser = parse_memory_limit)]
+    run_memory_limit: Option<u64>,
+
+    /// Limit each runner invocation to this percent of a CPU (e.g. 50 for half a core), as if in a
+    /// throttled container, by running it in a transient systemd scope with a CPU quota. Advanced,
+    /// only supported on Linux with systemd
+    #[arg(long, default_value = None, value_parser = clap::value_parser!(u32).range(1..))]
+    run_cpu_quota_pct: Option<u32>,
+
+    /// Path to a Docker executable (this is used for solc), searched for in PATH if omitted
+    #[arg(long)]
+    docker_executable: Option<PathBuf>,
+
+    /// Path to a forge executable (this is used by the forge build backend), searched for in PATH
+    /// if omitted
+    #[arg(long)]
+    forge_executable: Option<PathBuf>,
+
+    /// Path to a CPython executable (this is used for runners), searched for in PATH if omitted
+    #[arg(long)]
+    cpython_executable: Option<PathBuf>,
 
-    /// Path to a PyPy executable (this is used for runners)
-    #[arg(long, default_value = "pypy3")]
-    pypy_executable: PathBuf,
+    /// Path to a PyPy executable (this is used for runners), searched for in PATH if omitted
+    #[arg(long)]
+    pypy_executable: Option<PathBuf>,
 
-    /// Path to a NPM executable (this is used for runners)
-    #[arg(long, default_value = "npm")]
-    npm_executable: PathBuf,
+    /// Path to an NPM executable (this is used for runners), searched for in PATH if omitted
+    #[arg(long)]
+    npm_executable: Option<PathBuf>,
 
     /// Path to benchmark metadata schema
     #[arg(long, default_value = "./benchmarks/schema.json")]
@@ -83,41 +383,439 @@ struct Args {
     #[arg(long, default_value = "stable")]
     default_solc_version: String,
 
+    /// Hash of the contract metadata for solc to append to bytecode if none specified in the
+    /// benchmark metadata, solc's own default if omitted. `none` makes builds reproducible
+    #[arg(long, value_enum, default_value = None)]
+    solc_metadata_hash: Option<MetadataHash>,
+
     /// Default number of runs to use if none specified in the benchmark metadata
-    #[arg(long, default_value = "10")]
+    #[arg(long, default_value = "10", value_parser = clap::value_parser!(u64).range(1..))]
     default_num_runs: u64,
 
-    /// Default calldata to use if none specified in the benchmark metadata
+    /// Default calldata to use if none specified in the benchmark metadata, as hex with or without
+    /// 0x. Benchmarks that set empty calldata are called with no input regardless
     #[arg(long, default_value = "")]
     default_calldata_str: String,
+
+    /// Formats to output results in, as <format> to print to stdout or <format>=<path> to write
+    /// to a file. Can be repeated or comma-separated to output several formats at once
+    #[arg(long, value_delimiter = ',', default_value = "markdown")]
+    format: Vec<FormatOutput>,
+
+    /// Results file to compare against, used by the github-markdown format
+    #[arg(long, default_value = None, conflicts_with = "since")]
+    baseline: Option<PathBuf>,
+
+    /// Compare against an earlier results file in the same directory instead of --baseline,
+    /// either the `last` one or the most recent one at or before an RFC 3339 time
+    #[arg(long, default_value = None)]
+    since: Option<Since>,
+
+    /// Percentage slowdown against the baseline that counts as a regression
+    #[arg(long, default_value = "5")]
+    regression_threshold_pct: f64,
+
+    /// Rather than --regression-threshold-pct, flag changes against the baseline whose run times
+    /// differ significantly at this level under a Mann-Whitney U test, reporting their p-values
+    #[arg(long, default_value = None, value_parser = parse_significance_alpha)]
+    significance_alpha: Option<f64>,
+
+    /// Only print the summary rows of the results table, omitting per-benchmark rows
+    #[arg(long)]
+    only_summary: bool,
+
+    /// Also report run times normalized by this, e.g. nanoseconds per opcode for runners that
+    /// report opcode counts
+    #[arg(long, value_enum, default_value = None)]
+    normalize_by: Option<NormalizeBy>,
+
+    /// Statistic of each runner's mean benchmark times that orders the runner columns of the
+    /// results table, fastest first
+    #[arg(long, value_enum, default_value_t)]
+    sort_runners_by: SortRunnersBy,
+
+    /// Fraction of each benchmark's leading runs to drop as warmup when reporting, e.g. 0.1 for
+    /// the first 10%. The results file keeps all runs
+    #[arg(long, default_value = "0", value_parser = parse_trim_fraction)]
+    trim_fraction: f64,
+
+    /// Print tables with runners as rows and benchmarks as columns
+    #[arg(long)]
+    transpose: bool,
+
+    /// Show the coefficient of variation (standard deviation relative to the mean) of each
+    /// benchmark's run times next to its mean
+    #[arg(long)]
+    show_cv: bool,
+
+    /// Warn about benchmarks whose run times have a coefficient of variation above this
+    /// percentage, as too noisy to trust
+    #[arg(long = "warn-cv", value_name = "PCT")]
+    warn_cv_pct: Option<f64>,
+
+    /// Also tabulate the mean of this runner-reported metric for each benchmark. Can be repeated
+    /// or comma-separated
+    #[arg(long = "metric", value_delimiter = ',')]
+    metrics: Vec<String>,
+
+    /// Number of decimals of rendered times and relative times, rather than all digits of times
+    /// and 3 decimals of relative times
+    #[arg(long)]
+    precision: Option<usize>,
+
+    /// Render a histogram sparkline of each benchmark's run times next to its mean
+    #[arg(long)]
+    sparklines: bool,
+}
+
+/// Logs in the human format, or as one JSON object per line for log aggregators if `json` is set.
+/// Either way, `RUST_LOG` controls which logs are written.
+fn init_logger(json: bool) {
+    let mut builder = env_logger::Builder::from_default_env();
+    if json {
+        builder.format(|buf, record| {
+            writeln!(
+                buf,
+                "{}",
+                serde_json::json!({
+                    "time": chrono::offset::Utc::now().to_rfc3339(),
+                    "level": record.level().as_str(),
+                    "target": record.target(),
+                    "message": record.args().to_string(),
+                })
+            )
+        });
+    }
+    builder.init();
+}
+
+fn parse_label(label: &str) -> Result<(String, String), String> {
+    let (key, value) = label
+        .split_once('=')
+        .ok_or(format!("label {label} is not of the form key=value"))?;
+    if key.is_empty() {
+        return Err(format!("label {label} has an empty key"));
+    }
+    Ok((key.to_string(), value.to_string()))
+}
+
+fn parse_runner_num_runs(arg: &str) -> Result<(String, u64), String> {
+    let (runner, num_runs) = arg
+        .split_once('=')
+        .ok_or(format!("{arg} is not of the form runner=num_runs"))?;
+    let num_runs = num_runs
+        .parse()
+        .map_err(|e| format!("could not parse number of runs in {arg}: {e}"))?;
+    if num_runs == 0 {
+        return Err(format!("number of runs in {arg} must be at least 1"));
+    }
+    Ok((runner.to_string(), num_runs))
+}
+
+/// Number of logical cores, to run that many benchmark and runner pairs at once by default.
+fn default_jobs() -> u64 {
+    thread::available_parallelism().map_or(1, |jobs| jobs.get() as u64)
+}
+
+fn parse_trend(arg: &str) -> Result<(String, String), String> {
+    let (benchmark, runner) = arg
+        .split_once('/')
+        .ok_or(format!("{arg} is not of the form benchmark/runner"))?;
+    Ok((benchmark.to_string(), runner.to_string()))
+}
+
+/// Parses a non-negative, finite number of seconds.
+fn parse_secs(arg: &str) -> Result<Duration, String> {
+    let secs = arg
+        .parse::<f64>()
+        .map_err(|e| format!("could not parse seconds {arg}: {e}"))?;
+    Duration::try_from_secs_f64(secs)
+        .map_err(|_| format!("{arg} is not a non-negative, finite number of seconds"))
+}
+
+fn parse_trim_fraction(arg: &str) -> Result<f64, String> {
+    let fraction = arg
+        .parse::<f64>()
+        .map_err(|e| format!("could not parse trim fraction {arg}: {e}"))?;
+    if (0.0..1.0).contains(&fraction) {
+        Ok(fraction)
+    } else {
+        Err(format!("trim fraction {arg} is not in [0, 1)"))
+    }
+}
+
+fn parse_half_life_days(arg: &str) -> Result<f64, String> {
+    let days = arg
+        .parse::<f64>()
+        .map_err(|e| format!("could not parse half-life {arg}: {e}"))?;
+    if days > 0.0 && days.is_finite() {
+        Ok(days)
+    } else {
+        Err(format!("half-life {arg} is not a positive number of days"))
+    }
+}
+
+fn parse_significance_alpha(arg: &str) -> Result<f64, String> {
+    let alpha = arg
+        .parse::<f64>()
+        .map_err(|e| format!("could not parse significance level {arg}: {e}"))?;
+    if alpha > 0.0 && alpha < 1.0 {
+        Ok(alpha)
+    } else {
+        Err(format!("significance level {arg} is not in (0, 1)"))
+    }
+}
+
+fn parse_memory_limit(arg: &str) -> Result<u64, String> {
+    let (number, scale) = match arg.char_indices().last() {
+        Some((i, 'K' | 'k')) => (&arg[..i], 1 << 10),
+        Some((i, 'M' | 'm')) => (&arg[..i], 1 << 20),
+        Some((i, 'G' | 'g')) => (&arg[..i], 1 << 30),
+        _ => (arg, 1),
+    };
+    let bytes = number
+        .parse::<u64>()
+        .ok()
+        .and_then(|number| number.checked_mul(scale))
+        .ok_or(format!("could not parse memory limit {arg}"))?;
+    if bytes == 0 {
+        return Err(format!("memory limit {arg} must be positive"));
+    }
+    Ok(bytes)
+}
+
+/// Canonicalizes a path given through `flag`, naming the flag and the working directory that
+/// relative paths are resolved against if it can't be found.
+fn canonicalize_flag(flag: &str, path: &Path) -> Result<PathBuf, Box<dyn error::Error>> {
+    path.canonicalize().map_err(|e| {
+        let cwd = env::current_dir()
+            .map(|cwd| cwd.display().to_string())
+            .unwrap_or_else(|_| "an unknown directory".to_string());
+        format!(
+            "could not find {} given by --{flag}: {e} (relative paths are resolved from {cwd})",
+            path.display()
+        )
+        .into()
+    })
+}
+
+/// Canonicalizes each of the paths given through a repeatable `flag`.
+fn canonicalize_flags(
+    flag: &str,
+    paths: &[PathBuf],
+) -> Result<Vec<PathBuf>, Box<dyn error::Error>> {
+    paths
+        .iter()
+        .map(|path| canonicalize_flag(flag, path))
+        .collect()
+}
+
+#[cfg(feature = "tui")]
+fn browse_results(results_file_path: &Path) -> Result<(), Box<dyn error::Error>> {
+    evm_bench::tui::browse_results(results_file_path)
+}
+
+#[cfg(not(feature = "tui"))]
+const TUI_UNAVAILABLE: &str =
+    "--tui needs evm-bench to be built with the tui feature (cargo build --features tui)";
+
+#[cfg(not(feature = "tui"))]
+fn browse_results(_: &Path) -> Result<(), Box<dyn error::Error>> {
+    Err(TUI_UNAVAILABLE.into())
+}
+
+/// Errors if the terminal viewer can't be shown, so that `--tui` fails before rather than after a
+/// run.
+#[cfg(feature = "tui")]
+fn check_tui_available() -> Result<(), Box<dyn error::Error>> {
+    Ok(())
+}
+
+#[cfg(not(feature = "tui"))]
+fn check_tui_available() -> Result<(), Box<dyn error::Error>> {
+    Err(TUI_UNAVAILABLE.into())
+}
+
+/// Values of every flag as parsed from `matches`, with where each came from, keyed by flag name.
+/// Flags that take several values are arrays, switches are booleans, and unset optional flags are
+/// null.
+fn resolved_config(matches: &ArgMatches) -> serde_json::Value {
+    let mut config = serde_json::Map::new();
+    for arg in Args::command().get_arguments() {
+        let id = arg.get_id().as_str();
+        let mut values = matches
+            .get_raw(id)
+            .into_iter()
+            .flatten()
+            .map(|value| serde_json::Value::from(value.to_string_lossy()));
+        let value = match arg.get_action() {
+            ArgAction::SetTrue => matches.get_flag(id).into(),
+            ArgAction::Append => values.collect(),
+            _ => values.next().unwrap_or_default(),
+        };
+        let source = match matches.value_source(id) {
+            Some(ValueSource::CommandLine) => "command line",
+            Some(ValueSource::EnvVariable) => "environment",
+            Some(ValueSource::DefaultValue) => "default",
+            Some(_) => "other",
+            None => "unset",
+        };
+        config.insert(
+            arg.get_long().unwrap_or(id).to_string(),
+            serde_json::json!({ "value": value, "source": source }),
+        );
+    }
+    config.into()
 }
 
 fn main() {
-    env_logger::init();
+    let matches = Args::command().get_matches();
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    if args.print_config {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&resolved_config(&matches)).unwrap()
+        );
+        return;
+    }
 
-    let args = Args::parse();
+    init_logger(args.json_logs);
 
     (|| -> Result<(), Box<dyn error::Error>> {
-        let docker_executable = validate_executable("docker", &args.docker_executable)?;
-        let _ = validate_executable("cargo", &PathBuf::from("cargo"))?;
-        let _ = validate_executable("poetry", &PathBuf::from("poetry"))?;
-        let _ = validate_executable("python3", &PathBuf::from(args.cpython_executable))?;
-        let _ = validate_executable("pypy3", &PathBuf::from(args.pypy_executable))?;
-        let _ = validate_executable("npm", &PathBuf::from(args.npm_executable))?;
-
-        let default_calldata = hex::decode(args.default_calldata_str.to_string())?;
-
-        let benchmarks_path = args.benchmark_search_path.canonicalize()?;
-        let benchmarks = find_benchmarks(
-            &args.benchmark_metadata_name,
-            &args.benchmark_metadata_schema,
-            &benchmarks_path,
-            BenchmarkDefaults {
-                solc_version: args.default_solc_version,
-                num_runs: args.default_num_runs,
-                calldata: default_calldata,
-            },
+        handle_interrupts()?;
+        if args.tui.is_some() {
+            check_tui_available()?;
+        }
+
+        if let Some((benchmark_name, runner_name)) = &args.trend {
+            return print_trend(
+                &args.output_path.join("results"),
+                benchmark_name,
+                runner_name,
+                args.trend_csv,
+                args.trim_fraction,
+                args.trend_by_commit,
+                args.trend_half_life_days
+                    .map(|days| Duration::from_secs_f64(days * 24.0 * 60.0 * 60.0)),
+            );
+        }
+
+        if let Some(diff_runners) = &args.diff_runners {
+            let results_file_path = match &args.diff_results {
+                Some(results_file_path) => results_file_path.clone(),
+                None => find_results_since(
+                    &args.output_path.join("results"),
+                    Since::Last,
+                    Path::new(""),
+                )?
+                .ok_or("found no results to compare runners in")?,
+            };
+            return print_runner_diff(
+                &results_file_path,
+                &diff_runners[0],
+                &diff_runners[1],
+                &PrintOptions {
+                    trim_fraction: args.trim_fraction,
+                    precision: args.precision,
+                    transpose: args.transpose,
+                    ..Default::default()
+                },
+            );
+        }
+
+        if let Some(Some(results_file_path)) = &args.tui {
+            return browse_results(results_file_path);
+        }
+
+        if args.new_benchmark.is_some() || args.new_runner.is_some() {
+            if let Some(name) = &args.new_benchmark {
+                let dir = new_benchmark(
+                    args.benchmark_search_path
+                        .first()
+                        .ok_or("no benchmark search path to create the benchmark in")?,
+                    name,
+                    &args.benchmark_metadata_name,
+                    &args.benchmark_metadata_schema,
+                    &BenchmarkDefaults {
+                        solc_version: args.default_solc_version.clone(),
+                        num_runs: args.default_num_runs,
+                        calldata: Vec::new(),
+                        metadata_hash: args.solc_metadata_hash,
+                    },
+                )?;
+                println!("created benchmark {name} in {}", dir.display());
+            }
+            if let Some(name) = &args.new_runner {
+                let dir = new_runner(
+                    args.runner_search_path
+                        .first()
+                        .ok_or("no runner search path to create the runner in")?,
+                    name,
+                    &args.runner_metadata_name,
+                    &args.runner_metadata_schema,
+                )?;
+                println!("created runner {name} in {}", dir.display());
+            }
+            return Ok(());
+        }
+
+        // Docker, or forge with the forge backend, is only used to build benchmarks.
+        let build_executable = if args.no_build {
+            None
+        } else {
+            Some(match args.build_backend {
+                BuildBackend::SolcDocker => validate_executable(
+                    "docker",
+                    Some("docker-executable"),
+                    args.docker_executable.as_deref(),
+                )?,
+                BuildBackend::Forge => validate_executable(
+                    "forge",
+                    Some("forge-executable"),
+                    args.forge_executable.as_deref(),
+                )?,
+            })
+        };
+        let _ = validate_executable("cargo", None, None)?;
+        let _ = validate_executable("poetry", None, None)?;
+        let _ = validate_executable(
+            "python3",
+            Some("cpython-executable"),
+            args.cpython_executable.as_deref(),
+        )?;
+        let _ = validate_executable(
+            "pypy3",
+            Some("pypy-executable"),
+            args.pypy_executable.as_deref(),
         )?;
+        let _ = validate_executable(
+            "npm",
+            Some("npm-executable"),
+            args.npm_executable.as_deref(),
+        )?;
+
+        let default_calldata = parse_hex(&args.default_calldata_str, "default calldata")?;
+
+        let benchmark_defaults = BenchmarkDefaults {
+            solc_version: args.default_solc_version.clone(),
+            num_runs: args.default_num_runs,
+            calldata: default_calldata,
+            metadata_hash: args.solc_metadata_hash,
+        };
+        let mut benchmarks = match args.benchmark_manifest {
+            Some(manifest_path) => read_benchmark_manifest(
+                &args.benchmark_metadata_schema,
+                &canonicalize_flag("benchmark-manifest", &manifest_path)?,
+                benchmark_defaults,
+            )?,
+            None => find_benchmarks(
+                &args.benchmark_metadata_name,
+                &args.benchmark_metadata_schema,
+                &canonicalize_flags("benchmark-search-path", &args.benchmark_search_path)?,
+                benchmark_defaults,
+            )?,
+        };
+        apply_overrides(&mut benchmarks, &args.overrides)?;
         let mut benchmarks = match args.benchmarks {
             None => benchmarks,
             Some(arg_benchmarks) => benchmarks
@@ -127,11 +825,56 @@ fn main() {
         };
         benchmarks.sort_by_key(|b| b.name.clone());
 
-        let runners_path = args.runner_search_path.canonicalize()?;
+        let commands = args
+            .emit_commands
+            .as_deref()
+            .map(CommandScript::create)
+            .transpose()?
+            .map(Arc::new);
+        let build_options = BuildOptions {
+            timeout: args.build_timeout_secs.map(Duration::from_secs),
+            jobs: args.build_jobs as usize,
+            max_concurrent_docker: args.max_concurrent_docker as usize,
+            artifacts_by_benchmark_name: args.contract_name_from_metadata_name,
+            commands: commands.clone(),
+            backend: args.build_backend,
+            pull_attempts: args.pull_attempts,
+            pull_backoff: args.pull_backoff_secs,
+            dedup_builds: args.dedup_builds,
+        };
+
+        if args.compile_check {
+            return check_benchmarks(
+                &benchmarks,
+                build_executable
+                    .as_deref()
+                    .ok_or("docker is required to check benchmarks")?,
+                &args.output_path.join("build"),
+                &build_options,
+            );
+        }
+
+        if args.check_reproducible {
+            return check_reproducible(
+                &benchmarks,
+                build_executable
+                    .as_deref()
+                    .ok_or("docker is required to check benchmarks")?,
+                &args.output_path.join("build"),
+                args.build_timeout_secs.map(Duration::from_secs),
+            );
+        }
+
+        let events = match (&args.events_file, args.events_fd) {
+            (Some(path), _) => EventSink::to_file(path)?,
+            (None, Some(fd)) => EventSink::to_fd(fd)?,
+            (None, None) => EventSink::disabled(),
+        };
+
         let runners = find_runners(
             &args.runner_metadata_name,
             &args.runner_metadata_schema,
-            &runners_path,
+            &canonicalize_flags("runner-search-path", &args.runner_search_path)?,
             (),
         )?;
         let mut runners = match args.runners {
@@ -141,21 +884,266 @@ fn main() {
                 .filter(|r| arg_runners.contains(&r.name))
                 .collect(),
         };
+        if let Some(hardforks) = &args.hardforks {
+            runners = sweep_hardforks(runners, hardforks);
+        }
         runners.sort_by_key(|b| b.name.clone());
+        if let Some(runner_name) = &args.flamegraph {
+            if !runners.iter().any(|runner| runner.name == *runner_name) {
+                return Err(
+                    format!("no runner named {runner_name} to write flamegraphs of").into(),
+                );
+            }
+        }
 
-        fs::create_dir_all(&args.output_path)?;
-        let outputs_path = args.output_path.canonicalize()?;
+        fs::create_dir_all(&args.output_path).map_err(|e| {
+            format!(
+                "could not create {} given by --output-path: {e}",
+                args.output_path.display()
+            )
+        })?;
+        let outputs_path = canonicalize_flag("output-path", &args.output_path)?;
+
+        if args.run_memory_limit.is_some() && !MEMORY_LIMIT_SUPPORTED {
+            log::warn!("--run-memory-limit is not supported on this platform, ignoring it");
+        }
+        if args.run_cpu_quota_pct.is_some() {
+            if CPU_QUOTA_SUPPORTED {
+                let _ = validate_executable("systemd-run", None, None)?;
+            } else {
+                log::warn!("--run-cpu-quota-pct is not supported on this platform, ignoring it");
+            }
+        }
+        let run_options = RunOptions {
+            timeout_per_run: args.timeout_per_run_secs,
+            startup_allowance: args.runner_startup_secs,
+            runner_num_runs: args.runner_num_runs.into_iter().collect(),
+            measure_deploy: args.measure_deploy,
+            measure_analysis: args.measure_analysis,
+            // Gas is only reported when asked for, as not every runner supports it.
+            report_gas: args
+                .format
+                .iter()
+                .any(|output| output.format == Format::GasSnapshot),
+            // Counting opcodes costs runners an extra run, so they only do when it's reported.
+            count_opcodes: args.normalize_by == Some(NormalizeBy::Opcode),
+            steady_state: args.steady_state,
+            warmup_time: args.warmup_time_ms.map(Duration::from_millis),
+            flamegraph: args
+                .flamegraph
+                .map(|runner_name| (runner_name, outputs_path.join("flamegraphs"))),
+            run_files_path: Some(outputs_path.join("runs")),
+            save_logs: args.save_logs,
+            memory_limit: args.run_memory_limit.filter(|_| MEMORY_LIMIT_SUPPORTED),
+            cpu_quota_pct: args.run_cpu_quota_pct.filter(|_| CPU_QUOTA_SUPPORTED),
+            commands,
+            jobs: args.jobs as usize,
+            no_batch: args.no_batch,
+        };
+        if args.self_test {
+            return self_test(
+                &runners,
+                build_executable.as_deref(),
+                &outputs_path.join("self-test"),
+                &args.default_solc_version,
+                &run_options,
+            );
+        }
+        check_num_runs(&benchmarks, &runners, &run_options, args.max_num_runs)?;
 
         let builds_path = outputs_path.join("build");
         fs::create_dir_all(&builds_path)?;
-        let built_benchmarks = build_benchmarks(&benchmarks, &docker_executable, &builds_path)?;
+        let built_benchmarks = match &build_executable {
+            None => find_built_benchmarks(&benchmarks, &builds_path, &build_options)?,
+            Some(build_executable) => build_benchmarks(
+                &benchmarks,
+                build_executable,
+                &builds_path,
+                &build_options,
+                &events,
+            )?,
+        };
+        if interrupted() {
+            return Err("interrupted while building, nothing was run".into());
+        }
+        let runners = prepare_runners(runners, run_options.commands.as_deref());
+
+        let governor = if args.performance_governor {
+            Some(PerformanceGovernor::set()?)
+        } else {
+            warn_unless_performance_governor();
+            None
+        };
+        let (results, labels) = match &args.retry_from {
+            None => (
+                run_benchmarks_on_runners(&built_benchmarks, &runners, &run_options, &events)?,
+                args.labels.into_iter().collect(),
+            ),
+            Some(retry_from) => {
+                let (mut labels, previous) = load_results(retry_from)?;
+                labels.extend(args.labels);
+                (
+                    run_missing_on_runners(
+                        &built_benchmarks,
+                        &runners,
+                        &run_options,
+                        &events,
+                        previous,
+                    )?,
+                    labels,
+                )
+            }
+        };
+        // Restored as soon as running is done.
+        drop(governor);
+        let consensus = check_output_consensus(&results, args.strict);
+
+        let result_file_path = match &args.retry_from {
+            None => {
+                let results_path = outputs_path.join("results");
+                fs::create_dir_all(&results_path)?;
+                record_results(
+                    &results_path,
+                    args.output_file_name,
+                    match (args.overwrite, args.append_suffix) {
+                        (true, _) => OnCollision::Overwrite,
+                        (_, true) => OnCollision::AppendSuffix,
+                        _ => OnCollision::Error,
+                    },
+                    labels,
+                    args.git_rev.clone(),
+                    args.binary_results,
+                    &results,
+                )?
+            }
+            Some(retry_from) => record_results(
+                retry_from
+                    .parent()
+                    .ok_or("could not get parent of --retry-from")?,
+                retry_from
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string()),
+                OnCollision::Overwrite,
+                labels,
+                args.git_rev.clone(),
+                args.binary_results,
+                &results,
+            )?,
+        };
+        let baseline = match args.since {
+            None => args.baseline,
+            Some(since) => {
+                let baseline = find_results_since(
+                    result_file_path
+                        .parent()
+                        .ok_or("could not get results directory")?,
+                    since,
+                    &result_file_path,
+                )?;
+                match &baseline {
+                    Some(baseline) => log::info!("comparing against {}", baseline.display()),
+                    None => log::warn!("found no earlier results to compare against"),
+                }
+                baseline
+            }
+        };
+        let report_paths = args
+            .format
+            .iter()
+            .filter_map(|output| output.path.clone())
+            .collect::<Vec<_>>();
+        print_results(
+            &result_file_path,
+            &PrintOptions {
+                outputs: args.format,
+                sparklines: args.sparklines,
+                only_summary: args.only_summary,
+                baseline,
+                regression_threshold_pct: args.regression_threshold_pct,
+                significance_alpha: args.significance_alpha,
+                normalize_by: args.normalize_by,
+                // Only color output to a terminal, see https://no-color.org.
+                color: io::stdout().is_terminal()
+                    && env::var_os("NO_COLOR").unwrap_or_default().is_empty(),
+                transpose: args.transpose,
+                trim_fraction: args.trim_fraction,
+                show_cv: args.show_cv,
+                warn_cv_pct: args.warn_cv_pct,
+                metrics: args.metrics,
+                precision: args.precision,
+                sort_runners_by: args.sort_runners_by,
+            },
+        )?;
+
+        let mut manifest = Manifest::new(&result_file_path)?;
+        for path in &report_paths {
+            manifest.add(ArtifactKind::Report, path);
+        }
+        for built_benchmark in &built_benchmarks {
+            manifest.add(
+                ArtifactKind::Bytecode,
+                &built_benchmark.result.contract_bin_path,
+            );
+        }
+        // Only the run files of this run, not those kept from earlier ones.
+        let runs_path = outputs_path.join("runs");
+        for runner in &runners {
+            manifest.add_dir(
+                ArtifactKind::Log,
+                &runs_path.join(BATCH_RUN_DIR).join(&runner.name),
+            );
+            for built_benchmark in &built_benchmarks {
+                manifest.add_dir(
+                    ArtifactKind::Log,
+                    &runs_path
+                        .join(&built_benchmark.benchmark.name)
+                        .join(&runner.name),
+                );
+            }
+        }
+        if let Some((runner_name, flamegraphs_path)) = &run_options.flamegraph {
+            for built_benchmark in &built_benchmarks {
+                manifest.add_dir(
+                    ArtifactKind::Flamegraph,
+                    &flamegraphs_path
+                        .join(&built_benchmark.benchmark.name)
+                        .join(runner_name),
+                );
+            }
+        }
+        if let Some(path) = &args.emit_commands {
+            manifest.add(ArtifactKind::Commands, path);
+        }
+        if let Some(path) = &args.events_file {
+            manifest.add(ArtifactKind::Events, path);
+        }
+        manifest.write(&outputs_path.join(MANIFEST_FILE_NAME))?;
 
-        let results = run_benchmarks_on_runners(&built_benchmarks, &runners)?;
+        if interrupted() {
+            return Err(format!(
+                "interrupted, partial results were saved to {}",
+                result_file_path.display()
+            )
+            .into());
+        }
+        if let Some(None) = args.tui {
+            browse_results(&result_file_path)?;
+        }
 
-        let results_path = outputs_path.join("results");
-        fs::create_dir_all(&results_path)?;
-        let result_file_path = record_results(&results_path, args.output_file_name, &results)?;
-        print_results(&result_file_path)?;
+        check_run_inputs(&results)?;
+        consensus?;
+        if let Some(path) = &args.write_expected {
+            write_expected_outputs(path, &results)?;
+        }
+        if let Some(path) = &args.check_expected {
+            check_expected_outputs(path, &results)?;
+        }
+        if let Some(assert_runners) = args.assert_runners {
+            assert_runners_ran(&results, &runners, &assert_runners)?;
+        }
+        if args.fail_on_missing_cells {
+            check_missing_cells(&benchmarks, &runners, &results)?;
+        }
 
         Ok(())
     })()
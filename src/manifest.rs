@@ -0,0 +1,104 @@
+use std::{
+    error, fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+/// Name of the manifest file written to the output path after a run.
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+/// Kind of a file produced by a run.
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ArtifactKind {
+    /// The results file of the run.
+    Results,
+    /// Results written in a `--format` to a file.
+    Report,
+    /// Built bytecode of a benchmark.
+    Bytecode,
+    /// Files of a runner invocation kept with `--save-logs` or on failure.
+    Log,
+    /// Flamegraph of a benchmark, from `--flamegraph`.
+    Flamegraph,
+    /// Shell script of the commands run, from `--emit-commands`.
+    Commands,
+    /// Progress events, from `--events-file`.
+    Events,
+}
+
+#[derive(Debug, Serialize)]
+struct Artifact {
+    kind: ArtifactKind,
+    path: PathBuf,
+    size: u64,
+}
+
+/// Files produced by a run, written as JSON so that CI can collect them without globbing.
+#[derive(Debug, Serialize)]
+pub struct Manifest {
+    /// The results file, the primary artifact of the run.
+    results: Artifact,
+    artifacts: Vec<Artifact>,
+}
+
+/// Describes the file at `path` as an artifact, or `None` if it doesn't exist.
+fn artifact(kind: ArtifactKind, path: &Path) -> Option<Artifact> {
+    let path = path.canonicalize().ok()?;
+    let metadata = fs::metadata(&path)
+        .ok()
+        .filter(|metadata| metadata.is_file())?;
+    Some(Artifact {
+        kind,
+        path,
+        size: metadata.len(),
+    })
+}
+
+impl Manifest {
+    pub fn new(results_file_path: &Path) -> Result<Self, Box<dyn error::Error>> {
+        Ok(Self {
+            results: artifact(ArtifactKind::Results, results_file_path).ok_or_else(|| {
+                format!(
+                    "could not find results file {}",
+                    results_file_path.display()
+                )
+            })?,
+            artifacts: Vec::new(),
+        })
+    }
+
+    /// Lists the file at `path`, skipping it if it doesn't exist, e.g. because it was never
+    /// written.
+    pub fn add(&mut self, kind: ArtifactKind, path: &Path) {
+        match artifact(kind, path) {
+            Some(artifact) => self.artifacts.push(artifact),
+            None => log::debug!("not listing missing artifact {}", path.display()),
+        }
+    }
+
+    /// Lists every file under the directory at `path`, if there is one, in order of their paths.
+    pub fn add_dir(&mut self, kind: ArtifactKind, path: &Path) {
+        let pattern = path.join("**").join("*");
+        let paths = glob::glob(&pattern.to_string_lossy())
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|path| path.is_file());
+        for path in paths {
+            self.add(kind, &path);
+        }
+    }
+
+    /// Writes the manifest as JSON to `path`.
+    pub fn write(&self, path: &Path) -> Result<(), Box<dyn error::Error>> {
+        fs::write(path, serde_json::to_string_pretty(self)? + "\n")?;
+        log::info!(
+            "listed {} artifacts in {}",
+            self.artifacts.len() + 1,
+            path.display()
+        );
+        Ok(())
+    }
+}
@@ -1,10 +1,11 @@
 use std::{
-    collections::HashSet,
+    collections::{BTreeMap, HashSet},
     error, fs,
     path::{Path, PathBuf},
 };
 
 use glob::glob;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use serde::{Deserialize, Serialize};
 
 pub trait MetadataParser
@@ -60,15 +61,271 @@ pub struct Benchmark {
     pub name: String,
     pub solc_version: String,
     pub num_runs: u64,
+    /// Weight of the benchmark in summaries of all benchmarks.
+    #[serde(default)]
+    pub weight: Weight,
     pub contract: PathBuf,
+    /// Name of the contract to benchmark, if different from the contract file name.
+    #[serde(default)]
+    pub contract_name: Option<String>,
     pub build_context: PathBuf,
     pub calldata: Vec<u8>,
+    #[serde(default)]
+    pub access_list: Vec<AccessListItem>,
+    /// Storage of the benchmark contract to set before each run, as hex slot to hex value.
+    #[serde(default)]
+    pub storage: BTreeMap<String, String>,
+    /// Gas each run should consume, by calling the contract repeatedly, if benchmarking throughput
+    /// rather than single calls.
+    #[serde(default)]
+    pub gas_budget: Option<u64>,
+    /// JSON-RPC endpoint of a chain to fork, so that the benchmark runs against its state.
+    #[serde(default)]
+    pub fork_url: Option<String>,
+    /// Block of the forked chain whose state to run against, required with `fork_url`.
+    #[serde(default)]
+    pub fork_block: Option<u64>,
+    /// Block environment to run the benchmark in, the runner's defaults for unset fields.
+    #[serde(default)]
+    pub block: BlockEnv,
+    /// Hex address that deploys the benchmark contract, the runner's default caller if `None`.
+    #[serde(default)]
+    pub deployer: Option<String>,
+    /// Nonce of the deployer when deploying the benchmark contract, 0 if `None`.
+    #[serde(default)]
+    pub deployer_nonce: Option<u64>,
+    /// solc standard-JSON `settings` as a JSON string, compiling with `--standard-json` if set.
+    #[serde(default)]
+    pub solc_settings: Option<String>,
+    /// Calls to make in order against the same state in each run, instead of the single call with
+    /// `calldata`.
+    #[serde(default)]
+    pub calls: Vec<Call>,
+    /// Index of the call in `calls` to time, timing the whole sequence if `None`.
+    #[serde(default)]
+    pub measure_step: Option<usize>,
+    /// Shell command to run once before running the benchmark on any runner.
+    #[serde(default)]
+    pub setup_cmd: Option<String>,
+    /// Shell command to run once after running the benchmark on all runners.
+    #[serde(default)]
+    pub teardown_cmd: Option<String>,
+    /// Hardforks the benchmark can run under, e.g. because it relies on opcodes introduced by one,
+    /// any if `None`.
+    #[serde(default)]
+    pub hardforks: Option<Vec<String>>,
+    /// Hash of the contract metadata that solc appends to the bytecode, solc's default if `None`.
+    #[serde(default)]
+    pub metadata_hash: Option<MetadataHash>,
+    /// Number of the fastest of each runner's runs to report, all of them if `None`.
+    #[serde(default)]
+    pub report_runs: Option<u64>,
+    /// Gas each run should use, failing runners that report otherwise, unchecked if `None`.
+    #[serde(default)]
+    pub expected_gas: Option<u64>,
+    /// Gas that runs may use more or less than `expected_gas`, none if `None`.
+    #[serde(default)]
+    pub expected_gas_tolerance: Option<u64>,
+}
+
+/// Hash of the contract metadata that solc appends to bytecode, as passed to its
+/// `--metadata-hash`. Any hash makes bytecode depend on more than the compiled sources, such as
+/// comments and file paths, so only `none` reliably builds the same bytecode from the same code.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MetadataHash {
+    Ipfs,
+    Bzzr1,
+    /// Append no hash, only the compiler version.
+    None,
+}
+
+impl MetadataHash {
+    /// Oldest solc version with `--metadata-hash`.
+    const MIN_SOLC_VERSION: (u64, u64, u64) = (0, 6, 0);
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MetadataHash::Ipfs => "ipfs",
+            MetadataHash::Bzzr1 => "bzzr1",
+            MetadataHash::None => "none",
+        }
+    }
+}
+
+impl std::str::FromStr for MetadataHash {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        clap::ValueEnum::from_str(s, false)
+            .map_err(|_| format!("unknown metadata hash {s}, expected ipfs, bzzr1, or none"))
+    }
+}
+
+/// Weight of a benchmark in summaries, 1 by default. Compared and hashed by its bits, so that
+/// benchmarks can be used as keys.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct Weight(pub f64);
+
+impl Default for Weight {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+impl Eq for Weight {}
+
+impl std::hash::Hash for Weight {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
+}
+
+/// An EIP-2930 access list entry, with the address and storage keys as hex.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct AccessListItem {
+    pub address: String,
+    pub storage_keys: Vec<String>,
+}
+
+impl AccessListItem {
+    /// Formats the entry as a runner `--access-list` argument, `<address>[:<key>,<key>...]`.
+    pub fn to_arg(&self) -> String {
+        if self.storage_keys.is_empty() {
+            self.address.clone()
+        } else {
+            format!("{}:{}", self.address, self.storage_keys.join(","))
+        }
+    }
+}
+
+/// Fields of the block environment that a benchmark runs in, with the hash as hex.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct BlockEnv {
+    #[serde(default)]
+    pub number: Option<u64>,
+    #[serde(default)]
+    pub timestamp: Option<u64>,
+    #[serde(default)]
+    pub basefee: Option<u64>,
+    #[serde(default)]
+    pub gas_limit: Option<u64>,
+    #[serde(default)]
+    pub chain_id: Option<u64>,
+    #[serde(default)]
+    pub prevrandao: Option<String>,
+}
+
+impl BlockEnv {
+    /// Formats the set fields as runner `--block` arguments, `<field>=<value>`.
+    pub fn to_args(&self) -> Vec<String> {
+        [
+            ("number", self.number.map(|v| v.to_string())),
+            ("timestamp", self.timestamp.map(|v| v.to_string())),
+            ("basefee", self.basefee.map(|v| v.to_string())),
+            ("gas-limit", self.gas_limit.map(|v| v.to_string())),
+            ("chain-id", self.chain_id.map(|v| v.to_string())),
+            ("prevrandao", self.prevrandao.clone()),
+        ]
+        .into_iter()
+        .filter_map(|(field, value)| Some(format!("{field}={}", value?)))
+        .collect()
+    }
+
+    fn parse(json: &serde_json::Value) -> Result<Self, Box<dyn error::Error>> {
+        let object = json.as_object().ok_or("could not parse block as object")?;
+        let mut block = Self::default();
+        for (field, value) in object {
+            let number = || {
+                value
+                    .as_u64()
+                    .ok_or(format!("could not parse block {field} as u64"))
+            };
+            match field.as_str() {
+                "number" => block.number = Some(number()?),
+                "timestamp" => block.timestamp = Some(number()?),
+                "basefee" => block.basefee = Some(number()?),
+                "gas-limit" => block.gas_limit = Some(number()?),
+                "chain-id" => block.chain_id = Some(number()?),
+                "prevrandao" => {
+                    block.prevrandao = Some(parse_padded_hex(
+                        value
+                            .as_str()
+                            .ok_or("could not parse block prevrandao as string")?,
+                        "block prevrandao",
+                        32,
+                    )?)
+                }
+                field => return Err(format!("unknown block field {field}").into()),
+            }
+        }
+        Ok(block)
+    }
+}
+
+/// A call of a benchmark's call sequence, with the caller and value as hex.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Call {
+    pub calldata: Vec<u8>,
+    #[serde(default)]
+    pub caller: Option<String>,
+    #[serde(default)]
+    pub value: Option<String>,
+}
+
+impl Call {
+    /// Formats the call as a runner `--call` argument, `<caller>,<value>,<calldata>`, leaving the
+    /// caller and value empty to use the runner's defaults.
+    pub fn to_arg(&self) -> String {
+        format!(
+            "{},{},{}",
+            self.caller.as_deref().unwrap_or_default(),
+            self.value.as_deref().unwrap_or_default(),
+            hex::encode(&self.calldata)
+        )
+    }
+}
+
+/// Alias for the address of the deployed benchmark contract, which differs between runners.
+pub const CONTRACT_ADDRESS_ALIAS: &str = "contract";
+
+/// Parses hex bytes, such as calldata, for which `what` is named in errors. The hex may be prefixed
+/// with `0x` or not, and must have an even number of digits. Empty hex is no bytes.
+pub fn parse_hex(value: &str, what: &str) -> Result<Vec<u8>, Box<dyn error::Error>> {
+    let digits = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+        .unwrap_or(value);
+    hex::decode(digits).map_err(|e| {
+        format!(
+            "could not parse {what} {value:?} as hex (with or without 0x, and an even number of \
+             digits): {e}"
+        )
+        .into()
+    })
+}
+
+fn parse_padded_hex(
+    value: &str,
+    what: &str,
+    max_len: usize,
+) -> Result<String, Box<dyn error::Error>> {
+    let bytes = parse_hex(value, what)?;
+    if bytes.len() > max_len {
+        return Err(format!("{what} {value} is longer than {max_len} bytes").into());
+    }
+    // Left-pad to the full width, so that short values like `0x01` are accepted by all runners.
+    let mut padded = vec![0u8; max_len - bytes.len()];
+    padded.extend(bytes);
+    Ok(format!("0x{}", hex::encode(padded)))
 }
 
 pub struct BenchmarkDefaults {
     pub solc_version: String,
     pub num_runs: u64,
     pub calldata: Vec<u8>,
+    pub metadata_hash: Option<MetadataHash>,
 }
 
 impl MetadataParser for Benchmark {
@@ -99,7 +356,23 @@ impl MetadataParser for Benchmark {
                 .get("num-runs")
                 .map_or(Ok::<u64, Box<dyn error::Error>>(defaults.num_runs), |x| {
                     Ok(x.as_u64().ok_or("could not parse num-runs as u64")?)
+                })
+                .and_then(|num_runs| match num_runs {
+                    0 => Err("num-runs must be at least 1".into()),
+                    num_runs => Ok(num_runs),
                 })?,
+            weight: object
+                .get("weight")
+                .map(|x| {
+                    match x.as_f64().ok_or("could not parse weight as number")? {
+                        weight if weight.is_finite() && weight >= 0.0 => Ok(Weight(weight)),
+                        _ => Err::<_, Box<dyn error::Error>>(
+                            "weight must be a non-negative number".into(),
+                        ),
+                    }
+                })
+                .transpose()?
+                .unwrap_or_default(),
             contract: base_path
                 .join(PathBuf::from(
                     object
@@ -109,6 +382,14 @@ impl MetadataParser for Benchmark {
                         .ok_or("could not parse contract as string")?,
                 ))
                 .canonicalize()?,
+            contract_name: object
+                .get("contract-name")
+                .map(|x| {
+                    x.as_str()
+                        .ok_or("could not parse contract-name as string")
+                        .map(str::to_string)
+                })
+                .transpose()?,
             build_context: base_path
                 .join(PathBuf::from(object.get("build-context").map_or(
                     Ok::<String, Box<dyn error::Error>>(".".into()),
@@ -121,23 +402,536 @@ impl MetadataParser for Benchmark {
                 .canonicalize()?,
             calldata: object.get("calldata").map_or(
                 Ok::<Vec<u8>, Box<dyn error::Error>>(defaults.calldata.clone()),
+                |x| parse_hex(x.as_str().ok_or("could not parse calldata as string")?, "calldata"),
+            )?,
+            access_list: object.get("access-list").map_or(
+                Ok::<Vec<AccessListItem>, Box<dyn error::Error>>(Vec::new()),
                 |x| {
-                    Ok(hex::decode(
-                        x.as_str().ok_or("could not parse calldata as bytes")?,
-                    )?)
+                    x.as_array()
+                        .ok_or("could not parse access-list as array")?
+                        .iter()
+                        .map(|item| {
+                            Ok(AccessListItem {
+                                address: match item
+                                    .get("address")
+                                    .ok_or("could not find access-list address")?
+                                    .as_str()
+                                    .ok_or("could not parse access-list address as string")?
+                                {
+                                    CONTRACT_ADDRESS_ALIAS => CONTRACT_ADDRESS_ALIAS.to_string(),
+                                    address => {
+                                        parse_padded_hex(address, "access-list address", 20)?
+                                    }
+                                },
+                                storage_keys: item.get("storage-keys").map_or(
+                                    Ok(Vec::new()),
+                                    |keys| {
+                                        keys.as_array()
+                                            .ok_or(
+                                                "could not parse access-list storage-keys as array",
+                                            )?
+                                            .iter()
+                                            .map(|key| {
+                                                parse_padded_hex(
+                                                    key.as_str().ok_or(
+                                                        "could not parse access-list storage key as string",
+                                                    )?,
+                                                    "access-list storage key",
+                                                    32,
+                                                )
+                                            })
+                                            .collect()
+                                    },
+                                )?,
+                            })
+                        })
+                        .collect()
+                },
+            )?,
+            storage: object.get("storage").map_or(
+                Ok::<BTreeMap<String, String>, Box<dyn error::Error>>(BTreeMap::new()),
+                |x| {
+                    x.as_object()
+                        .ok_or("could not parse storage as object")?
+                        .iter()
+                        .map(|(slot, value)| {
+                            Ok((
+                                parse_padded_hex(slot, "storage slot", 32)?,
+                                parse_padded_hex(
+                                    value
+                                        .as_str()
+                                        .ok_or("could not parse storage value as string")?,
+                                    "storage value",
+                                    32,
+                                )?,
+                            ))
+                        })
+                        .collect()
+                },
+            )?,
+            gas_budget: object
+                .get("gas-budget")
+                .map(|x| x.as_u64().ok_or("could not parse gas-budget as u64"))
+                .transpose()?,
+            fork_url: object
+                .get("fork-url")
+                .map(|x| {
+                    x.as_str()
+                        .ok_or("could not parse fork-url as string")
+                        .map(str::to_string)
+                })
+                .transpose()?,
+            fork_block: object
+                .get("fork-block")
+                .map(|x| x.as_u64().ok_or("could not parse fork-block as u64"))
+                .transpose()?,
+            block: object
+                .get("block")
+                .map(BlockEnv::parse)
+                .transpose()?
+                .unwrap_or_default(),
+            deployer: object
+                .get("deployer")
+                .map(|x| {
+                    parse_padded_hex(
+                        x.as_str().ok_or("could not parse deployer as string")?,
+                        "deployer",
+                        20,
+                    )
+                })
+                .transpose()?,
+            deployer_nonce: object
+                .get("deployer-nonce")
+                .map(|x| x.as_u64().ok_or("could not parse deployer-nonce as u64"))
+                .transpose()?,
+            solc_settings: object
+                .get("solc-settings")
+                .map(|x| {
+                    if x.is_object() {
+                        Ok(x.to_string())
+                    } else {
+                        Err("could not parse solc-settings as object")
+                    }
+                })
+                .transpose()?,
+            calls: object.get("calls").map_or(
+                Ok::<Vec<Call>, Box<dyn error::Error>>(Vec::new()),
+                |x| {
+                    x.as_array()
+                        .ok_or("could not parse calls as array")?
+                        .iter()
+                        .map(|call| {
+                            Ok(Call {
+                                calldata: parse_hex(
+                                    call.get("calldata")
+                                        .ok_or("could not find call calldata")?
+                                        .as_str()
+                                        .ok_or("could not parse call calldata as string")?,
+                                    "call calldata",
+                                )?,
+                                caller: call
+                                    .get("caller")
+                                    .map(|caller| {
+                                        parse_padded_hex(
+                                            caller
+                                                .as_str()
+                                                .ok_or("could not parse call caller as string")?,
+                                            "call caller",
+                                            20,
+                                        )
+                                    })
+                                    .transpose()?,
+                                value: call
+                                    .get("value")
+                                    .map(|value| {
+                                        parse_padded_hex(
+                                            value
+                                                .as_str()
+                                                .ok_or("could not parse call value as string")?,
+                                            "call value",
+                                            32,
+                                        )
+                                    })
+                                    .transpose()?,
+                            })
+                        })
+                        .collect()
                 },
             )?,
+            measure_step: object
+                .get("measure-step")
+                .map(|x| {
+                    x.as_u64()
+                        .map(|step| step as usize)
+                        .ok_or("could not parse measure-step as u64")
+                })
+                .transpose()?,
+            setup_cmd: object
+                .get("setup-cmd")
+                .map(|x| {
+                    x.as_str()
+                        .ok_or("could not parse setup-cmd as string")
+                        .map(str::to_string)
+                })
+                .transpose()?,
+            teardown_cmd: object
+                .get("teardown-cmd")
+                .map(|x| {
+                    x.as_str()
+                        .ok_or("could not parse teardown-cmd as string")
+                        .map(str::to_string)
+                })
+                .transpose()?,
+            hardforks: object
+                .get("hardforks")
+                .map(parse_hardforks)
+                .transpose()?,
+            metadata_hash: object
+                .get("metadata-hash")
+                .map(|x| {
+                    x.as_str()
+                        .ok_or("could not parse metadata-hash as string")?
+                        .parse::<MetadataHash>()
+                })
+                .transpose()?
+                .or(defaults.metadata_hash),
+            report_runs: object
+                .get("report-runs")
+                .map(|x| x.as_u64().ok_or("could not parse report-runs as u64"))
+                .transpose()?,
+            expected_gas: object
+                .get("expected-gas")
+                .map(|x| x.as_u64().ok_or("could not parse expected-gas as u64"))
+                .transpose()?,
+            expected_gas_tolerance: object
+                .get("expected-gas-tolerance")
+                .map(|x| {
+                    x.as_u64()
+                        .ok_or("could not parse expected-gas-tolerance as u64")
+                })
+                .transpose()?,
         };
+        benchmark.validate()?;
         log::debug!("parsed benchmark metadata: {}", &benchmark.name);
         log::trace!("benchmark metadata: {:?}", benchmark);
         Ok(benchmark)
     }
 }
 
+/// Override of a single benchmark field from the command line, as `<benchmark>.<field>=<value>`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BenchmarkOverride {
+    pub benchmark: String,
+    pub field: String,
+    pub value: String,
+}
+
+impl std::str::FromStr for BenchmarkOverride {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key, value) = s.split_once('=').ok_or(format!(
+            "override {s} is not of the form benchmark.field=value"
+        ))?;
+        // Benchmark names may themselves contain dots, but field names never do.
+        let (benchmark, field) = key.rsplit_once('.').ok_or(format!(
+            "override {s} is not of the form benchmark.field=value"
+        ))?;
+        Ok(Self {
+            benchmark: benchmark.to_string(),
+            field: field.to_string(),
+            value: value.to_string(),
+        })
+    }
+}
+
+impl Benchmark {
+    /// Whether the benchmark can run on `runner`, see [`Benchmark::unsupported_by`].
+    pub fn supports_runner(&self, runner: &Runner) -> bool {
+        self.unsupported_by(runner).is_none()
+    }
+
+    /// Why the benchmark can't run on `runner`, if it can't: the runner runs under a hardfork the
+    /// benchmark doesn't list, or the benchmark uses a feature the runner doesn't declare, which
+    /// the runner would reject as an unknown option.
+    pub fn unsupported_by(&self, runner: &Runner) -> Option<&'static str> {
+        if let (Some(hardforks), Some(hardfork)) = (&self.hardforks, &runner.hardfork) {
+            if !hardforks.contains(hardfork) {
+                return Some("runs under a hardfork it doesn't support");
+            }
+        }
+        if !self.storage.is_empty() && !runner.storage {
+            Some("doesn't support storage")
+        } else if !self.access_list.is_empty() && !runner.access_list {
+            Some("doesn't support access lists")
+        } else if !self.calls.is_empty() && !runner.calls {
+            Some("doesn't support sequences of calls")
+        } else {
+            None
+        }
+    }
+
+    /// Checks constraints between fields that can't be checked on their own.
+    fn validate(&self) -> Result<(), Box<dyn error::Error>> {
+        if self.fork_url.is_some() != self.fork_block.is_some() {
+            // Forking without a fixed block would make results depend on when they were taken.
+            return Err(format!(
+                "benchmark {} must set both fork-url and fork-block, or neither",
+                self.name
+            )
+            .into());
+        }
+        if self.report_runs == Some(0) {
+            return Err(format!("benchmark {} must report at least 1 run", self.name).into());
+        }
+        if let Some(measure_step) = self.measure_step {
+            if measure_step >= self.calls.len() {
+                return Err(format!(
+                    "benchmark {} measures step {measure_step} of only {} calls",
+                    self.name,
+                    self.calls.len()
+                )
+                .into());
+            }
+        }
+        if self.metadata_hash.is_some() {
+            // Versions that aren't numbers, such as `stable`, can't be checked up front.
+            let version = self
+                .solc_version
+                .split('.')
+                .map(|part| part.parse::<u64>().ok())
+                .collect::<Option<Vec<_>>>();
+            if let Some([major, minor, patch]) = version.as_deref() {
+                if (*major, *minor, *patch) < MetadataHash::MIN_SOLC_VERSION {
+                    return Err(format!(
+                        "benchmark {} sets metadata-hash, which needs solc 0.6.0 or later, but uses \
+                         solc {}",
+                        self.name, self.solc_version
+                    )
+                    .into());
+                }
+            }
+        }
+        if self.expected_gas_tolerance.is_some() && self.expected_gas.is_none() {
+            return Err(format!(
+                "benchmark {} sets expected-gas-tolerance without expected-gas",
+                self.name
+            )
+            .into());
+        }
+        if self.expected_gas.is_some() && self.gas_budget.is_some() {
+            // Runs under a gas budget use however much gas it takes to exceed the budget.
+            return Err(format!(
+                "benchmark {} cannot set both expected-gas and gas-budget",
+                self.name
+            )
+            .into());
+        }
+        if !self.calls.is_empty() && self.gas_budget.is_some() {
+            return Err(format!(
+                "benchmark {} cannot set both calls and gas-budget",
+                self.name
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Sets a field from its textual value, accepting both the metadata file's kebab-case and
+    /// snake_case field names.
+    fn set_field(&mut self, field: &str, value: &str) -> Result<(), Box<dyn error::Error>> {
+        let parse_u64 = |value: &str| {
+            value
+                .parse::<u64>()
+                .map_err(|e| format!("could not parse {field} value {value} as u64: {e}"))
+        };
+        let optional = |value: &str| (!value.is_empty()).then(|| value.to_string());
+        match field.replace('-', "_").as_str() {
+            "solc_version" if value.is_empty() => return Err("solc-version must not be empty".into()),
+            "solc_version" => self.solc_version = value.to_string(),
+            "num_runs" => match parse_u64(value)? {
+                0 => return Err("num-runs must be at least 1".into()),
+                num_runs => self.num_runs = num_runs,
+            },
+            "weight" => match value.parse::<f64>() {
+                Ok(weight) if weight.is_finite() && weight >= 0.0 => self.weight = Weight(weight),
+                _ => return Err(format!("weight {value} is not a non-negative number").into()),
+            },
+            "calldata" => self.calldata = parse_hex(value, "calldata")?,
+            "contract_name" => self.contract_name = optional(value),
+            "gas_budget" => self.gas_budget = optional(value).as_deref().map(parse_u64).transpose()?,
+            "fork_url" => self.fork_url = optional(value),
+            "fork_block" => self.fork_block = optional(value).as_deref().map(parse_u64).transpose()?,
+            "setup_cmd" => self.setup_cmd = optional(value),
+            "teardown_cmd" => self.teardown_cmd = optional(value),
+            "metadata_hash" => {
+                self.metadata_hash = optional(value)
+                    .map(|value| value.parse::<MetadataHash>())
+                    .transpose()?
+            }
+            "report_runs" => match optional(value).as_deref().map(parse_u64).transpose()? {
+                Some(0) => return Err("report-runs must be at least 1".into()),
+                report_runs => self.report_runs = report_runs,
+            },
+            "expected_gas" => {
+                self.expected_gas = optional(value).as_deref().map(parse_u64).transpose()?
+            }
+            "expected_gas_tolerance" => {
+                self.expected_gas_tolerance = optional(value).as_deref().map(parse_u64).transpose()?
+            }
+            _ => {
+                return Err(format!(
+                    "unknown or unsupported benchmark field {field}, expected one of solc-version, \
+                     num-runs, weight, calldata, contract-name, gas-budget, fork-url, fork-block, setup-cmd, \
+                     teardown-cmd, metadata-hash, report-runs, expected-gas, expected-gas-tolerance"
+                )
+                .into())
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Applies command-line overrides to the matching benchmarks, in order. Empty values unset
+/// optional fields.
+pub fn apply_overrides(
+    benchmarks: &mut [Benchmark],
+    overrides: &[BenchmarkOverride],
+) -> Result<(), Box<dyn error::Error>> {
+    for o in overrides {
+        let benchmark = benchmarks
+            .iter_mut()
+            .find(|b| b.name == o.benchmark)
+            .ok_or(format!("no benchmark named {} to override", o.benchmark))?;
+        benchmark
+            .set_field(&o.field, &o.value)
+            .map_err(|e| format!("could not override {}.{}: {e}", o.benchmark, o.field))?;
+        log::info!("overrode {}.{} with {:?}", o.benchmark, o.field, o.value);
+    }
+    benchmarks.iter().try_for_each(Benchmark::validate)
+}
+
+/// How a runner reports run times on stdout, besides the tagged lines every runner may print.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum OutputFormat {
+    /// A line per run with the run time in milliseconds.
+    #[default]
+    FloatMs,
+    /// A line per run with the run time in seconds.
+    FloatS,
+    /// A JSON object per run with the run time in milliseconds as `time_ms` or in seconds as
+    /// `time_s`. Other lines are ignored.
+    JsonLines,
+    /// Lines matching a regex, with the run time captured as `ms` in milliseconds or as `s` in
+    /// seconds. Other lines are ignored.
+    Regex(String),
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "float-ms" => Ok(Self::FloatMs),
+            "float-s" => Ok(Self::FloatS),
+            "json-lines" => Ok(Self::JsonLines),
+            _ => {
+                let pattern = s.strip_prefix("regex:").ok_or(format!(
+                    "unknown output format {s}, expected one of float-ms, float-s, json-lines, \
+                     regex:<pattern>"
+                ))?;
+                let regex = regex::Regex::new(pattern)
+                    .map_err(|e| format!("could not parse output format regex: {e}"))?;
+                if !regex
+                    .capture_names()
+                    .flatten()
+                    .any(|n| n == "ms" || n == "s")
+                {
+                    return Err(format!(
+                        "output format regex {pattern} has no capture named ms or s"
+                    ));
+                }
+                Ok(Self::Regex(pattern.to_string()))
+            }
+        }
+    }
+}
+
+impl TryFrom<String> for OutputFormat {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<OutputFormat> for String {
+    fn from(format: OutputFormat) -> Self {
+        match format {
+            OutputFormat::FloatMs => "float-ms".to_string(),
+            OutputFormat::FloatS => "float-s".to_string(),
+            OutputFormat::JsonLines => "json-lines".to_string(),
+            OutputFormat::Regex(pattern) => format!("regex:{pattern}"),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub struct Runner {
     pub name: String,
     pub entry: PathBuf,
+    /// Script that builds or installs the runner, run once before any benchmark is run on it so
+    /// that parallel invocations don't each do so at once.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prepare: Option<PathBuf>,
+    #[serde(default)]
+    pub category: Option<String>,
+    /// Number of runs to use instead of the benchmark's, e.g. for very slow runners.
+    #[serde(default)]
+    pub num_runs: Option<u64>,
+    #[serde(default)]
+    pub output_format: OutputFormat,
+    /// Hardforks the runner can run benchmarks under, when passed one with `--hardfork`.
+    #[serde(default)]
+    pub hardforks: Vec<String>,
+    /// Hardfork this runner runs benchmarks under, for runners swept across hardforks with
+    /// [`sweep_hardforks`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hardfork: Option<String>,
+    /// Whether the runner can run several benchmarks in one invocation, when passed a batch with
+    /// `--batch-path`.
+    #[serde(default)]
+    pub batch: bool,
+    /// Whether the runner can report the gas used by each run when passed `--report-gas`.
+    #[serde(default)]
+    pub report_gas: bool,
+    /// Whether the runner can output only its fastest runs when passed `--report-runs`.
+    #[serde(default)]
+    pub report_runs: bool,
+    /// Whether the runner can report the opcodes executed by a run when passed `--count-opcodes`.
+    #[serde(default)]
+    pub count_opcodes: bool,
+    /// Whether the runner can seed the contract's storage when passed `--storage`.
+    #[serde(default)]
+    pub storage: bool,
+    /// Whether the runner can apply an access list when passed `--access-list`.
+    #[serde(default)]
+    pub access_list: bool,
+    /// Whether the runner can make a sequence of calls when passed `--call` and `--measure-step`.
+    #[serde(default)]
+    pub calls: bool,
+}
+
+impl Runner {
+    /// Name of the runner this one was swept from with [`sweep_hardforks`], or its own name if it
+    /// wasn't.
+    pub fn base_name(&self) -> &str {
+        self.hardfork
+            .as_ref()
+            .and_then(|hardfork| self.name.strip_suffix(hardfork.as_str())?.strip_suffix('@'))
+            .unwrap_or(&self.name)
+    }
 }
 
 impl MetadataParser for Runner {
@@ -157,15 +951,97 @@ impl MetadataParser for Runner {
                 .as_str()
                 .ok_or("could not parse name as string")?
                 .to_string(),
-            entry: base_path
-                .join(PathBuf::from(
+            entry: {
+                let entry = base_path.join(
                     object
                         .get("entry")
                         .ok_or("could not find entry")?
                         .as_str()
                         .ok_or("could not parse entry as string")?,
-                ))
-                .canonicalize()?,
+                );
+                entry.canonicalize().map_err(|e| {
+                    format!("runner entry {} could not be found: {e}", entry.display())
+                })?
+            },
+            prepare: object
+                .get("prepare")
+                .map(|x| -> Result<_, Box<dyn error::Error>> {
+                    let prepare =
+                        base_path.join(x.as_str().ok_or("could not parse prepare as string")?);
+                    Ok(prepare.canonicalize().map_err(|e| {
+                        format!(
+                            "runner prepare script {} could not be found: {e}",
+                            prepare.display()
+                        )
+                    })?)
+                })
+                .transpose()?,
+            category: object
+                .get("category")
+                .map(|x| {
+                    x.as_str()
+                        .ok_or("could not parse category as string")
+                        .map(str::to_string)
+                })
+                .transpose()?,
+            num_runs: object
+                .get("num-runs")
+                .map(|x| match x.as_u64() {
+                    Some(0) => Err("num-runs must be at least 1"),
+                    Some(num_runs) => Ok(num_runs),
+                    None => Err("could not parse num-runs as u64"),
+                })
+                .transpose()?,
+            output_format: object
+                .get("output-format")
+                .map(|x| {
+                    x.as_str()
+                        .ok_or("could not parse output-format as string")?
+                        .parse::<OutputFormat>()
+                })
+                .transpose()?
+                .unwrap_or_default(),
+            hardforks: object
+                .get("hardforks")
+                .map(parse_hardforks)
+                .transpose()?
+                .unwrap_or_default(),
+            hardfork: None,
+            batch: object
+                .get("batch")
+                .map(|x| x.as_bool().ok_or("could not parse batch as bool"))
+                .transpose()?
+                .unwrap_or_default(),
+            report_gas: object
+                .get("report-gas")
+                .map(|x| x.as_bool().ok_or("could not parse report-gas as bool"))
+                .transpose()?
+                .unwrap_or_default(),
+            report_runs: object
+                .get("report-runs")
+                .map(|x| x.as_bool().ok_or("could not parse report-runs as bool"))
+                .transpose()?
+                .unwrap_or_default(),
+            count_opcodes: object
+                .get("count-opcodes")
+                .map(|x| x.as_bool().ok_or("could not parse count-opcodes as bool"))
+                .transpose()?
+                .unwrap_or_default(),
+            storage: object
+                .get("storage")
+                .map(|x| x.as_bool().ok_or("could not parse storage as bool"))
+                .transpose()?
+                .unwrap_or_default(),
+            access_list: object
+                .get("access-list")
+                .map(|x| x.as_bool().ok_or("could not parse access-list as bool"))
+                .transpose()?
+                .unwrap_or_default(),
+            calls: object
+                .get("calls")
+                .map(|x| x.as_bool().ok_or("could not parse calls as bool"))
+                .transpose()?
+                .unwrap_or_default(),
         };
         log::debug!("parsed runner metadata: {}", &runner.name);
         log::trace!("runner metadata: {:?}", runner);
@@ -173,65 +1049,175 @@ impl MetadataParser for Runner {
     }
 }
 
+/// Checks that a runner's entry point can be executed, so that a missing `chmod +x` isn't reported
+/// as a bare "permission denied" when running it.
+pub fn check_entry(entry: &Path) -> Result<(), Box<dyn error::Error>> {
+    if !entry.is_file() {
+        return Err(format!("runner entry {} is not a file", entry.display()).into());
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if fs::metadata(entry)?.permissions().mode() & 0o111 == 0 {
+            return Err(format!(
+                "runner entry {} is not executable, make it so with `chmod +x {}`",
+                entry.display(),
+                entry.display()
+            )
+            .into());
+        }
+    }
+    Ok(())
+}
+
+fn parse_hardforks(value: &serde_json::Value) -> Result<Vec<String>, Box<dyn error::Error>> {
+    value
+        .as_array()
+        .ok_or("could not parse hardforks as array")?
+        .iter()
+        .map(|hardfork| {
+            Ok(hardfork
+                .as_str()
+                .ok_or("could not parse hardfork as string")?
+                .to_string())
+        })
+        .collect()
+}
+
+/// Replaces each runner with one per hardfork in `hardforks` that it supports, named
+/// `<runner>@<hardfork>`, so that each hardfork gets its own column of results. Runners are
+/// skipped for hardforks they don't list.
+pub fn sweep_hardforks(runners: Vec<Runner>, hardforks: &[String]) -> Vec<Runner> {
+    let mut swept = Vec::new();
+    for runner in runners {
+        for hardfork in hardforks {
+            if !runner.hardforks.contains(hardfork) {
+                log::warn!(
+                    "runner {} does not support hardfork {hardfork}, skipping it",
+                    runner.name
+                );
+                continue;
+            }
+            swept.push(Runner {
+                name: format!("{}@{hardfork}", runner.name),
+                hardfork: Some(hardfork.clone()),
+                ..runner.clone()
+            });
+        }
+    }
+    swept
+}
+
+/// Name of the file at the root of a search path listing paths to skip, in gitignore syntax.
+const IGNORE_FILE_NAME: &str = ".evm-benchignore";
+
+/// Reads the ignore file at the root of `search_path`, matching nothing if there is none.
+fn read_ignore_file(search_path: &Path) -> Result<Gitignore, Box<dyn error::Error>> {
+    let ignore_path = search_path.join(IGNORE_FILE_NAME);
+    let mut builder = GitignoreBuilder::new(search_path);
+    if ignore_path.is_file() {
+        if let Some(e) = builder.add(&ignore_path) {
+            return Err(format!("could not read {}: {e}", ignore_path.display()).into());
+        }
+    }
+    Ok(builder.build()?)
+}
+
+/// Finds and parses metadata files under each of `search_paths`, skipping those ignored by the
+/// search path's ignore file. Files under more than one of them, e.g. when one is nested in
+/// another, are only parsed once.
 fn find_metadata<T: MetadataParser>(
     file_name: &str,
     schema_path: &Path,
-    search_path: &Path,
+    search_paths: &[PathBuf],
     defaults: T::Defaults,
 ) -> Result<Vec<T>, Box<dyn error::Error>> {
     let schema = Benchmark::parse_schema_from_file(schema_path)?;
 
-    let search_path = search_path.canonicalize()?;
-    if !search_path.is_dir() {
-        return Err(format!("{} is not a directory", search_path.display()).into());
+    let mut found = HashSet::new();
+    let mut parsed = Vec::new();
+    for search_path in search_paths {
+        let search_path = search_path.canonicalize()?;
+        if !search_path.is_dir() {
+            return Err(format!("{} is not a directory", search_path.display()).into());
+        }
+        let ignore = read_ignore_file(&search_path)?;
+        let mut num_ignored = 0;
+
+        parsed.extend(
+            glob(&search_path.join("**").join(file_name).to_string_lossy())?
+                .flat_map(|entry| match entry {
+                    Ok(path) => {
+                        log::debug!(
+                            "found {}",
+                            path.strip_prefix(&search_path).unwrap_or(&path).display()
+                        );
+                        Some(path)
+                    }
+                    Err(e) => {
+                        log::warn!("error globing file: {:?}", e);
+                        None
+                    }
+                })
+                .filter(|path| {
+                    let ignored = ignore.matched_path_or_any_parents(path, false).is_ignore();
+                    if ignored {
+                        log::trace!(
+                            "ignoring {}",
+                            path.strip_prefix(&search_path).unwrap_or(path).display()
+                        );
+                        num_ignored += 1;
+                    }
+                    !ignored
+                })
+                .filter(|path| found.insert(path.clone()))
+                .flat_map(|path| match T::parse_from_file(&schema, &path, &defaults) {
+                    Ok(res) => {
+                        log::debug!(
+                            "parsed {}",
+                            path.strip_prefix(&search_path).unwrap_or(&path).display()
+                        );
+                        Some(res)
+                    }
+                    Err(e) => {
+                        log::warn!("error parsing {}: {e}", path.display());
+                        None
+                    }
+                }),
+        );
+        log::debug!(
+            "ignored {num_ignored} paths under {} matching {IGNORE_FILE_NAME}",
+            search_path.display()
+        );
     }
+    Ok(parsed)
+}
 
-    Ok(
-        glob(&search_path.join("**").join(file_name).to_string_lossy())?
-            .flat_map(|entry| match entry {
-                Ok(path) => {
-                    log::debug!(
-                        "found {}",
-                        path.strip_prefix(&search_path).unwrap_or(&path).display()
-                    );
-                    Some(path)
-                }
-                Err(e) => {
-                    log::warn!("error globing file: {:?}", e);
-                    None
-                }
-            })
-            .flat_map(|path| match T::parse_from_file(&schema, &path, &defaults) {
-                Ok(res) => {
-                    log::debug!(
-                        "parsed {}",
-                        path.strip_prefix(&search_path).unwrap_or(&path).display()
-                    );
-                    Some(res)
-                }
-                Err(e) => {
-                    log::warn!("error parsing file: {:?}", e);
-                    None
-                }
-            })
-            .collect(),
-    )
+/// Names that occur more than once, sorted.
+fn duplicate_names<'a>(names: impl Iterator<Item = &'a str>) -> Vec<&'a str> {
+    let mut seen = HashSet::new();
+    let mut duplicates = names
+        .filter(|name| !seen.insert(*name))
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    duplicates.sort();
+    duplicates
 }
 
-pub fn find_benchmarks(
-    file_name: &str,
-    schema_path: &Path,
-    search_path: &Path,
-    benchmark_defaults: BenchmarkDefaults,
+fn validate_benchmarks(
+    benchmarks: Vec<Benchmark>,
 ) -> Result<Vec<Benchmark>, Box<dyn error::Error>> {
-    let benchmarks =
-        find_metadata::<Benchmark>(file_name, schema_path, search_path, benchmark_defaults)?;
     let benchmark_names = benchmarks
         .iter()
         .map(|b| b.name.clone())
         .collect::<HashSet<_>>();
     if benchmark_names.len() != benchmarks.len() {
-        Err("found duplicate benchmark names".into())
+        Err(format!(
+            "found duplicate benchmark names: {}",
+            duplicate_names(benchmarks.iter().map(|b| b.name.as_str())).join(", ")
+        )
+        .into())
     } else {
         log::info!(
             "found {} benchmarks: {}",
@@ -246,19 +1232,92 @@ pub fn find_benchmarks(
     }
 }
 
+pub fn find_benchmarks(
+    file_name: &str,
+    schema_path: &Path,
+    search_paths: &[PathBuf],
+    benchmark_defaults: BenchmarkDefaults,
+) -> Result<Vec<Benchmark>, Box<dyn error::Error>> {
+    validate_benchmarks(find_metadata::<Benchmark>(
+        file_name,
+        schema_path,
+        search_paths,
+        benchmark_defaults,
+    )?)
+}
+
+/// Reads all benchmarks from a single manifest file rather than searching for per-directory
+/// metadata. The manifest is an object with a `benchmarks` array, each entry of which follows the
+/// benchmark metadata schema with paths relative to the manifest.
+pub fn read_benchmark_manifest(
+    schema_path: &Path,
+    manifest_path: &Path,
+    benchmark_defaults: BenchmarkDefaults,
+) -> Result<Vec<Benchmark>, Box<dyn error::Error>> {
+    let schema = Benchmark::parse_schema_from_file(schema_path)?;
+
+    let manifest_path = manifest_path.canonicalize()?;
+    let base_path = manifest_path.parent().ok_or("could not get parent")?;
+    let manifest: serde_json::Value = serde_json::from_reader(&fs::File::open(&manifest_path)?)?;
+    let entries = manifest
+        .get("benchmarks")
+        .ok_or("could not find benchmarks in manifest")?
+        .as_array()
+        .ok_or("could not parse benchmarks in manifest as array")?;
+    log::debug!(
+        "found {} entries in manifest {}",
+        entries.len(),
+        manifest_path.display()
+    );
+
+    validate_benchmarks(
+        entries
+            .iter()
+            .enumerate()
+            .flat_map(|(i, entry)| {
+                match Benchmark::parse(base_path, &schema, entry, &benchmark_defaults) {
+                    Ok(res) => Some(res),
+                    Err(e) => {
+                        log::warn!("error parsing manifest entry {i}: {:?}", e);
+                        None
+                    }
+                }
+            })
+            .collect(),
+    )
+}
+
 pub fn find_runners(
     file_name: &str,
     schema_path: &Path,
-    search_path: &Path,
+    search_paths: &[PathBuf],
     runner_defaults: (),
 ) -> Result<Vec<Runner>, Box<dyn error::Error>> {
-    let runners = find_metadata::<Runner>(file_name, schema_path, search_path, runner_defaults)?;
+    let runners = find_metadata::<Runner>(file_name, schema_path, search_paths, runner_defaults)?
+        .into_iter()
+        .filter(|runner| {
+            let checked = check_entry(&runner.entry)
+                .and_then(|()| runner.prepare.as_deref().map_or(Ok(()), check_entry));
+            match checked {
+                Ok(()) => true,
+                Err(e) => {
+                    // Unlike other broken metadata, this is easily missed when adding a runner.
+                    log::error!("skipping runner {}: {e}", runner.name);
+                    false
+                }
+            }
+        })
+        .collect::<Vec<_>>();
     let runner_names = runners
         .iter()
         .map(|b| b.name.clone())
         .collect::<HashSet<_>>();
     if runner_names.len() != runners.len() {
-        Err("found duplicate runners names".into())
+        Err(format!(
+            "found duplicate runners names: {}",
+            duplicate_names(runners.iter().map(|r| r.name.as_str())).join(", ")
+        )
+        .into())
     } else {
         log::info!(
             "found {} runners: {}",
@@ -268,3 +1327,61 @@ pub fn find_runners(
         Ok(runners)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_hex_accepts_0x_prefix() {
+        assert_eq!(parse_hex("0x01ab", "calldata").unwrap(), vec![0x01, 0xab]);
+        assert_eq!(parse_hex("0X01AB", "calldata").unwrap(), vec![0x01, 0xab]);
+        assert_eq!(parse_hex("0x", "calldata").unwrap(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn parse_hex_accepts_no_prefix() {
+        assert_eq!(parse_hex("01ab", "calldata").unwrap(), vec![0x01, 0xab]);
+        assert_eq!(parse_hex("", "calldata").unwrap(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn parse_hex_rejects_odd_length() {
+        let err = parse_hex("0x123", "calldata").unwrap_err().to_string();
+        assert!(err.contains("calldata \"0x123\""), "{err}");
+        assert!(parse_hex("123", "calldata").is_err());
+    }
+
+    #[test]
+    fn parse_hex_rejects_invalid_digits() {
+        assert!(parse_hex("0xzz", "calldata").is_err());
+        assert!(parse_hex("0x0x01", "calldata").is_err());
+        assert!(parse_hex("01 ab", "calldata").is_err());
+    }
+
+    #[test]
+    fn benchmarks_with_storage_skip_runners_without_it() {
+        let benchmark: Benchmark = serde_json::from_value(serde_json::json!({
+            "name": "sload",
+            "solc_version": "0.8.17",
+            "num_runs": 2,
+            "contract": "SLoad.sol",
+            "build_context": ".",
+            "calldata": [],
+            "storage": {"0x00": "0x01"},
+        }))
+        .unwrap();
+        let runner: Runner =
+            serde_json::from_value(serde_json::json!({"name": "geth", "entry": "entry.sh"}))
+                .unwrap();
+        assert_eq!(
+            benchmark.unsupported_by(&runner),
+            Some("doesn't support storage")
+        );
+        let runner = Runner {
+            storage: true,
+            ..runner
+        };
+        assert!(benchmark.supports_runner(&runner));
+    }
+}
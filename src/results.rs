@@ -1,45 +1,263 @@
 use std::{
-    collections::{HashMap, HashSet},
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap, HashSet},
     error,
     fs::{self, create_dir_all},
-    io::Write,
+    io::{self, Write},
     path::{Path, PathBuf},
     time::Duration,
 };
 
-use chrono;
 use serde::{Deserialize, Serialize};
-use tabled::{builder::Builder, settings::Style};
+use tabled::{builder::Builder, settings::Style, Table};
 
 use crate::{
     metadata::{Benchmark, Runner},
     run::{Results, RunResult},
+    stats,
 };
 
+/// Version of the results file format written by this build, bumped whenever older files need
+/// migrating to be read. Fields added with defaults don't need a new version.
+pub const RESULTS_SCHEMA_VERSION: u32 = 1;
+
+/// Results as written to results files, keyed by benchmark and runner names.
 #[derive(Deserialize, Serialize)]
-struct ResultsFormatted {
-    benchmarks: HashMap<String, Benchmark>,
-    runners: HashMap<String, Runner>,
-    runs: HashMap<String, HashMap<String, RunResult>>,
+pub struct ResultsFormatted {
+    /// Format version of the file, zero for files written before it was versioned.
+    #[serde(default)]
+    pub schema_version: u32,
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    /// Machine the results were recorded on, missing from results recorded before it was.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<Environment>,
+    pub benchmarks: HashMap<String, Benchmark>,
+    pub runners: HashMap<String, Runner>,
+    pub runs: HashMap<String, HashMap<String, RunResult>>,
+}
+
+/// Machine and software that results were recorded with, for publishing alongside them.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Environment {
+    pub date: String,
+    pub os: String,
+    pub arch: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_cores: Option<usize>,
+    pub evm_bench_version: String,
+    /// Git commit of the working tree evm-bench ran in, with a `-dirty` suffix if it had
+    /// uncommitted changes, unless given explicitly.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_rev: Option<String>,
+}
+
+impl Environment {
+    pub fn current() -> Self {
+        Self {
+            date: chrono::offset::Utc::now().to_rfc3339(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            cpu: cpu_model(),
+            cpu_cores: std::thread::available_parallelism().ok().map(usize::from),
+            evm_bench_version: env!("CARGO_PKG_VERSION").to_string(),
+            git_rev: git_rev(),
+        }
+    }
+}
+
+/// Best-effort git commit of the working tree in the current directory, suffixed with `-dirty` if
+/// tracked files have uncommitted changes.
+fn git_rev() -> Option<String> {
+    let git = |args: &[&str]| {
+        let output = std::process::Command::new("git").args(args).output().ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8(output.stdout).ok())
+            .flatten()
+    };
+    let rev = git(&["rev-parse", "HEAD"])?.trim().to_string();
+    let dirty = !git(&["status", "--porcelain", "--untracked-files=no"])?
+        .trim()
+        .is_empty();
+    Some(if dirty { format!("{rev}-dirty") } else { rev })
+}
+
+/// Best-effort CPU model name, from `/proc/cpuinfo` on Linux or `sysctl` on macOS.
+fn cpu_model() -> Option<String> {
+    if let Ok(cpuinfo) = fs::read_to_string("/proc/cpuinfo") {
+        return cpuinfo
+            .lines()
+            .find_map(|line| line.strip_prefix("model name")?.split_once(':'))
+            .map(|(_, model)| model.trim().to_string());
+    }
+    let output = std::process::Command::new("sysctl")
+        .args(["-n", "machdep.cpu.brand_string"])
+        .output()
+        .ok()?;
+    let model = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (output.status.success() && !model.is_empty()).then_some(model)
 }
 
+/// Follows the timestamp in the names of results files, before any collision suffix and extension.
+const RESULTS_FILE_SUFFIX: &str = ".evm-bench.results";
+
+/// Extension of results files encoded as MessagePack rather than JSON.
+const BINARY_RESULTS_EXTENSION: &str = "msgpack";
+
+/// Whether the results file at `path` is encoded as MessagePack, by its extension.
+fn is_binary_results_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == BINARY_RESULTS_EXTENSION)
+}
+
+/// Which earlier results file to pick as the baseline.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Since {
+    /// The most recent one.
+    Last,
+    /// The most recent one recorded at or before this time.
+    Time(chrono::DateTime<chrono::FixedOffset>),
+}
+
+impl std::str::FromStr for Since {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "last" => Ok(Self::Last),
+            s => chrono::DateTime::parse_from_rfc3339(s)
+                .map(Self::Time)
+                .map_err(|e| format!("{s} is neither `last` nor an RFC 3339 time: {e}")),
+        }
+    }
+}
+
+/// Time that a results file was recorded at, from its name, if it was named by [`record_results`].
+fn results_file_time(path: &Path) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+    let name = path.file_name()?.to_string_lossy();
+    chrono::DateTime::parse_from_rfc3339(&name[..name.find(RESULTS_FILE_SUFFIX)?]).ok()
+}
+
+/// Finds the results file in `results_path` picked by `since` from the timestamps in their names,
+/// ignoring `exclude` (e.g. the file of the current run) and files not named by
+/// [`record_results`].
+pub fn find_results_since(
+    results_path: &Path,
+    since: Since,
+    exclude: &Path,
+) -> Result<Option<PathBuf>, Box<dyn error::Error>> {
+    let mut latest = None;
+    for entry in fs::read_dir(results_path)? {
+        let path = entry?.path();
+        if path == exclude {
+            continue;
+        }
+        let Some(time) = results_file_time(&path) else {
+            continue;
+        };
+        if matches!(since, Since::Time(since) if time > since) {
+            continue;
+        }
+        match &latest {
+            Some((latest_time, _)) if *latest_time >= time => {}
+            _ => latest = Some((time, path)),
+        }
+    }
+    Ok(latest.map(|(_, path)| path))
+}
+
+/// What to do when the results file to write already exists.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OnCollision {
+    /// Refuse to write, erroring out.
+    #[default]
+    Error,
+    /// Replace the existing file.
+    Overwrite,
+    /// Write to a new file with a numeric suffix, e.g. `results-1.json`.
+    AppendSuffix,
+}
+
+fn open_result_file(
+    result_file_path: &Path,
+    on_collision: OnCollision,
+) -> Result<(fs::File, PathBuf), Box<dyn error::Error>> {
+    let open = |path: &Path| {
+        fs::OpenOptions::new()
+            .create_new(on_collision != OnCollision::Overwrite)
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(path)
+    };
+
+    match (open(result_file_path), on_collision) {
+        (Ok(file), _) => Ok((file, result_file_path.to_path_buf())),
+        (Err(e), OnCollision::AppendSuffix) if e.kind() == io::ErrorKind::AlreadyExists => {
+            let stem = result_file_path
+                .file_stem()
+                .ok_or("could not get result file stem")?
+                .to_string_lossy();
+            for suffix in 1.. {
+                let mut file_name = format!("{stem}-{suffix}");
+                if let Some(extension) = result_file_path.extension() {
+                    file_name = format!("{file_name}.{}", extension.to_string_lossy());
+                }
+                let path = result_file_path.with_file_name(file_name);
+                match open(&path) {
+                    Ok(file) => return Ok((file, path)),
+                    Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            unreachable!("ran out of suffixes")
+        }
+        (Err(e), _) if e.kind() == io::ErrorKind::AlreadyExists => Err(format!(
+            "{} already exists, use --overwrite or --append-suffix to write anyway",
+            result_file_path.display()
+        )
+        .into()),
+        (Err(e), _) => Err(e.into()),
+    }
+}
+
+/// Writes results to `result_file_name` under `results_path`, or to a file named after the current
+/// time. Files are encoded as MessagePack if their name has its extension, which is the default
+/// with `binary`, and as JSON otherwise.
 pub fn record_results(
     results_path: &Path,
     result_file_name: Option<String>,
+    on_collision: OnCollision,
+    labels: HashMap<String, String>,
+    git_rev: Option<String>,
+    binary: bool,
     results: &Results,
 ) -> Result<PathBuf, Box<dyn error::Error>> {
     log::debug!("writing all results out...");
 
-    create_dir_all(&results_path)?;
+    create_dir_all(results_path)?;
 
     let mut runners = HashSet::<&Runner>::new();
-    for (_, benchmark_results) in results {
-        for (runner, _) in benchmark_results {
+    for benchmark_results in results.values() {
+        for runner in benchmark_results.keys() {
             runners.insert(runner);
         }
     }
 
     let results_formatted = ResultsFormatted {
+        schema_version: RESULTS_SCHEMA_VERSION,
+        labels,
+        environment: Some(match git_rev {
+            Some(git_rev) => Environment {
+                git_rev: Some(git_rev),
+                ..Environment::current()
+            },
+            None => Environment::current(),
+        }),
         benchmarks: results
             .keys()
             .map(|b| (b.name.clone(), b.clone()))
@@ -62,19 +280,25 @@ pub fn record_results(
     };
 
     let result_file_path = results_path.join(result_file_name.unwrap_or(format!(
-        "{}.evm-bench.results.json",
-        chrono::offset::Utc::now().to_rfc3339()
+        "{}{RESULTS_FILE_SUFFIX}.{}",
+        chrono::offset::Utc::now().to_rfc3339(),
+        if binary {
+            BINARY_RESULTS_EXTENSION
+        } else {
+            "json"
+        }
     )));
-    let mut result_file = fs::OpenOptions::new()
-        .create_new(true)
-        .write(true)
-        .truncate(true)
-        .open(&result_file_path)?;
-    write!(
-        result_file,
-        "{}",
-        serde_json::to_string_pretty(&results_formatted)?
-    )?;
+    let (mut result_file, result_file_path) = open_result_file(&result_file_path, on_collision)?;
+    if is_binary_results_file(&result_file_path) {
+        // Encoded with field names, so that fields can be skipped and added like in JSON.
+        result_file.write_all(&rmp_serde::to_vec_named(&results_formatted)?)?;
+    } else {
+        write!(
+            result_file,
+            "{}",
+            serde_json::to_string_pretty(&results_formatted)?
+        )?;
+    }
 
     log::info!(
         "wrote out results to {}",
@@ -83,66 +307,504 @@ pub fn record_results(
     Ok(result_file_path)
 }
 
-pub fn print_results(results_file_path: &Path) -> Result<(), Box<dyn error::Error>> {
+/// Loads results previously written by [`record_results`], along with their labels.
+pub fn load_results(
+    results_file_path: &Path,
+) -> Result<(HashMap<String, String>, Results), Box<dyn error::Error>> {
+    let results = read_results(results_file_path)?;
+    let mut loaded = Results::new();
+    for (benchmark_name, benchmark_runs) in results.runs {
+        let benchmark = results.benchmarks.get(&benchmark_name).ok_or(format!(
+            "results have runs of unknown benchmark {benchmark_name}"
+        ))?;
+        let mut benchmark_results = HashMap::new();
+        for (runner_name, run) in benchmark_runs {
+            let runner = results
+                .runners
+                .get(&runner_name)
+                .ok_or(format!("results have runs of unknown runner {runner_name}"))?;
+            benchmark_results.insert(runner.clone(), run);
+        }
+        loaded.insert(benchmark.clone(), benchmark_results);
+    }
+    Ok((results.labels, loaded))
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    /// Plain markdown table.
+    #[default]
+    Markdown,
+    /// Markdown for GitHub PR comments, flagging regressions against a baseline.
+    GithubMarkdown,
+    /// Bencher Metric Format JSON, for continuous benchmarking dashboards.
+    Bmf,
+    /// Markdown table preceded by a fenced JSON block of the environment, for publishing in docs.
+    DocsMarkdown,
+    /// The results as JSON, in the same structure as results files.
+    Json,
+    /// CSV with one row of statistics per benchmark and runner.
+    Csv,
+    /// Standalone HTML page with the results table.
+    Html,
+    /// SVG bar chart of the total time of each runner.
+    Svg,
+    /// shields.io endpoint JSON naming the fastest runner, for a README badge.
+    Badge,
+    /// A single line of space-separated key=value fields naming the fastest runner, for scripts.
+    Oneline,
+    /// Foundry `.gas-snapshot` lines with the gas used by a run of each benchmark.
+    GasSnapshot,
+}
+
+/// A format to output results in, and the file to write them to rather than stdout if any.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FormatOutput {
+    pub format: Format,
+    pub path: Option<PathBuf>,
+}
+
+impl std::str::FromStr for FormatOutput {
+    type Err = String;
+
+    /// Parses `<format>` or `<format>=<path>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (format, path) = match s.split_once('=') {
+            Some((format, path)) => (format, Some(PathBuf::from(path))),
+            None => (s, None),
+        };
+        Ok(Self {
+            format: clap::ValueEnum::from_str(format, true)?,
+            path,
+        })
+    }
+}
+
+/// Destination for results, e.g. a report in some format.
+pub trait OutputSink {
+    fn write(&self, results: &ResultsFormatted) -> Result<(), Box<dyn error::Error>>;
+}
+
+/// What to divide run times by when reporting, to compare benchmarks of different sizes.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NormalizeBy {
+    /// Nanoseconds per executed opcode, for runners that report opcode counts.
+    Opcode,
+}
+
+/// Statistic of each runner's benchmark times that orders the runner columns, fastest first.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortRunnersBy {
+    /// Sum of the weighted mean times, as in the sum row.
+    #[default]
+    Sum,
+    /// Geometric mean of the mean times, so that no single benchmark dominates.
+    Geomean,
+    /// Median of the mean times, for typical performance.
+    Median,
+    /// 95th percentile of the mean times, for the slowest benchmarks.
+    P95,
+}
+
+impl SortRunnersBy {
+    /// Aggregates a runner's mean times of the benchmarks in the summary. Only the sum is weighted,
+    /// from `weighted_times`, as scaling times by weights would skew the other statistics.
+    fn aggregate(self, times: &[Duration], weighted_times: &[Duration]) -> Option<Duration> {
+        match self {
+            Self::Sum => Some(stats::sum(weighted_times)),
+            Self::Geomean => stats::geomean(times),
+            Self::Median => stats::median(times),
+            Self::P95 => stats::percentile(times, 95.0),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct PrintOptions {
+    /// Formats to output results in, each to stdout or its own file.
+    pub outputs: Vec<FormatOutput>,
+    pub sparklines: bool,
+    /// Only print the summary rows, omitting per-benchmark rows.
+    pub only_summary: bool,
+    /// Results file to compare against, for formats that support it.
+    pub baseline: Option<PathBuf>,
+    /// Percentage slowdown against the baseline above which a cell counts as a regression.
+    pub regression_threshold_pct: f64,
+    /// Significance level of the Mann-Whitney U test between the run times of each cell and its
+    /// baseline, below which a change counts as a regression or improvement. Replaces
+    /// `regression_threshold_pct` if set.
+    pub significance_alpha: Option<f64>,
+    /// Additionally tabulate run times normalized by this, if set.
+    pub normalize_by: Option<NormalizeBy>,
+    /// Color relative times by how large they are, for terminals.
+    pub color: bool,
+    /// Swap the axes of tables, so that runners are rows and benchmarks are columns.
+    pub transpose: bool,
+    /// Fraction of each cell's leading run times to drop as warmup before computing statistics.
+    pub trim_fraction: f64,
+    /// Show the coefficient of variation of each cell's run times next to its mean.
+    pub show_cv: bool,
+    /// Coefficient of variation in percent above which cells are warned about as noisy.
+    pub warn_cv_pct: Option<f64>,
+    /// Runner-reported metrics to additionally tabulate the mean of, by name.
+    pub metrics: Vec<String>,
+    /// Number of decimals of rendered times and ratios. Times keep all their digits and ratios
+    /// have 3 decimals if `None`.
+    pub precision: Option<usize>,
+    /// Statistic that orders the runner columns of the results table.
+    pub sort_runners_by: SortRunnersBy,
+}
+
+impl PrintOptions {
+    /// Renders a time in automatically chosen units.
+    fn time(&self, time: Duration) -> String {
+        match self.precision {
+            Some(precision) => format!("{time:.precision$?}"),
+            None => format!("{time:?}"),
+        }
+    }
+
+    /// Renders a ratio between times, such as a relative time.
+    fn ratio(&self, ratio: f64) -> String {
+        format!("{ratio:.*}x", self.precision.unwrap_or(3))
+    }
+}
+
+const SPARKLINE_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const SPARKLINE_BINS: usize = 8;
+
+/// Renders a histogram of `times` as a unicode sparkline, one bar per bin between the min and max.
+fn sparkline(times: &[Duration]) -> String {
+    let (Some(min), Some(max)) = (times.iter().min(), times.iter().max()) else {
+        return String::new();
+    };
+    let bins = SPARKLINE_BINS.min(times.len());
+    let width = (*max - *min).as_secs_f64();
+
+    let mut counts = vec![0usize; bins];
+    for time in times {
+        let bin = if width == 0.0 {
+            0
+        } else {
+            (((*time - *min).as_secs_f64() / width) * bins as f64) as usize
+        };
+        counts[bin.min(bins - 1)] += 1;
+    }
+
+    let max_count = *counts.iter().max().unwrap_or(&1);
+    counts
+        .iter()
+        .map(|count| match count {
+            0 => ' ',
+            count => SPARKLINE_BARS[(count * SPARKLINE_BARS.len() - 1) / max_count],
+        })
+        .collect()
+}
+
+/// Renders a table whose first record is its header as markdown, swapping its rows and columns if
+/// `transpose` is set so that the header becomes the first column.
+fn markdown_table(builder: Builder, transpose: bool) -> Table {
+    let builder = if transpose {
+        let records: Vec<Vec<String>> = builder.into();
+        let columns = records.first().map_or(0, Vec::len);
+        Builder::from(
+            (0..columns)
+                .map(|i| records.iter().map(|record| record[i].clone()).collect())
+                .collect::<Vec<Vec<String>>>(),
+        )
+    } else {
+        builder
+    };
+    let mut table = builder.build();
+    table.with(Style::markdown());
+    table
+}
+
+/// ANSI 256-color codes from green to red, for relative times of up to 1.4x, 2x, 4x, 16x and more.
+const RELATIVE_COLORS: [(f64, u8); 5] = [
+    (1.4, 46),
+    (2.0, 118),
+    (4.0, 226),
+    (16.0, 208),
+    (f64::INFINITY, 196),
+];
+
+fn color_relative(cell: &str) -> String {
+    let Some(relative) = cell
+        .trim()
+        .strip_suffix('x')
+        .and_then(|value| value.parse::<f64>().ok())
+    else {
+        return cell.to_string();
+    };
+    let (_, color) = RELATIVE_COLORS
+        .iter()
+        .find(|(max, _)| relative < *max)
+        .expect("last color has no upper bound");
+    // Only color the value itself, keeping the padding so that columns stay aligned.
+    let start = cell.len() - cell.trim_start().len();
+    let end = cell.trim_end().len();
+    format!(
+        "{}\x1b[38;5;{color}m{}\x1b[0m{}",
+        &cell[..start],
+        &cell[start..end],
+        &cell[end..]
+    )
+}
+
+/// Colors the cells of the `**relative**` row of a rendered markdown table, or of its column if
+/// the table is transposed. Colors are added after rendering since escape codes would otherwise
+/// count towards the width of cells.
+fn color_relative_cells(table: &str) -> String {
+    const RELATIVE: &str = "**relative**";
+    let header = table
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .split('|')
+        .position(|cell| cell.trim() == RELATIVE);
+    table
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let cells = line.split('|').collect::<Vec<_>>();
+            let colored = match header {
+                // The header and separator rows of a transposed table have nothing to color.
+                Some(column) if i > 1 => cells
+                    .iter()
+                    .enumerate()
+                    .map(|(j, cell)| {
+                        if j == column {
+                            color_relative(cell)
+                        } else {
+                            cell.to_string()
+                        }
+                    })
+                    .collect::<Vec<_>>(),
+                None if cells.get(1).map(|cell| cell.trim()) == Some(RELATIVE) => cells
+                    .iter()
+                    .enumerate()
+                    .map(|(j, cell)| {
+                        if j > 1 {
+                            color_relative(cell)
+                        } else {
+                            cell.to_string()
+                        }
+                    })
+                    .collect::<Vec<_>>(),
+                _ => return line.to_string(),
+            };
+            colored.join("|")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn read_results(results_file_path: &Path) -> Result<ResultsFormatted, Box<dyn error::Error>> {
     log::info!(
         "reading and parsing results from {}...",
         results_file_path.to_string_lossy()
     );
-    let results =
-        serde_json::from_str::<ResultsFormatted>(&fs::read_to_string(results_file_path)?)?;
+    let contents = fs::read(results_file_path)?;
+    let binary = is_binary_results_file(results_file_path);
+    let version = if binary {
+        rmp_serde::from_slice::<SchemaVersion>(&contents)?
+    } else {
+        serde_json::from_slice::<SchemaVersion>(&contents)?
+    }
+    .schema_version;
+    if version > RESULTS_SCHEMA_VERSION {
+        return Err(format!(
+            "results file {} has schema version {version}, but this evm-bench only reads up to \
+             version {RESULTS_SCHEMA_VERSION}, upgrade it to read the file",
+            results_file_path.display()
+        )
+        .into());
+    }
+    let results = match (version == RESULTS_SCHEMA_VERSION, binary) {
+        (true, true) => rmp_serde::from_slice::<ResultsFormatted>(&contents)?,
+        (true, false) => serde_json::from_slice::<ResultsFormatted>(&contents)?,
+        // Older files of both encodings are migrated alike, as JSON values.
+        (false, binary) => {
+            let mut json = if binary {
+                rmp_serde::from_slice::<serde_json::Value>(&contents)?
+            } else {
+                serde_json::from_slice::<serde_json::Value>(&contents)?
+            };
+            migrate_results(&mut json, version);
+            serde_json::from_value::<ResultsFormatted>(json)?
+        }
+    };
     log::debug!(
         "read and parsed results from {}",
         results_file_path.to_string_lossy()
     );
+    Ok(results)
+}
 
+/// Just the schema version of a results file, read first to tell whether the file needs migrating.
+#[derive(Deserialize)]
+struct SchemaVersion {
+    #[serde(default)]
+    schema_version: u32,
+}
+
+/// Migrates results read in the format of schema `version` to the current format, one version at
+/// a time.
+fn migrate_results(json: &mut serde_json::Value, version: u32) {
+    for version in version..RESULTS_SCHEMA_VERSION {
+        log::debug!("migrating results from schema version {version}");
+        match version {
+            // Unversioned files only lack fields that have defaults.
+            0 => {}
+            _ => unreachable!("no migration from results schema version {version}"),
+        }
+    }
+    json["schema_version"] = RESULTS_SCHEMA_VERSION.into();
+}
+
+/// Drops the leading `fraction` of each cell's runs with their per-run values, rounding down but
+/// always keeping at least one. This only affects what is reported, the results file keeps all
+/// samples.
+fn trim_leading_runs(results: &mut ResultsFormatted, fraction: f64) {
+    if fraction <= 0.0 {
+        return;
+    }
+    for run in results.runs.values_mut().flat_map(|runs| runs.values_mut()) {
+        let len = run.run_times.len();
+        let trim = ((len as f64 * fraction) as usize).min(len.saturating_sub(1));
+        run.retain_runs(&(0..len).map(|i| i >= trim).collect::<Vec<_>>());
+    }
+}
+
+/// Mean of the run times, or `None` if there are none.
+fn mean_run_time(run: &RunResult) -> Option<Duration> {
+    stats::mean(&run.run_times)
+}
+
+fn labels_line(results: &ResultsFormatted) -> Option<String> {
+    if results.labels.is_empty() {
+        return None;
+    }
+    let mut labels = results
+        .labels
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<_>>();
+    labels.sort();
+    Some(format!("**labels**: {}", labels.join(", ")))
+}
+
+/// Weight of a benchmark in sums over benchmarks, 1 for benchmarks not in the results.
+fn benchmark_weight(results: &ResultsFormatted, benchmark_name: &str) -> f64 {
+    results
+        .benchmarks
+        .get(benchmark_name)
+        .map_or(1.0, |benchmark| benchmark.weight.0)
+}
+
+fn results_table(
+    results: &ResultsFormatted,
+    options: &PrintOptions,
+) -> Result<Table, Box<dyn error::Error>> {
+    Ok(markdown_table(
+        results_builder(results, options)?,
+        options.transpose,
+    ))
+}
+
+/// Whether the runner measures the harness rather than an EVM, like the nop runner, so that it
+/// shouldn't be what other runners are compared against.
+fn is_calibration(results: &ResultsFormatted, runner_name: &str) -> bool {
+    results
+        .runners
+        .get(runner_name)
+        .is_some_and(|runner| runner.category.as_deref() == Some("calibration"))
+}
+
+/// Shown instead of the main results table when no benchmark ran successfully on any runner.
+const NO_SUCCESSFUL_RUNS: &str = "No successful runs to report.";
+
+/// Whether any benchmark ran successfully on any runner, so that there is a results table to show.
+/// Warns if not.
+fn has_successful_runs(results: &ResultsFormatted) -> bool {
+    let any = results
+        .runs
+        .values()
+        .flat_map(|runs| runs.values())
+        .any(|run| !run.run_times.is_empty());
+    if !any {
+        log::warn!("no benchmark ran successfully on any runner");
+    }
+    any
+}
+
+/// Builds the main results table, with a header of runner names, the sum and relative rows, and a
+/// row per benchmark.
+fn results_builder(
+    results: &ResultsFormatted,
+    options: &PrintOptions,
+) -> Result<Builder, Box<dyn error::Error>> {
     let mut runner_names: Vec<_> = results.runners.keys().cloned().collect();
     runner_names.sort();
 
-    let mut runs = results.runs.into_iter().collect::<Vec<_>>();
-    runs.sort_by_key(|(b, _)| b.clone());
+    let mut runs = results.runs.iter().collect::<Vec<_>>();
+    runs.sort_by_key(|(b, _)| *b);
 
     let mut runner_times = HashMap::<String, Vec<Duration>>::new();
-    for (_, benchmark_runs) in runs.iter() {
+    let mut weighted_runner_times = HashMap::<String, Vec<Duration>>::new();
+    for (benchmark_name, benchmark_runs) in runs.iter() {
+        // Benchmarks weighted 0 are left out of the summary altogether.
+        let weight = benchmark_weight(results, benchmark_name);
+        if weight == 0.0 {
+            continue;
+        }
         runner_names.iter().for_each(|runner_name| {
-            let run = benchmark_runs.get(runner_name).unwrap();
-            let avg_run_time = run
-                .run_times
-                .iter()
-                .fold(Duration::ZERO, |a, v| a + v.clone())
-                .div_f64(run.run_times.len() as f64);
-            runner_times
-                .entry(runner_name.clone())
-                .or_default()
-                .push(avg_run_time);
+            // Runners that failed a benchmark have no run for it.
+            if let Some(avg_run_time) = benchmark_runs.get(runner_name).and_then(mean_run_time) {
+                runner_times
+                    .entry(runner_name.clone())
+                    .or_default()
+                    .push(avg_run_time);
+                weighted_runner_times
+                    .entry(runner_name.clone())
+                    .or_default()
+                    .push(avg_run_time.mul_f64(weight));
+            }
         });
     }
 
+    // Runners without any times to aggregate come last.
     runner_names.sort_by_key(|runner_name| {
-        runner_times
-            .get(runner_name)
-            .unwrap()
-            .into_iter()
-            .fold(Duration::ZERO, |a, v| a + v.clone())
+        let aggregate = runner_times.get(runner_name).and_then(|times| {
+            options
+                .sort_runners_by
+                .aggregate(times, &weighted_runner_times[runner_name])
+        });
+        (aggregate.is_none(), aggregate)
     });
 
     let mut builder = Builder::default();
-    let average_runner_times = runner_times
+    let average_runner_times = weighted_runner_times
         .iter()
-        .map(|(name, times)| (name.clone(), times.iter().sum::<Duration>()))
+        .map(|(name, times)| (name.clone(), stats::sum(times)))
         .collect::<HashMap<String, Duration>>();
     let mut record = vec!["**sum**".to_string()];
     record.extend(
         runner_names
             .iter()
             .map(|runner_name| average_runner_times.get(runner_name))
-            .map(|val| Some(format!("{:?}", val?)))
+            .map(|val| Some(options.time(*val?)))
             .map(|s| s.unwrap_or_default()),
     );
     builder.push_record(record);
+    // Calibration runners and runners too fast to time would make every other runner infinitely
+    // slower, so the relative row is based on the fastest of the rest.
     let min_runner_time = average_runner_times
-        .values()
-        .min()
-        .ok_or("could not get minimum runner time")?;
+        .iter()
+        .filter(|(name, time)| !time.is_zero() && !is_calibration(results, name))
+        .map(|(_, time)| time)
+        .min();
     let mut record = vec!["**relative**".to_string()];
     record.extend(
         runner_names
@@ -150,33 +812,42 @@ pub fn print_results(results_file_path: &Path) -> Result<(), Box<dyn error::Erro
             .map(|runner_name| {
                 Some(
                     average_runner_times.get(runner_name)?.as_secs_f64()
-                        / min_runner_time.as_secs_f64(),
+                        / min_runner_time?.as_secs_f64(),
                 )
             })
-            .map(|val| Some(format!("{:.3?}x", val?)))
+            .map(|val| Some(options.ratio(val?)))
             .map(|s| s.unwrap_or_default()),
     );
     builder.push_record(record);
 
-    for (benchmark_name, benchmark_runs) in runs.iter() {
+    let detail_runs = if options.only_summary {
+        &runs[..0]
+    } else {
+        &runs[..]
+    };
+    for (benchmark_name, benchmark_runs) in detail_runs {
         let vals = runner_names.iter().map(|runner_name| {
             let run = benchmark_runs.get(runner_name)?;
-            let avg_run_time = run
-                .run_times
-                .iter()
-                .fold(Duration::ZERO, |a, v| a + v.clone())
-                .div_f64(run.run_times.len() as f64);
-            runner_times
-                .entry(runner_name.clone())
-                .or_default()
-                .push(avg_run_time);
-            Some(avg_run_time)
+            let avg_run_time = mean_run_time(run)?;
+            Some((avg_run_time, &run.run_times))
         });
 
-        let mut record = vec![benchmark_name.clone()];
+        let mut record = vec![benchmark_name.to_string()];
         record.extend(
-            vals.map(|val| Some(format!("{:?}", val?)))
-                .map(|s| s.unwrap_or_default()),
+            vals.map(|val| {
+                let (avg_run_time, run_times) = val?;
+                let mut cell = options.time(avg_run_time);
+                if let Some(cv) =
+                    stats::coefficient_of_variation(run_times).filter(|_| options.show_cv)
+                {
+                    cell.push_str(&format!(" ±{:.1}%", cv * 100.0));
+                }
+                if options.sparklines {
+                    cell.push_str(&format!(" {}", sparkline(run_times)));
+                }
+                Some(cell)
+            })
+            .map(|s| s.unwrap_or_default()),
         );
         builder.push_record(record);
     }
@@ -185,9 +856,1326 @@ pub fn print_results(results_file_path: &Path) -> Result<(), Box<dyn error::Erro
     columns.extend(runner_names);
     builder.set_header(columns);
 
-    let mut table = builder.build();
-    table.with(Style::markdown());
-    println!("{}", table);
+    Ok(builder)
+}
+
+/// Tabulates a cell per benchmark and runner, formatted from the runner's results of the benchmark
+/// by `cell`, under a `heading` for the table. Cells `cell` gives nothing for are left blank, and
+/// `None` is returned if it gives nothing for any.
+fn per_cell_table(
+    results: &ResultsFormatted,
+    options: &PrintOptions,
+    heading: &str,
+    cell: impl Fn(&RunResult) -> Option<String>,
+) -> Option<Table> {
+    let mut runner_names: Vec<_> = results.runners.keys().cloned().collect();
+    runner_names.sort();
+    let mut runs = results.runs.iter().collect::<Vec<_>>();
+    runs.sort_by_key(|(b, _)| *b);
+
+    let mut any = false;
+    let mut builder = Builder::default();
+    for (benchmark_name, benchmark_runs) in runs {
+        let mut record = vec![benchmark_name.to_string()];
+        record.extend(runner_names.iter().map(|runner_name| {
+            let value = benchmark_runs.get(runner_name).and_then(&cell);
+            any |= value.is_some();
+            value.unwrap_or_default()
+        }));
+        builder.push_record(record);
+    }
+    if !any {
+        return None;
+    }
+
+    let mut columns = vec![heading.to_owned()];
+    columns.extend(runner_names);
+    builder.set_header(columns);
+
+    let table = markdown_table(builder, options.transpose);
+    Some(table)
+}
+
+/// Tabulates mean contract deployment times. Returns `None` if no runner reported any.
+fn deploy_table(results: &ResultsFormatted, options: &PrintOptions) -> Option<Table> {
+    per_cell_table(results, options, "deploy", |run| {
+        Some(options.time(stats::mean(&run.deploy_times)?))
+    })
+}
+
+/// Tabulates mean bytecode analysis times, with the share of a cold call they make up, taking a
+/// cold call as the analysis followed by a mean run. Returns `None` if no runner reported any.
+fn analysis_table(results: &ResultsFormatted, options: &PrintOptions) -> Option<Table> {
+    per_cell_table(results, options, "analysis", |run| {
+        let analysis = stats::mean(&run.analysis_times)?;
+        let cold = analysis + mean_run_time(run)?;
+        if cold.is_zero() {
+            return Some(options.time(analysis));
+        }
+        Some(format!(
+            "{} ({:.1}% of a cold call)",
+            options.time(analysis),
+            analysis.as_secs_f64() / cold.as_secs_f64() * 100.0
+        ))
+    })
+}
+
+/// Tabulates the steady-state mean that runners measured after their own warmup, with its
+/// difference from the mean of all runs as recorded. Returns `None` if no runner reported any.
+fn steady_state_table(results: &ResultsFormatted, options: &PrintOptions) -> Option<Table> {
+    per_cell_table(results, options, "steady state", |run| {
+        let steady_state = run.steady_state.as_ref()?;
+        let raw_mean = mean_run_time(run)?;
+        let mean = Duration::from_secs_f64(steady_state.mean_ms / 1e3);
+        let stddev = Duration::from_secs_f64(steady_state.stddev_ms / 1e3);
+        if raw_mean.is_zero() {
+            return Some(format!("{} ±{}", options.time(mean), options.time(stddev)));
+        }
+        Some(format!(
+            "{} ±{} ({:+.1}% vs all runs)",
+            options.time(mean),
+            options.time(stddev),
+            (mean.as_secs_f64() / raw_mean.as_secs_f64() - 1.0) * 100.0
+        ))
+    })
+}
+
+/// Tabulates throughput in MGas/s, as the total gas over the total time of each cell's runs.
+/// Returns `None` if no runner reported any gas.
+fn throughput_table(results: &ResultsFormatted, options: &PrintOptions) -> Option<Table> {
+    per_cell_table(results, options, "throughput", |run| {
+        let time = stats::sum(&run.run_times);
+        if run.gas_used.is_empty() || time.is_zero() {
+            return None;
+        }
+        Some(format!(
+            "{:.2} MGas/s",
+            run.gas_used.iter().sum::<u64>() as f64 / time.as_secs_f64() / 1e6
+        ))
+    })
+}
+
+/// Tabulates mean run times divided by the number of opcodes executed per run, falling back to the
+/// raw mean for cells whose runner did not report an opcode count. Returns `None` if no runner
+/// reported any.
+fn opcode_table(results: &ResultsFormatted, options: &PrintOptions) -> Option<Table> {
+    let mut runner_names: Vec<_> = results.runners.keys().cloned().collect();
+    runner_names.sort();
+    let mut runs = results.runs.iter().collect::<Vec<_>>();
+    runs.sort_by_key(|(b, _)| *b);
+
+    let ns_per_opcode = |run: &RunResult| {
+        let opcode_count = run.opcode_count.filter(|count| *count > 0)?;
+        Some(mean_run_time(run)?.as_nanos() as f64 / opcode_count as f64)
+    };
+
+    let mut runner_ns_per_opcode = HashMap::<&str, Vec<f64>>::new();
+    let mut builder = Builder::default();
+    for (benchmark_name, benchmark_runs) in &runs {
+        let mut record = vec![benchmark_name.to_string()];
+        record.extend(runner_names.iter().map(|runner_name| {
+            let Some(run) = benchmark_runs.get(runner_name) else {
+                return String::new();
+            };
+            match (ns_per_opcode(run), mean_run_time(run)) {
+                (Some(ns), _) => {
+                    runner_ns_per_opcode
+                        .entry(runner_name)
+                        .or_default()
+                        .push(ns);
+                    format!("{ns:.2}ns/op")
+                }
+                (None, Some(mean)) => options.time(mean),
+                (None, None) => String::new(),
+            }
+        }));
+        if !options.only_summary {
+            builder.push_record(record);
+        }
+    }
+    if runner_ns_per_opcode.is_empty() {
+        return None;
+    }
+
+    let mut record = vec!["**mean**".to_string()];
+    record.extend(runner_names.iter().map(|runner_name| {
+        match runner_ns_per_opcode.get(runner_name.as_str()) {
+            Some(values) => format!(
+                "{:.2}ns/op",
+                values.iter().sum::<f64>() / values.len() as f64
+            ),
+            None => String::new(),
+        }
+    }));
+    builder.insert_record(0, record);
+
+    let mut columns = vec!["per opcode".to_owned()];
+    columns.extend(runner_names);
+    builder.set_header(columns);
+
+    let table = markdown_table(builder, options.transpose);
+    Some(table)
+}
+
+/// Renders the table of times normalized by `options.normalize_by`, warning and returning `None`
+/// if nothing could be normalized so that only the raw times are shown.
+fn normalized_table(results: &ResultsFormatted, options: &PrintOptions) -> Option<Table> {
+    match options.normalize_by? {
+        NormalizeBy::Opcode => {
+            let table = opcode_table(results, options);
+            if table.is_none() {
+                log::warn!("no runner reported opcode counts, only showing raw times");
+            }
+            table
+        }
+    }
+}
+
+/// Tabulates the mean of a runner-reported metric over the runs of each benchmark. Returns `None`
+/// if no runner reported the metric.
+fn metric_table(results: &ResultsFormatted, options: &PrintOptions, metric: &str) -> Option<Table> {
+    per_cell_table(results, options, metric, |run| {
+        let values = run
+            .metrics
+            .get(metric)
+            .filter(|values| !values.is_empty())?;
+        Some(format!(
+            "{:.3}",
+            values.iter().sum::<f64>() / values.len() as f64
+        ))
+    })
+}
+
+/// Summarizes results by runner category, averaging the mean run times of all runners in each
+/// category. Returns `None` if no runner has a category.
+fn category_table(results: &ResultsFormatted, options: &PrintOptions) -> Option<Table> {
+    let mut categories = HashMap::<&str, Vec<&str>>::new();
+    for (runner_name, runner) in &results.runners {
+        if let Some(category) = &runner.category {
+            categories.entry(category).or_default().push(runner_name);
+        }
+    }
+    if categories.is_empty() {
+        return None;
+    }
+    let mut category_names = categories.keys().copied().collect::<Vec<_>>();
+    category_names.sort();
+
+    let mut benchmark_names = results.runs.keys().collect::<Vec<_>>();
+    benchmark_names.sort();
+
+    let category_times = benchmark_names
+        .iter()
+        .map(|benchmark_name| {
+            let runs = &results.runs[*benchmark_name];
+            category_names
+                .iter()
+                .map(|category| {
+                    let times = categories[category]
+                        .iter()
+                        .filter_map(|runner_name| runs.get(*runner_name))
+                        .filter_map(mean_run_time)
+                        .collect::<Vec<_>>();
+                    stats::mean(&times)
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let sums = (0..category_names.len())
+        .map(|i| {
+            category_times
+                .iter()
+                .zip(&benchmark_names)
+                .filter_map(|(times, benchmark_name)| {
+                    Some(times[i]?.mul_f64(benchmark_weight(results, benchmark_name)))
+                })
+                .sum::<Duration>()
+        })
+        .collect::<Vec<_>>();
+    let min_sum = sums.iter().filter(|sum| !sum.is_zero()).min().copied();
+
+    let mut builder = Builder::default();
+    let mut record = vec!["**sum**".to_string()];
+    record.extend(sums.iter().map(|sum| options.time(*sum)));
+    builder.push_record(record);
+    let mut record = vec!["**relative**".to_string()];
+    record.extend(sums.iter().map(|sum| match min_sum {
+        Some(min_sum) => options.ratio(sum.as_secs_f64() / min_sum.as_secs_f64()),
+        None => String::new(),
+    }));
+    builder.push_record(record);
+    let detail_count = if options.only_summary {
+        0
+    } else {
+        benchmark_names.len()
+    };
+    for (benchmark_name, times) in benchmark_names
+        .iter()
+        .zip(&category_times)
+        .take(detail_count)
+    {
+        let mut record = vec![benchmark_name.to_string()];
+        record.extend(times.iter().map(|time| match time {
+            Some(time) => options.time(*time),
+            None => String::new(),
+        }));
+        builder.push_record(record);
+    }
+
+    let mut columns = vec!["".to_owned()];
+    columns.extend(category_names.iter().map(|category| category.to_string()));
+    builder.set_header(columns);
+
+    let table = markdown_table(builder, options.transpose);
+    Some(table)
+}
+
+/// Renders a per-cell comparison against `baseline`, followed by the full table in a collapsed
+/// `<details>` block, for posting as a PR comment.
+fn github_markdown(
+    results: &ResultsFormatted,
+    baseline: Option<&ResultsFormatted>,
+    options: &PrintOptions,
+) -> Result<String, Box<dyn error::Error>> {
+    let mut out = String::from("### evm-bench results\n\n");
+    if let Some(labels) = labels_line(results) {
+        out.push_str(&format!("{labels}\n\n"));
+    }
+
+    match baseline {
+        Some(baseline) => {
+            let mut runner_names: Vec<_> = results.runners.keys().cloned().collect();
+            runner_names.sort();
+            let mut benchmark_names: Vec<_> = results.runs.keys().cloned().collect();
+            benchmark_names.sort();
+
+            let mut regressions = 0;
+            let mut builder = Builder::default();
+            for benchmark_name in &benchmark_names {
+                let mut record = vec![benchmark_name.clone()];
+                for runner_name in &runner_names {
+                    let current_run = results
+                        .runs
+                        .get(benchmark_name)
+                        .and_then(|runs| runs.get(runner_name));
+                    let previous_run = baseline
+                        .runs
+                        .get(benchmark_name)
+                        .and_then(|runs| runs.get(runner_name));
+                    let current = current_run.and_then(mean_run_time);
+                    let previous = previous_run.and_then(mean_run_time);
+                    record.push(match (current, previous) {
+                        (Some(current), Some(previous)) => {
+                            let change_pct =
+                                (current.as_secs_f64() / previous.as_secs_f64() - 1.0) * 100.0;
+                            let significance = options.significance_alpha.and_then(|alpha| {
+                                let p = stats::mann_whitney_p(
+                                    &current_run?.run_times,
+                                    &previous_run?.run_times,
+                                )?;
+                                Some((p, p < alpha))
+                            });
+                            match significance {
+                                Some((p, true)) if change_pct > 0.0 => {
+                                    regressions += 1;
+                                    format!(
+                                        "⚠️ {} (**{change_pct:+.1}%**, p={p:.3})",
+                                        options.time(current)
+                                    )
+                                }
+                                Some((p, true)) => format!(
+                                    "🚀 {} (**{change_pct:+.1}%**, p={p:.3})",
+                                    options.time(current)
+                                ),
+                                Some((_, false)) => {
+                                    format!("✅ {} ({change_pct:+.1}%)", options.time(current))
+                                }
+                                None if change_pct > options.regression_threshold_pct => {
+                                    regressions += 1;
+                                    format!("⚠️ {} (**{change_pct:+.1}%**)", options.time(current))
+                                }
+                                None => {
+                                    format!("✅ {} ({change_pct:+.1}%)", options.time(current))
+                                }
+                            }
+                        }
+                        (Some(current), None) => format!("{} (new)", options.time(current)),
+                        (None, _) => String::new(),
+                    });
+                }
+                builder.push_record(record);
+            }
+            let mut columns = vec!["".to_owned()];
+            columns.extend(runner_names);
+            builder.set_header(columns);
+            let table = markdown_table(builder, options.transpose);
+
+            out.push_str(&match (regressions, options.significance_alpha) {
+                (0, _) => "✅ no regressions against the baseline\n\n".to_string(),
+                (n, Some(alpha)) => {
+                    format!("⚠️ {n} regressions significant at α={alpha} against the baseline\n\n")
+                }
+                (n, None) => format!(
+                    "⚠️ {n} regressions of more than {}% against the baseline\n\n",
+                    options.regression_threshold_pct
+                ),
+            });
+            out.push_str(&format!("{table}\n\n"));
+        }
+        None => log::warn!("no baseline given, only rendering the full results table"),
+    }
+
+    let table = if has_successful_runs(results) {
+        results_table(results, options)?.to_string()
+    } else {
+        NO_SUCCESSFUL_RUNS.to_string()
+    };
+    out.push_str(&format!(
+        "<details>\n<summary>Full results</summary>\n\n{table}\n\n"
+    ));
+    if let Some(table) = deploy_table(results, options).filter(|_| !options.only_summary) {
+        out.push_str(&format!("{table}\n\n"));
+    }
+    if let Some(table) = analysis_table(results, options).filter(|_| !options.only_summary) {
+        out.push_str(&format!("{table}\n\n"));
+    }
+    if let Some(table) = steady_state_table(results, options).filter(|_| !options.only_summary) {
+        out.push_str(&format!("{table}\n\n"));
+    }
+    if let Some(table) = throughput_table(results, options).filter(|_| !options.only_summary) {
+        out.push_str(&format!("{table}\n\n"));
+    }
+    if let Some(table) = normalized_table(results, options) {
+        out.push_str(&format!("{table}\n\n"));
+    }
+    if let Some(table) = category_table(results, options) {
+        out.push_str(&format!("{table}\n\n"));
+    }
+    out.push_str("</details>");
+    Ok(out)
+}
+
+/// Converts results to Bencher Metric Format (BMF) JSON.
+///
+/// Each benchmark and runner pair becomes a BMF benchmark named `<benchmark>/<runner>`, with a
+/// `latency` measure in nanoseconds whose value is the mean run time and whose lower and upper
+/// values are the fastest and slowest runs.
+fn bmf(results: &ResultsFormatted) -> serde_json::Value {
+    let mut metrics = serde_json::Map::new();
+    for (benchmark_name, benchmark_runs) in &results.runs {
+        for (runner_name, run) in benchmark_runs {
+            let (Some(mean), Some(min), Some(max)) = (
+                mean_run_time(run),
+                run.run_times.iter().min(),
+                run.run_times.iter().max(),
+            ) else {
+                continue;
+            };
+            metrics.insert(
+                format!("{benchmark_name}/{runner_name}"),
+                serde_json::json!({
+                    "latency": {
+                        "value": mean.as_nanos() as f64,
+                        "lower_value": min.as_nanos() as f64,
+                        "upper_value": max.as_nanos() as f64,
+                    }
+                }),
+            );
+        }
+    }
+    serde_json::Value::Object(metrics)
+}
+
+/// Warns about each cell whose run times vary by more than `threshold_pct` of their mean.
+fn warn_noisy_cells(results: &ResultsFormatted, threshold_pct: f64) {
+    let mut noisy = results
+        .runs
+        .iter()
+        .flat_map(|(benchmark_name, benchmark_runs)| {
+            benchmark_runs.iter().filter_map(move |(runner_name, run)| {
+                let cv_pct = stats::coefficient_of_variation(&run.run_times)? * 100.0;
+                (cv_pct > threshold_pct).then_some((benchmark_name, runner_name, cv_pct))
+            })
+        })
+        .collect::<Vec<_>>();
+    noisy.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+    for (benchmark_name, runner_name, cv_pct) in noisy {
+        log::warn!(
+            "benchmark {benchmark_name} on runner {runner_name} is noisy, varying by {cv_pct:.1}% \
+             of its mean (more than {threshold_pct}%), consider re-running with more runs or on a \
+             quieter machine"
+        );
+    }
+}
+
+/// Prints the mean time of a benchmark on a runner in each results file in `results_path`, oldest
+/// first, as a markdown table or as CSV for plotting. Files without results for the pair are
+/// skipped. With `by_commit`, prints a row per git commit the results were recorded at instead,
+/// pooling the runs of all files of a commit and skipping files without one. With `half_life`, also
+/// prints a mean of the runs of each row and all earlier rows, weighting each run by half for
+/// every `half_life` its row is older.
+pub fn print_trend(
+    results_path: &Path,
+    benchmark_name: &str,
+    runner_name: &str,
+    csv: bool,
+    trim_fraction: f64,
+    by_commit: bool,
+    half_life: Option<Duration>,
+) -> Result<(), Box<dyn error::Error>> {
+    let mut paths = fs::read_dir(results_path)
+        .map_err(|e| format!("could not read results in {}: {e}", results_path.display()))?
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            Some((results_file_time(&path)?, path))
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    // Each point is the time of its first results file, its commit, and its run times.
+    let mut points: Vec<(_, Option<String>, Vec<Duration>)> = Vec::new();
+    for (time, path) in paths {
+        let mut results = read_results(&path)?;
+        trim_leading_runs(&mut results, trim_fraction);
+        let git_rev = results
+            .environment
+            .as_ref()
+            .and_then(|environment| environment.git_rev.clone());
+        let Some(run) = results
+            .runs
+            .get(benchmark_name)
+            .and_then(|runs| runs.get(runner_name))
+        else {
+            log::debug!(
+                "skipping {}, which has no results for {benchmark_name} on {runner_name}",
+                path.display()
+            );
+            continue;
+        };
+        if run.run_times.is_empty() {
+            continue;
+        }
+        if !by_commit {
+            points.push((time, git_rev, run.run_times.clone()));
+            continue;
+        }
+        let Some(git_rev) = git_rev else {
+            log::debug!("skipping {}, which has no git commit", path.display());
+            continue;
+        };
+        match points
+            .iter_mut()
+            .find(|(_, rev, _)| rev.as_ref() == Some(&git_rev))
+        {
+            Some((_, _, run_times)) => run_times.extend(&run.run_times),
+            None => points.push((time, Some(git_rev), run.run_times.clone())),
+        }
+    }
+    if points.is_empty() {
+        return Err(format!(
+            "no results for benchmark {benchmark_name} on runner {runner_name}{} in {}",
+            if by_commit { " with a git commit" } else { "" },
+            results_path.display()
+        )
+        .into());
+    }
+
+    let show_commits = points.iter().any(|(_, git_rev, _)| git_rev.is_some());
+    let decayed_means = points
+        .iter()
+        .map(|(time, _, _)| {
+            let half_life = half_life?;
+            let weighted = points
+                .iter()
+                .take_while(|(earlier, _, _)| earlier <= time)
+                .flat_map(|(earlier, _, run_times)| {
+                    let age = (*time - *earlier).to_std().unwrap_or_default();
+                    let weight = 0.5f64.powf(age.as_secs_f64() / half_life.as_secs_f64());
+                    run_times.iter().map(move |run_time| (*run_time, weight))
+                })
+                .collect::<Vec<_>>();
+            stats::weighted_mean(&weighted)
+        })
+        .collect::<Vec<_>>();
+    let points = points.into_iter().zip(decayed_means).filter_map(
+        |((time, git_rev, run_times), decayed_mean)| {
+            Some((
+                time,
+                git_rev,
+                stats::mean(&run_times)?,
+                run_times.len(),
+                decayed_mean,
+            ))
+        },
+    );
+    if csv {
+        // The commit comes last, so that columns of scripts written before it keep their place,
+        // followed by the weighted mean only when asked for.
+        print!("time,mean_ns,num_runs,git_rev");
+        if half_life.is_some() {
+            print!(",weighted_mean_ns");
+        }
+        println!();
+        for (time, git_rev, mean, num_runs, decayed_mean) in points {
+            print!(
+                "{},{},{num_runs},{}",
+                time.to_rfc3339(),
+                mean.as_nanos(),
+                git_rev.unwrap_or_default()
+            );
+            if half_life.is_some() {
+                print!(",{}", decayed_mean.unwrap_or_default().as_nanos());
+            }
+            println!();
+        }
+    } else {
+        let mut builder = Builder::default();
+        for (time, git_rev, mean, num_runs, decayed_mean) in points {
+            let mut record = vec![time.to_rfc3339()];
+            if show_commits {
+                record.push(git_rev.unwrap_or_default());
+            }
+            record.extend([format!("{mean:?}"), num_runs.to_string()]);
+            if half_life.is_some() {
+                record.push(
+                    decayed_mean
+                        .map(|decayed_mean| format!("{decayed_mean:?}"))
+                        .unwrap_or_default(),
+                );
+            }
+            builder.push_record(record);
+        }
+        let mut header = vec![format!("{benchmark_name} on {runner_name}")];
+        if show_commits {
+            header.push("commit".to_string());
+        }
+        header.extend(["mean".to_string(), "runs".to_string()]);
+        if half_life.is_some() {
+            header.push("weighted mean".to_string());
+        }
+        builder.set_header(header);
+        println!("{}", markdown_table(builder, false));
+    }
+    Ok(())
+}
+
+/// Prints the mean time of each benchmark on runners `base` and `other` from the results in
+/// `results_file_path`, with how many times slower `other` was, and the geometric mean of those
+/// ratios over all benchmarks both runners ran. Errors if either runner is missing from the results.
+pub fn print_runner_diff(
+    results_file_path: &Path,
+    base: &str,
+    other: &str,
+    options: &PrintOptions,
+) -> Result<(), Box<dyn error::Error>> {
+    let mut results = read_results(results_file_path)?;
+    trim_leading_runs(&mut results, options.trim_fraction);
+    for runner_name in [base, other] {
+        if !results.runners.contains_key(runner_name) {
+            let mut runner_names = results.runners.keys().cloned().collect::<Vec<_>>();
+            runner_names.sort();
+            return Err(format!(
+                "runner {runner_name} is not in {}, which has runners {}",
+                results_file_path.display(),
+                runner_names.join(", ")
+            )
+            .into());
+        }
+    }
+
+    let mut benchmark_names = results.runs.keys().collect::<Vec<_>>();
+    benchmark_names.sort();
+    let mut builder = Builder::default();
+    let mut ratios = Vec::new();
+    for benchmark_name in benchmark_names {
+        let runs = &results.runs[benchmark_name];
+        let mean = |runner_name| runs.get(runner_name).and_then(mean_run_time);
+        let (Some(base_mean), Some(other_mean)) = (mean(base), mean(other)) else {
+            log::debug!("skipping {benchmark_name}, which only one of the runners ran");
+            continue;
+        };
+        let ratio = other_mean.as_secs_f64() / base_mean.as_secs_f64();
+        builder.push_record([
+            benchmark_name.clone(),
+            options.time(base_mean),
+            options.time(other_mean),
+            options.ratio(ratio),
+        ]);
+        if ratio.is_finite() && ratio > 0.0 {
+            ratios.push(ratio);
+        }
+    }
+    if ratios.is_empty() {
+        return Err(format!("runners {base} and {other} ran no benchmarks in common").into());
+    }
+    let geomean = (ratios.iter().map(|ratio| ratio.ln()).sum::<f64>() / ratios.len() as f64).exp();
+    builder.push_record([
+        "**geomean**".to_string(),
+        String::new(),
+        String::new(),
+        format!("**{}**", options.ratio(geomean)),
+    ]);
+    builder.set_header([
+        String::new(),
+        base.to_string(),
+        other.to_string(),
+        format!("{other} / {base}"),
+    ]);
+    println!("{}", markdown_table(builder, options.transpose));
+    Ok(())
+}
+
+/// Renders the markdown tables of all results, as printed by default.
+fn markdown(
+    results: &ResultsFormatted,
+    options: &PrintOptions,
+    color: bool,
+) -> Result<String, Box<dyn error::Error>> {
+    let color = |table: Table| {
+        if color {
+            color_relative_cells(&table.to_string())
+        } else {
+            table.to_string()
+        }
+    };
+
+    let mut out = String::new();
+    if let Some(labels) = labels_line(results) {
+        out.push_str(&format!("{labels}\n\n"));
+    }
+    if !has_successful_runs(results) {
+        out.push_str(NO_SUCCESSFUL_RUNS);
+        return Ok(out);
+    }
+    out.push_str(&color(results_table(results, options)?));
+    if let Some(table) = deploy_table(results, options).filter(|_| !options.only_summary) {
+        out.push_str(&format!("\n\n{table}"));
+    }
+    if let Some(table) = analysis_table(results, options).filter(|_| !options.only_summary) {
+        out.push_str(&format!("\n\n{table}"));
+    }
+    if let Some(table) = steady_state_table(results, options).filter(|_| !options.only_summary) {
+        out.push_str(&format!("\n\n{table}"));
+    }
+    if let Some(table) = throughput_table(results, options).filter(|_| !options.only_summary) {
+        out.push_str(&format!("\n\n{table}"));
+    }
+    if let Some(table) = normalized_table(results, options) {
+        out.push_str(&format!("\n\n{table}"));
+    }
+    for metric in &options.metrics {
+        match metric_table(results, options, metric) {
+            Some(table) => out.push_str(&format!("\n\n{table}")),
+            None => log::warn!("no runner reported metric {metric}"),
+        }
+    }
+    if let Some(table) = category_table(results, options) {
+        out.push_str(&format!("\n\n{}", color(table)));
+    }
+    Ok(out)
+}
+
+#[derive(Serialize)]
+struct DocsPreamble<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    environment: Option<&'a Environment>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    labels: BTreeMap<&'a String, &'a String>,
+}
+
+/// Renders the results table for static docs, with the environment and labels the results were
+/// recorded with as a fenced JSON preamble.
+fn docs_markdown(
+    results: &ResultsFormatted,
+    options: &PrintOptions,
+) -> Result<String, Box<dyn error::Error>> {
+    let preamble = DocsPreamble {
+        environment: results.environment.as_ref(),
+        labels: results.labels.iter().collect(),
+    };
+    if preamble.environment.is_none() {
+        log::warn!("results do not record their environment, so it is missing from the output");
+    }
+    let table = if has_successful_runs(results) {
+        results_table(results, options)?.to_string()
+    } else {
+        NO_SUCCESSFUL_RUNS.to_string()
+    };
+    Ok(format!(
+        "```json\n{}\n```\n\n{table}",
+        serde_json::to_string_pretty(&preamble)?
+    ))
+}
+
+/// Converts results to CSV, with one row per benchmark and runner pair, sorted by both.
+fn csv(results: &ResultsFormatted) -> String {
+    let escape = |field: &str| {
+        if field.contains([',', '"', '\n']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_string()
+        }
+    };
+
+    let mut rows = Vec::new();
+    for (benchmark_name, benchmark_runs) in &results.runs {
+        for (runner_name, run) in benchmark_runs {
+            let (Some(mean), Some(min), Some(max)) = (
+                mean_run_time(run),
+                run.run_times.iter().min(),
+                run.run_times.iter().max(),
+            ) else {
+                continue;
+            };
+            rows.push(format!(
+                "{},{},{},{},{},{}",
+                escape(benchmark_name),
+                escape(runner_name),
+                mean.as_nanos(),
+                min.as_nanos(),
+                max.as_nanos(),
+                run.run_times.len()
+            ));
+        }
+    }
+    rows.sort();
+    rows.insert(
+        0,
+        "benchmark,runner,mean_ns,min_ns,max_ns,num_runs".to_string(),
+    );
+    rows.join("\n")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Renders the main results table as a standalone HTML page.
+fn html(
+    results: &ResultsFormatted,
+    options: &PrintOptions,
+) -> Result<String, Box<dyn error::Error>> {
+    let records: Vec<Vec<String>> = if has_successful_runs(results) {
+        results_builder(results, options)?.into()
+    } else {
+        Vec::new()
+    };
+    let cell = |tag: &str, text: &str| {
+        // Cells like `**sum**` are bold in markdown.
+        match text
+            .strip_prefix("**")
+            .and_then(|text| text.strip_suffix("**"))
+        {
+            Some(text) => format!("<{tag}><strong>{}</strong></{tag}>", escape_html(text)),
+            None => format!("<{tag}>{}</{tag}>", escape_html(text)),
+        }
+    };
+
+    let mut out = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>evm-bench results</title>\n</head>\n<body>\n",
+    );
+    if let Some(labels) = labels_line(results) {
+        out.push_str(&format!(
+            "<p>{}</p>\n",
+            escape_html(&labels.replace("**", ""))
+        ));
+    }
+    if records.is_empty() {
+        out.push_str(&format!("<p>{NO_SUCCESSFUL_RUNS}</p>\n</body>\n</html>"));
+        return Ok(out);
+    }
+    out.push_str("<table>\n");
+    for (i, record) in records.iter().enumerate() {
+        let tag = if i == 0 { "th" } else { "td" };
+        out.push_str(&format!(
+            "<tr>{}</tr>\n",
+            record
+                .iter()
+                .map(|text| cell(tag, text))
+                .collect::<String>()
+        ));
+    }
+    out.push_str("</table>\n</body>\n</html>");
+    Ok(out)
+}
+
+const SVG_BAR_HEIGHT: usize = 24;
+const SVG_LABEL_WIDTH: usize = 160;
+const SVG_BAR_WIDTH: usize = 480;
+
+/// Renders a horizontal bar chart of the sum of each runner's mean run times, fastest first.
+fn svg(results: &ResultsFormatted) -> String {
+    let mut sums = results
+        .runners
+        .keys()
+        .map(|runner_name| {
+            let times = results
+                .runs
+                .iter()
+                .filter_map(|(benchmark_name, runs)| {
+                    Some(
+                        mean_run_time(runs.get(runner_name)?)?
+                            .mul_f64(benchmark_weight(results, benchmark_name)),
+                    )
+                })
+                .collect::<Vec<_>>();
+            (runner_name, stats::sum(&times))
+        })
+        .collect::<Vec<_>>();
+    sums.sort_by_key(|(runner_name, sum)| (*sum, *runner_name));
+    let max = sums.iter().map(|(_, sum)| *sum).max().unwrap_or_default();
+
+    let width = SVG_LABEL_WIDTH + SVG_BAR_WIDTH + SVG_LABEL_WIDTH;
+    let height = SVG_BAR_HEIGHT * sums.len().max(1);
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" font-family=\"sans-serif\" font-size=\"12\">\n"
+    );
+    for (i, (runner_name, sum)) in sums.iter().enumerate() {
+        let y = i * SVG_BAR_HEIGHT;
+        let bar_width = if max.is_zero() {
+            0.0
+        } else {
+            sum.as_secs_f64() / max.as_secs_f64() * SVG_BAR_WIDTH as f64
+        };
+        let text_y = y + SVG_BAR_HEIGHT * 2 / 3;
+        out.push_str(&format!(
+            "<text x=\"{}\" y=\"{text_y}\" text-anchor=\"end\">{}</text>\n",
+            SVG_LABEL_WIDTH - 8,
+            escape_html(runner_name)
+        ));
+        out.push_str(&format!(
+            "<rect x=\"{SVG_LABEL_WIDTH}\" y=\"{}\" width=\"{bar_width:.1}\" height=\"{}\" fill=\"steelblue\"/>\n",
+            y + 2,
+            SVG_BAR_HEIGHT - 4
+        ));
+        out.push_str(&format!(
+            "<text x=\"{:.1}\" y=\"{text_y}\">{sum:?}</text>\n",
+            SVG_LABEL_WIDTH as f64 + bar_width + 4.0
+        ));
+    }
+    out.push_str("</svg>");
+    out
+}
+
+/// Sink rendering results in one of the built-in formats, to stdout or a file.
+pub struct FormatSink<'a> {
+    pub output: &'a FormatOutput,
+    pub options: &'a PrintOptions,
+    /// Results to compare against, for formats that support it.
+    pub baseline: Option<&'a ResultsFormatted>,
+}
+
+/// Geometric mean of each runner's run times relative to the fastest runner, over the benchmarks
+/// it ran, sorted from fastest.
+fn geomean_relative_times(results: &ResultsFormatted) -> Vec<(&str, f64)> {
+    let mut log_relatives = HashMap::<&str, Vec<f64>>::new();
+    for runs in results.runs.values() {
+        let means = runs
+            .iter()
+            .filter_map(|(runner_name, run)| Some((runner_name.as_str(), mean_run_time(run)?)))
+            .filter(|(_, mean)| !mean.is_zero())
+            .collect::<Vec<_>>();
+        let Some(fastest) = means
+            .iter()
+            .filter(|(runner_name, _)| !is_calibration(results, runner_name))
+            .map(|(_, mean)| *mean)
+            .min()
+        else {
+            continue;
+        };
+        for (runner_name, mean) in means {
+            log_relatives
+                .entry(runner_name)
+                .or_default()
+                .push((mean.as_secs_f64() / fastest.as_secs_f64()).ln());
+        }
+    }
+    let mut relatives = log_relatives
+        .into_iter()
+        .map(|(runner_name, logs)| {
+            (
+                runner_name,
+                (logs.iter().sum::<f64>() / logs.len() as f64).exp(),
+            )
+        })
+        .collect::<Vec<_>>();
+    relatives.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(b.0)));
+    relatives
+}
+
+/// [shields.io endpoint](https://shields.io/badges/endpoint-badge) JSON naming the runner with
+/// the lowest geometric mean relative time, and how much faster it is than the runner-up.
+fn badge(results: &ResultsFormatted, options: &PrintOptions) -> serde_json::Value {
+    let relatives = geomean_relative_times(results);
+    let (message, color) = match relatives.as_slice() {
+        [] => ("no results".to_string(), "lightgrey"),
+        [(winner, _)] => (winner.to_string(), "blue"),
+        [(winner, winner_relative), (runner_up, runner_up_relative), ..] => (
+            format!(
+                "{winner} ({} vs {runner_up})",
+                options.ratio(runner_up_relative / winner_relative)
+            ),
+            "brightgreen",
+        ),
+    };
+    serde_json::json!({
+        "schemaVersion": 1,
+        "label": "fastest EVM",
+        "message": message,
+        "color": color,
+    })
+}
+
+/// A single line with the fastest runner, how much faster it is than the slowest by geometric
+/// mean relative time, and the number of benchmarks and runners. The fields are stable, so that
+/// scripts can depend on them.
+fn oneline(results: &ResultsFormatted, options: &PrintOptions) -> String {
+    let relatives = geomean_relative_times(results);
+    let (fastest, relative_to_slowest) = match (relatives.first(), relatives.last()) {
+        (Some((fastest, fastest_relative)), Some((_, slowest_relative))) => {
+            (*fastest, slowest_relative / fastest_relative)
+        }
+        _ => ("none", 1.0),
+    };
+    format!(
+        "fastest={fastest} geomean_relative_to_slowest={} benchmarks={} runners={}",
+        options.ratio(relative_to_slowest),
+        results.benchmarks.len(),
+        results.runners.len()
+    )
+}
+
+/// Gas used by a run of each benchmark in the format of Foundry's `.gas-snapshot` files, as
+/// `<benchmark>:Benchmark() (gas: <gas>)` lines sorted by benchmark. Gas is the same on every
+/// correct EVM, so runners that disagree are warned about and the gas reported by most is used.
+/// Benchmarks without gas reported by any runner are left out.
+fn gas_snapshot(results: &ResultsFormatted) -> String {
+    let mut runs = results.runs.iter().collect::<Vec<_>>();
+    runs.sort_by_key(|(b, _)| *b);
+
+    let mut lines = Vec::new();
+    for (benchmark_name, benchmark_runs) in runs {
+        let mut runner_gas = benchmark_runs
+            .iter()
+            .flat_map(|(runner_name, run)| {
+                run.gas_used
+                    .iter()
+                    .map(move |gas| (runner_name.as_str(), *gas))
+            })
+            .collect::<Vec<_>>();
+        runner_gas.sort();
+        runner_gas.dedup();
+        let mut counts = BTreeMap::<u64, usize>::new();
+        for (_, gas) in &runner_gas {
+            *counts.entry(*gas).or_default() += 1;
+        }
+        let Some((gas, _)) = counts
+            .iter()
+            .max_by_key(|(gas, count)| (**count, Reverse(**gas)))
+        else {
+            continue;
+        };
+        if counts.len() > 1 {
+            log::warn!(
+                "runners disagree on the gas used by benchmark {benchmark_name}, using {gas}: {}",
+                runner_gas
+                    .iter()
+                    .map(|(runner_name, gas)| format!("{runner_name}={gas}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+        lines.push(format!("{benchmark_name}:Benchmark() (gas: {gas})"));
+    }
+    if lines.is_empty() {
+        log::warn!(
+            "no runner reported gas, pass --format gas-snapshot when running to ask them to"
+        );
+    }
+    lines.join("\n")
+}
+
+impl OutputSink for FormatSink<'_> {
+    fn write(&self, results: &ResultsFormatted) -> Result<(), Box<dyn error::Error>> {
+        let options = self.options;
+        let rendered = match self.output.format {
+            // Never color files, which are not terminals.
+            Format::Markdown => markdown(
+                results,
+                options,
+                options.color && self.output.path.is_none(),
+            )?,
+            Format::GithubMarkdown => github_markdown(results, self.baseline, options)?,
+            Format::DocsMarkdown => docs_markdown(results, options)?,
+            Format::Bmf => serde_json::to_string_pretty(&bmf(results))?,
+            Format::Json => serde_json::to_string_pretty(results)?,
+            Format::Csv => csv(results),
+            Format::Html => html(results, options)?,
+            Format::Svg => svg(results),
+            Format::Badge => serde_json::to_string(&badge(results, options))?,
+            Format::Oneline => oneline(results, options),
+            Format::GasSnapshot => gas_snapshot(results),
+        };
+        match &self.output.path {
+            None => println!("{rendered}"),
+            Some(path) => {
+                fs::write(path, format!("{rendered}\n"))
+                    .map_err(|e| format!("could not write results to {}: {e}", path.display()))?;
+                log::info!(
+                    "wrote {:?} results to {}",
+                    self.output.format,
+                    path.display()
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn print_results(
+    results_file_path: &Path,
+    options: &PrintOptions,
+) -> Result<(), Box<dyn error::Error>> {
+    let read_trimmed = |path: &Path| {
+        let mut results = read_results(path)?;
+        trim_leading_runs(&mut results, options.trim_fraction);
+        Ok::<_, Box<dyn error::Error>>(results)
+    };
+    let results = read_trimmed(results_file_path)?;
+    let baseline = match options
+        .outputs
+        .iter()
+        .any(|output| output.format == Format::GithubMarkdown)
+    {
+        true => options.baseline.as_deref().map(read_trimmed).transpose()?,
+        false => None,
+    };
+
+    if let Some(threshold_pct) = options.warn_cv_pct {
+        warn_noisy_cells(&results, threshold_pct);
+    }
+
+    for output in &options.outputs {
+        FormatSink {
+            output,
+            options,
+            baseline: baseline.as_ref(),
+        }
+        .write(&results)?;
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec::TempDir;
+
+    /// Results without benchmark or runner metadata, with `runs` given as JSON.
+    fn results_with_runs(runs: serde_json::Value) -> ResultsFormatted {
+        serde_json::from_value(serde_json::json!({
+            "benchmarks": {},
+            "runners": {},
+            "runs": runs,
+        }))
+        .unwrap()
+    }
+
+    /// Writes results with one run as MessagePack if `binary` and as JSON otherwise, and reads them
+    /// back, returning what was written and what was read as JSON values to compare.
+    fn round_trip(binary: bool) -> (serde_json::Value, serde_json::Value) {
+        let benchmark: Benchmark = serde_json::from_value(serde_json::json!({
+            "name": "erc20.transfer",
+            "solc_version": "0.8.17",
+            "num_runs": 3,
+            "contract": "ERC20Transfer.sol",
+            "build_context": "benchmarks/erc20/transfer",
+            "calldata": [48, 98, 126, 33],
+            "storage": {"0x01": "0x02"},
+        }))
+        .unwrap();
+        let runner: Runner = serde_json::from_value(serde_json::json!({
+            "name": "revm",
+            "entry": "runners/revm/entry.sh",
+            "category": "interpreter",
+            "output_format": "regex:took (?<ms>[0-9.]+)ms",
+        }))
+        .unwrap();
+        let run: RunResult = serde_json::from_value(serde_json::json!({
+            "run_times": [{"secs": 0, "nanos": 250000}, {"secs": 1, "nanos": 5}],
+            "output": "00",
+            "gas_used": [21000, 21000],
+            "metrics": {"peak_alloc_bytes": [1024.0, 2048.0]},
+        }))
+        .unwrap();
+        let written = serde_json::json!({
+            "schema_version": RESULTS_SCHEMA_VERSION,
+            "labels": {"machine": "ci"},
+            "benchmarks": {&benchmark.name: &benchmark},
+            "runners": {&runner.name: &runner},
+            "runs": {&benchmark.name: {&runner.name: &run}},
+        });
+        let results = Results::from([(benchmark, HashMap::from([(runner, run)]))]);
+
+        let dir = TempDir::new(&format!("round-trip-{binary}"));
+        let path = record_results(
+            &dir,
+            None,
+            OnCollision::Error,
+            HashMap::from([("machine".to_string(), "ci".to_string())]),
+            None,
+            binary,
+            &results,
+        )
+        .unwrap();
+        let read = read_results(&path);
+        let mut read = serde_json::to_value(read.unwrap()).unwrap();
+        // The environment is that of the machine running the test.
+        read.as_object_mut().unwrap().remove("environment");
+        (written, read)
+    }
+
+    #[test]
+    fn results_round_trip_as_json() {
+        let (written, read) = round_trip(false);
+        assert_eq!(read, written);
+    }
+
+    #[test]
+    fn results_round_trip_as_msgpack() {
+        let (written, read) = round_trip(true);
+        assert_eq!(read, written);
+    }
+
+    #[test]
+    fn no_successful_runs_without_runs() {
+        assert!(!has_successful_runs(&results_with_runs(serde_json::json!(
+            {}
+        ))));
+    }
+
+    #[test]
+    fn no_successful_runs_when_all_are_empty() {
+        let results = results_with_runs(serde_json::json!({
+            "erc20.mint": {"revm": {"run_times": []}, "evmone": {"run_times": []}},
+            "erc20.transfer": {},
+        }));
+        assert!(!has_successful_runs(&results));
+    }
+
+    #[test]
+    fn successful_runs_when_some_are_empty() {
+        let results = results_with_runs(serde_json::json!({
+            "erc20.mint": {"revm": {"run_times": []}},
+            "erc20.transfer": {
+                "revm": {"run_times": []},
+                "evmone": {"run_times": [{"secs": 0, "nanos": 250000}]},
+            },
+        }));
+        assert!(has_successful_runs(&results));
+    }
+
+    /// Cells of the markdown table row of `markdown` whose first cell is `name`.
+    fn row<'a>(markdown: &'a str, name: &str) -> Vec<&'a str> {
+        let line = markdown
+            .lines()
+            .find(|line| line.split('|').nth(1).map(str::trim) == Some(name))
+            .unwrap_or_else(|| panic!("no {name} row in {markdown}"));
+        line.split('|')
+            .map(str::trim)
+            .skip(2)
+            .take(line.matches('|').count() - 2)
+            .collect()
+    }
+
+    #[test]
+    fn markdown_reports_no_successful_runs_when_all_are_empty() {
+        let results = results_with_runs(serde_json::json!({
+            "erc20.mint": {"revm": {"run_times": []}},
+        }));
+        let markdown = markdown(&results, &PrintOptions::default(), false).unwrap();
+        assert_eq!(markdown, NO_SUCCESSFUL_RUNS);
+    }
+
+    #[test]
+    fn markdown_leaves_summary_cells_of_runners_without_runs_blank() {
+        let runner = |name: &str| serde_json::json!({"name": name, "entry": "entry.sh"});
+        let results: ResultsFormatted = serde_json::from_value(serde_json::json!({
+            "benchmarks": {},
+            "runners": {"idle": runner("idle"), "revm": runner("revm")},
+            "runs": {"erc20.mint": {
+                "idle": {"run_times": []},
+                "revm": {"run_times": [{"secs": 0, "nanos": 250000}]},
+            }},
+        }))
+        .unwrap();
+        let markdown = markdown(&results, &PrintOptions::default(), false).unwrap();
+        assert_eq!(row(&markdown, ""), ["revm", "idle"], "{markdown}");
+        assert_eq!(row(&markdown, "**sum**")[1], "", "{markdown}");
+        assert_eq!(row(&markdown, "**relative**")[1], "", "{markdown}");
+        assert_ne!(row(&markdown, "**sum**")[0], "", "{markdown}");
+    }
+
+    #[test]
+    fn trimming_leading_runs_trims_their_per_run_values() {
+        let millis = |ms: u64| serde_json::to_value(Duration::from_millis(ms)).unwrap();
+        let mut results = results_with_runs(serde_json::json!({
+            "erc20.mint": {"revm": {
+                "run_times": [millis(9), millis(1), millis(2), millis(3)],
+                "deploy_times": [millis(90), millis(10), millis(20), millis(30)],
+                "gas_used": [900, 100, 200, 300],
+                "metrics": {"cache_misses": [9.0, 1.0, 2.0, 3.0]},
+            }},
+        }));
+        trim_leading_runs(&mut results, 0.25);
+        let run = &results.runs["erc20.mint"]["revm"];
+        assert_eq!(run.run_times.len(), 3);
+        assert_eq!(run.deploy_times[0], Duration::from_millis(10));
+        assert_eq!(run.gas_used, [100, 200, 300]);
+        assert_eq!(run.metrics["cache_misses"], [1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn summary_leaves_out_zero_weights_and_sorts_runners_without_times_last() {
+        let benchmark = |name: &str, weight: f64| {
+            serde_json::json!({
+                "name": name,
+                "solc_version": "0.8.17",
+                "num_runs": 1,
+                "weight": weight,
+                "contract": format!("{name}.sol"),
+                "build_context": ".",
+                "calldata": [],
+            })
+        };
+        let runner = |name: &str| serde_json::json!({"name": name, "entry": "entry.sh"});
+        let run = |millis: u32| serde_json::json!({"run_times": [{"secs": 0, "nanos": millis * 1_000_000}]});
+        let results: ResultsFormatted = serde_json::from_value(serde_json::json!({
+            "benchmarks": {"ignored": benchmark("ignored", 0.0), "counted": benchmark("counted", 1.0)},
+            "runners": {"fast": runner("fast"), "idle": runner("idle"), "slow": runner("slow")},
+            "runs": {
+                "ignored": {"fast": run(9), "slow": run(1), "idle": run(1)},
+                "counted": {"fast": run(1), "slow": run(2)},
+            },
+        }))
+        .unwrap();
+        let options = PrintOptions {
+            sort_runners_by: SortRunnersBy::Geomean,
+            only_summary: true,
+            ..Default::default()
+        };
+        let table = results_table(&results, &options).unwrap().to_string();
+        let header = table.lines().next().unwrap();
+        let columns = header
+            .split('|')
+            .map(str::trim)
+            .filter(|column| !column.is_empty())
+            .collect::<Vec<_>>();
+        assert_eq!(columns, ["fast", "slow", "idle"], "{table}");
+    }
+}
@@ -1,81 +1,1037 @@
 use std::{
-    collections::{HashMap, HashSet},
-    error,
-    process::Command,
-    time::Duration,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    error, fs, io, panic,
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus},
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
 };
 
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 use crate::{
     build::BuiltBenchmark,
-    metadata::{Benchmark, Runner},
+    events::{millis, Event, EventSink},
+    exec::{
+        cpu_quota_command, interrupted, limit_memory, map_in_order, output_with_timeout,
+        CommandScript, RunDir,
+    },
+    metadata::{check_entry, Benchmark, Call, OutputFormat, Runner},
+    stats,
 };
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct RunResult {
     pub run_times: Vec<Duration>,
+    /// Hex return data of the benchmark call, if the runner reported it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    /// Contract deployment times, if measured with [`RunOptions::measure_deploy`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deploy_times: Vec<Duration>,
+    /// Times spent on one-time bytecode analysis, such as finding valid jump destinations, if
+    /// measured with [`RunOptions::measure_analysis`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub analysis_times: Vec<Duration>,
+    /// Gas consumed by each run, if the benchmark has a gas budget.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub gas_used: Vec<u64>,
+    /// Number of opcodes executed by one run of the benchmark, if the runner reported it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub opcode_count: Option<u64>,
+    /// Statistics of the runs after the runner's own warmup, if asked for with
+    /// [`RunOptions::steady_state`] and the runner reported them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub steady_state: Option<SteadyState>,
+    /// Values of runner-specific metrics by name, one per run that reported the metric.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub metrics: HashMap<String, Vec<f64>>,
+    /// Hashes of what the runner was given, missing from results recorded before they were.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inputs: Option<RunInputs>,
+}
+
+impl RunResult {
+    /// Keeps only the runs that `keep` is set for, along with the values that were reported once
+    /// per run, such as gas and metrics. Values reported for other than every run are kept whole.
+    pub fn retain_runs(&mut self, keep: &[bool]) {
+        fn retain<T>(values: &mut Vec<T>, keep: &[bool]) {
+            if values.len() == keep.len() {
+                let mut kept = keep.iter();
+                values.retain(|_| *kept.next().unwrap());
+            }
+        }
+        retain(&mut self.run_times, keep);
+        retain(&mut self.deploy_times, keep);
+        retain(&mut self.analysis_times, keep);
+        retain(&mut self.gas_used, keep);
+        for values in self.metrics.values_mut() {
+            retain(values, keep);
+        }
+    }
+}
+
+/// SHA-256 hashes of the bytecode and calldata a runner was given, which should be the same for
+/// every runner of a benchmark.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Hash, Serialize)]
+pub struct RunInputs {
+    pub code_sha256: String,
+    /// Hash of the calldata and of any calls.
+    pub calldata_sha256: String,
+}
+
+fn sha256_hex(parts: &[&[u8]]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        // Length-prefixed, so that moving bytes between parts changes the hash.
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part);
+    }
+    hex::encode(hasher.finalize())
+}
+
+/// Statistics a runner measured itself over the runs of one process, after discarding the runs it
+/// considers warmup.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct SteadyState {
+    pub warmup_runs: u64,
+    pub mean_ms: f64,
+    pub stddev_ms: f64,
+}
+
+/// Warmup a runner did before its measured runs, when asked for with [`RunOptions::warmup_time`].
+#[derive(Deserialize)]
+struct Warmup {
+    runs: u64,
+    ms: f64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct RunOptions {
+    /// Time allowed per run of a benchmark, scaled by the number of runs to bound each runner
+    /// invocation. Unbounded if `None`.
+    pub timeout_per_run: Option<Duration>,
+    /// Time allowed on top of the scaled timeout for the runner to start up.
+    pub startup_allowance: Duration,
+    /// Number of runs to use for specific runners by name, taking precedence over both the runner
+    /// metadata and the benchmark. Runners swept across hardforks take the number of the runner
+    /// they were swept from, unless given their own as `<runner>@<hardfork>`.
+    pub runner_num_runs: HashMap<String, u64>,
+    /// Ask runners to also report contract deployment times.
+    pub measure_deploy: bool,
+    /// Ask runners to also report how long analysing the contract's bytecode took.
+    pub measure_analysis: bool,
+    /// Ask runners to also report statistics of their runs after their own warmup.
+    pub steady_state: bool,
+    /// Time to ask runners to warm up for before their measured runs, if any.
+    pub warmup_time: Option<Duration>,
+    /// Name of a runner to ask for a flamegraph of each benchmark, and the directory to put them
+    /// under by benchmark and runner.
+    pub flamegraph: Option<(String, PathBuf)>,
+    /// Ask runners to also report the gas used by each run.
+    pub report_gas: bool,
+    /// Ask runners to also report the number of opcodes executed by a run.
+    pub count_opcodes: bool,
+    /// Directory to put the files of each runner invocation under. Large calldata is passed
+    /// inline if `None`.
+    pub run_files_path: Option<PathBuf>,
+    /// Keep the files of successful runner invocations, which are otherwise only kept on failure.
+    pub save_logs: bool,
+    /// Bytes of address space each runner invocation may use, unlimited if `None`.
+    pub memory_limit: Option<u64>,
+    /// Percent of a CPU each runner invocation may use, unlimited if `None`.
+    pub cpu_quota_pct: Option<u32>,
+    /// Script to record the runner invocations and hooks in, if any.
+    pub commands: Option<Arc<CommandScript>>,
+    /// Number of benchmark and runner pairs to run at once. Pairs run one at a time, in order, if
+    /// this is at most 1.
+    pub jobs: usize,
+    /// Invoke runners once per benchmark, even those that accept batches.
+    pub no_batch: bool,
+}
+
+impl RunOptions {
+    /// Whether to keep the files of a runner invocation, which is done when asked to, when it
+    /// `failed`, and when recording commands, as the script refers to the inputs among the files.
+    fn keep_run_files(&self, failed: bool) -> bool {
+        self.save_logs || failed || self.commands.is_some()
+    }
+
+    /// Number of runs to use for a benchmark on a runner, after applying any runner overrides.
+    fn num_runs(&self, benchmark: &Benchmark, runner: &Runner) -> u64 {
+        self.runner_num_runs
+            .get(&runner.name)
+            .or_else(|| self.runner_num_runs.get(runner.base_name()))
+            .copied()
+            .or(runner.num_runs)
+            .unwrap_or(benchmark.num_runs)
+    }
+
+    fn timeout(&self, num_runs: u64) -> Option<Duration> {
+        self.timeout_per_run.map(|timeout_per_run| {
+            timeout_per_run.saturating_mul(num_runs.try_into().unwrap_or(u32::MAX))
+                + self.startup_allowance
+                + self.warmup_time.unwrap_or_default()
+        })
+    }
 }
 
 type BenchmarkResults = HashMap<Runner, RunResult>;
 pub type Results = HashMap<Benchmark, BenchmarkResults>;
 
-fn run_benchmark_on_runner(
+/// Hex calldata longer than this is passed to runners as a file rather than an argument, to stay
+/// clear of argument length limits.
+const CALLDATA_ARG_LIMIT: usize = 64 * 1024;
+
+/// Converts a time in milliseconds reported by a runner, keeping sub-millisecond precision so that
+/// fast runners don't report zero times.
+fn from_millis(millis: f64) -> Result<Duration, Box<dyn error::Error>> {
+    Duration::try_from_secs_f64(millis / 1e3)
+        .map_err(|e| format!("invalid run time {millis} ms: {e}").into())
+}
+
+fn parse_millis(value: &str) -> Result<Duration, Box<dyn error::Error>> {
+    from_millis(str::parse::<f64>(value)?)
+}
+
+fn parse_secs(value: &str) -> Result<Duration, Box<dyn error::Error>> {
+    from_millis(str::parse::<f64>(value)? * 1e3)
+}
+
+/// Parses run times from lines of a runner's stdout in its [`OutputFormat`].
+enum RunTimeParser {
+    FloatMs,
+    FloatS,
+    JsonLines,
+    Regex(regex::Regex),
+}
+
+impl RunTimeParser {
+    fn new(format: &OutputFormat) -> Result<Self, Box<dyn error::Error>> {
+        Ok(match format {
+            OutputFormat::FloatMs => Self::FloatMs,
+            OutputFormat::FloatS => Self::FloatS,
+            OutputFormat::JsonLines => Self::JsonLines,
+            OutputFormat::Regex(pattern) => Self::Regex(regex::Regex::new(pattern)?),
+        })
+    }
+
+    /// Parses the run time in `line`, if it has one. Lines of the float formats must have one,
+    /// unless they are `tagged` with an unknown tag.
+    fn parse(&self, line: &str, tagged: bool) -> Result<Option<Duration>, Box<dyn error::Error>> {
+        match self {
+            Self::FloatMs | Self::FloatS if tagged => Ok(None),
+            Self::FloatMs => parse_millis(line).map(Some),
+            Self::FloatS => parse_secs(line).map(Some),
+            Self::JsonLines => {
+                let Ok(object) = serde_json::from_str::<serde_json::Value>(line) else {
+                    return Ok(None);
+                };
+                let millis = match (object.get("time_ms"), object.get("time_s")) {
+                    (Some(ms), _) => ms.as_f64(),
+                    (None, Some(s)) => s.as_f64().map(|s| s * 1e3),
+                    (None, None) => return Ok(None),
+                };
+                let millis = millis.ok_or(format!("could not parse run time in {line}"))?;
+                from_millis(millis).map(Some)
+            }
+            Self::Regex(regex) => {
+                let Some(captures) = regex.captures(line) else {
+                    return Ok(None);
+                };
+                match (captures.name("ms"), captures.name("s")) {
+                    (Some(ms), _) => parse_millis(ms.as_str()).map(Some),
+                    (None, Some(s)) => parse_secs(s.as_str()).map(Some),
+                    (None, None) => Ok(None),
+                }
+            }
+        }
+    }
+}
+
+/// Keeps only the `count` fastest runs of `result` in their original order, for runners that
+/// reported more runs than asked to with `--report-runs`.
+fn keep_fastest_runs(result: &mut RunResult, count: usize) {
+    let times = &result.run_times;
+    if times.len() <= count {
+        return;
+    }
+    let mut order = (0..times.len()).collect::<Vec<_>>();
+    order.sort_by_key(|i| times[*i]);
+    let mut keep = vec![false; times.len()];
+    for i in &order[..count] {
+        keep[*i] = true;
+    }
+    result.retain_runs(&keep);
+}
+
+/// Arguments to run a benchmark on a runner with, and what is needed to make sense of its output.
+struct Invocation {
+    args: Vec<String>,
+    num_runs: u64,
+    /// Number of the fastest runs to keep, if fewer than are run.
+    report_runs: Option<u64>,
+    inputs: RunInputs,
+    /// Directory the runner is asked to write a flamegraph to, if any.
+    flamegraph_path: Option<PathBuf>,
+}
+
+/// Builds the arguments to run `benchmark` on `runner` with. Large calldata is written to a file in
+/// `run_dir` if there is one.
+fn benchmark_args(
     benchmark: &BuiltBenchmark,
     runner: &Runner,
-) -> Result<RunResult, Box<dyn error::Error>> {
-    log::info!(
-        "running benchmark {} on runner {}...",
-        benchmark.benchmark.name,
-        runner.name
-    );
+    options: &RunOptions,
+    run_dir: Option<&RunDir>,
+) -> Result<Invocation, Box<dyn error::Error>> {
+    let num_runs = options.num_runs(&benchmark.benchmark, runner);
     log::debug!(
-        "running {} times using code {} with calldata {}...",
-        benchmark.benchmark.num_runs,
+        "running {} times using code {} with {}...",
+        num_runs,
         benchmark
             .result
             .contract_bin_path
             .file_name()
             .unwrap()
             .to_string_lossy(),
-        hex::encode(&benchmark.benchmark.calldata),
+        if benchmark.benchmark.calldata.is_empty() {
+            "no calldata".to_string()
+        } else {
+            format!("calldata {}", hex::encode(&benchmark.benchmark.calldata))
+        },
     );
 
-    let out = Command::new(&runner.entry)
-        .args([
-            "--contract-code-path",
-            &benchmark.result.contract_bin_path.to_string_lossy(),
-        ])
-        .args(["--calldata", &hex::encode(&benchmark.benchmark.calldata)])
-        .args(["--num-runs", &format!("{}", benchmark.benchmark.num_runs)])
-        .output()?;
+    let mut args = Vec::new();
+    let mut arg = |parts: &[&str]| args.extend(parts.iter().map(|part| part.to_string()));
+    arg(&[
+        "--contract-code-path",
+        &benchmark.result.contract_bin_path.to_string_lossy(),
+    ]);
+    arg(&["--num-runs", &num_runs.to_string()]);
+    // Reporting as many runs as are run is the same as reporting all of them.
+    let report_runs = benchmark
+        .benchmark
+        .report_runs
+        .filter(|report_runs| *report_runs < num_runs);
+    // Runners that don't declare the flag output every run, of which the fastest are kept here.
+    if let Some(report_runs) = report_runs.filter(|_| runner.report_runs) {
+        arg(&["--report-runs", &report_runs.to_string()]);
+    }
+    let calldata = hex::encode(&benchmark.benchmark.calldata);
+    let call_args = benchmark
+        .benchmark
+        .calls
+        .iter()
+        .map(Call::to_arg)
+        .collect::<Vec<_>>();
+    let inputs = RunInputs {
+        code_sha256: sha256_hex(&[&fs::read(&benchmark.result.contract_bin_path)?]),
+        calldata_sha256: sha256_hex(
+            &std::iter::once(calldata.as_bytes())
+                .chain(call_args.iter().map(String::as_bytes))
+                .collect::<Vec<_>>(),
+        ),
+    };
+    match run_dir {
+        Some(run_dir) if calldata.len() > CALLDATA_ARG_LIMIT => {
+            arg(&[
+                "--calldata-path",
+                &run_dir.write("calldata.hex", &calldata)?.to_string_lossy(),
+            ]);
+        }
+        _ => {
+            // Empty calldata is passed as an empty argument rather than left out, so that runners
+            // which require the option don't need to tell "no calldata" apart from a missing one.
+            arg(&["--calldata", &calldata]);
+        }
+    }
+    // Only passed when set, so that runners without support for these keep working.
+    for item in &benchmark.benchmark.access_list {
+        arg(&["--access-list", &item.to_arg()]);
+    }
+    for (slot, value) in &benchmark.benchmark.storage {
+        arg(&["--storage", &format!("{slot}={value}")]);
+    }
+    if let (Some(fork_url), Some(fork_block)) = (
+        &benchmark.benchmark.fork_url,
+        benchmark.benchmark.fork_block,
+    ) {
+        arg(&["--fork-url", fork_url]);
+        arg(&["--fork-block", &fork_block.to_string()]);
+        arg(&[
+            "--fork-cache-path",
+            &benchmark
+                .result
+                .contract_bin_path
+                .with_file_name(format!("fork-{fork_block}-{}.json", runner.name))
+                .to_string_lossy(),
+        ]);
+    }
+    for field in benchmark.benchmark.block.to_args() {
+        arg(&["--block", &field]);
+    }
+    if let Some(deployer) = &benchmark.benchmark.deployer {
+        arg(&["--deployer", deployer]);
+    }
+    if let Some(deployer_nonce) = benchmark.benchmark.deployer_nonce {
+        arg(&["--deployer-nonce", &deployer_nonce.to_string()]);
+    }
+    for call_arg in &call_args {
+        arg(&["--call", call_arg]);
+    }
+    if let Some(measure_step) = benchmark.benchmark.measure_step {
+        arg(&["--measure-step", &measure_step.to_string()]);
+    }
+    if let Some(gas_budget) = benchmark.benchmark.gas_budget {
+        arg(&["--gas-budget", &gas_budget.to_string()]);
+    }
+    if let Some(hardfork) = &runner.hardfork {
+        arg(&["--hardfork", hardfork]);
+    }
+    if options.measure_deploy {
+        arg(&["--measure-deploy"]);
+    }
+    if options.measure_analysis {
+        arg(&["--measure-analysis"]);
+    }
+    // Runners that don't declare the flag may reject it as unknown.
+    if runner.report_gas && (options.report_gas || benchmark.benchmark.expected_gas.is_some()) {
+        arg(&["--report-gas"]);
+    }
+    if runner.count_opcodes && options.count_opcodes {
+        arg(&["--count-opcodes"]);
+    }
+    if options.steady_state {
+        arg(&["--steady-state"]);
+    }
+    if let Some(warmup_time) = options.warmup_time {
+        arg(&["--warmup-time-ms", &warmup_time.as_millis().to_string()]);
+    }
+    let flamegraph_path = options
+        .flamegraph
+        .as_ref()
+        .filter(|(runner_name, _)| *runner_name == runner.name)
+        .map(|(_, path)| path.join(&benchmark.benchmark.name).join(&runner.name));
+    if let Some(flamegraph_path) = &flamegraph_path {
+        // Clear out a previous flamegraph, so that a runner not writing one is noticed.
+        if flamegraph_path.exists() {
+            fs::remove_dir_all(flamegraph_path)?;
+        }
+        arg(&["--flamegraph-path", &flamegraph_path.to_string_lossy()]);
+    }
+    Ok(Invocation {
+        args,
+        num_runs,
+        report_runs,
+        inputs,
+        flamegraph_path,
+    })
+}
+
+/// Invokes `runner` with `args`, returning its stdout and how long it took, or erroring if it
+/// failed or took longer than `timeout`. Its output is written to `run_dir` if there is one.
+fn invoke_runner(
+    runner: &Runner,
+    args: &[String],
+    description: &str,
+    timeout: Option<Duration>,
+    options: &RunOptions,
+    run_dir: Option<&RunDir>,
+) -> Result<(String, Duration), Box<dyn error::Error>> {
+    let (status, stdout, wall_time) =
+        invoke_runner_unchecked(runner, args, description, timeout, options, run_dir)?;
+    check_runner_status(status, options)?;
+    Ok((stdout, wall_time))
+}
+
+/// Invokes `runner` like [`invoke_runner`], but returns its exit status along with its stdout
+/// rather than erroring if it failed, so that the output it gave before failing can be used.
+fn invoke_runner_unchecked(
+    runner: &Runner,
+    args: &[String],
+    description: &str,
+    timeout: Option<Duration>,
+    options: &RunOptions,
+    run_dir: Option<&RunDir>,
+) -> Result<(ExitStatus, String, Duration), Box<dyn error::Error>> {
+    let mut command = match options.cpu_quota_pct {
+        Some(pct) => cpu_quota_command(&runner.entry, pct),
+        None => Command::new(&runner.entry),
+    };
+    command.args(args);
+    if let Some(memory_limit) = options.memory_limit {
+        limit_memory(&mut command, memory_limit);
+    }
+    if let Some(commands) = &options.commands {
+        commands.record(description, &command);
+    }
+    let timer = Instant::now();
+    let out = output_with_timeout(&mut command, timeout)
+        .map_err(|e| -> Box<dyn error::Error> {
+            // The entry was checked when discovered, but may have changed since.
+            match check_entry(&runner.entry) {
+                Err(entry_error) if e.kind() == io::ErrorKind::PermissionDenied => entry_error,
+                _ => e.into(),
+            }
+        })?
+        .ok_or_else(|| format!("timed out after {:?}", timeout.unwrap_or_default()))?;
+    let wall_time = timer.elapsed();
+
+    if let Some(run_dir) = run_dir {
+        run_dir.write("stdout.log", &out.stdout)?;
+        run_dir.write("stderr.log", &out.stderr)?;
+    }
 
-    let stdout = String::from_utf8(out.stdout).unwrap();
+    // Output that isn't UTF-8 is still parsed, with its invalid bytes replaced.
+    let stdout = String::from_utf8_lossy(&out.stdout).into_owned();
     log::trace!("stdout: {}", stdout);
-    log::trace!("stderr: {}", String::from_utf8(out.stderr).unwrap());
+    log::trace!("stderr: {}", String::from_utf8_lossy(&out.stderr));
+    Ok((out.status, stdout, wall_time))
+}
+
+/// Errors if a runner exited with a failure `status`.
+fn check_runner_status(
+    status: ExitStatus,
+    options: &RunOptions,
+) -> Result<(), Box<dyn error::Error>> {
+    if status.success() {
+        Ok(())
+    } else if let Some(memory_limit) = options.memory_limit {
+        Err(format!("{status} (memory was limited to {memory_limit} bytes)").into())
+    } else {
+        Err(format!("{status}").into())
+    }
+}
+
+pub fn run_benchmark_on_runner(
+    benchmark: &BuiltBenchmark,
+    runner: &Runner,
+    options: &RunOptions,
+) -> Result<RunResult, Box<dyn error::Error>> {
+    log::info!(
+        "running benchmark {} on runner {}...",
+        benchmark.benchmark.name,
+        runner.name
+    );
+    let run_dir = options
+        .run_files_path
+        .as_deref()
+        .map(|root| RunDir::create(root, &benchmark.benchmark.name, &runner.name))
+        .transpose()?;
+    let result = run_benchmark_on_runner_in(benchmark, runner, options, run_dir.as_ref());
+    if let Some(run_dir) = run_dir {
+        run_dir.finish(options.keep_run_files(result.is_err()));
+    }
+    result
+}
+
+fn run_benchmark_on_runner_in(
+    benchmark: &BuiltBenchmark,
+    runner: &Runner,
+    options: &RunOptions,
+    run_dir: Option<&RunDir>,
+) -> Result<RunResult, Box<dyn error::Error>> {
+    let invocation = benchmark_args(benchmark, runner, options, run_dir)?;
+    let (stdout, wall_time) = invoke_runner(
+        runner,
+        &invocation.args,
+        &format!(
+            "run benchmark {} on runner {}",
+            benchmark.benchmark.name, runner.name
+        ),
+        options.timeout(invocation.num_runs),
+        options,
+        run_dir,
+    )?;
+    parse_run_output(benchmark, runner, invocation, &stdout, Some(wall_time))
+}
+
+/// Parses the results of running `benchmark` on `runner` from the runner's stdout. Reported times
+/// are checked against the `wall_time` the runner took, if known.
+fn parse_run_output(
+    benchmark: &BuiltBenchmark,
+    runner: &Runner,
+    invocation: Invocation,
+    stdout: &str,
+    wall_time: Option<Duration>,
+) -> Result<RunResult, Box<dyn error::Error>> {
+    let parser = RunTimeParser::new(&runner.output_format)?;
+    let mut times: Vec<Duration> = Vec::new();
+    let mut deploy_times: Vec<Duration> = Vec::new();
+    let mut analysis_times: Vec<Duration> = Vec::new();
+    let mut output: Option<String> = None;
+    let mut gas_used: Vec<u64> = Vec::new();
+    let mut opcode_count: Option<u64> = None;
+    let mut metrics: HashMap<String, Vec<f64>> = HashMap::new();
+    let mut steady_state: Option<SteadyState> = None;
+    for line in stdout
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+    {
+        match line.split_once(':') {
+            Some(("output", value)) => {
+                let value = value.trim().trim_start_matches("0x").to_lowercase();
+                match &output {
+                    Some(prev) if *prev != value => {
+                        log::warn!(
+                            "runner {} reported differing outputs across runs of {}: {prev} and {value}",
+                            runner.name,
+                            benchmark.benchmark.name
+                        );
+                    }
+                    _ => output = Some(value),
+                }
+            }
+            Some(("deploy", value)) => deploy_times.push(parse_millis(value.trim())?),
+            Some(("analysis", value)) => analysis_times.push(parse_millis(value.trim())?),
+            Some(("gas", value)) => gas_used.push(value.trim().parse()?),
+            Some(("opcodes", value)) => opcode_count = Some(value.trim().parse()?),
+            Some(("steady-state", value)) => {
+                steady_state = Some(
+                    serde_json::from_str(value.trim())
+                        .map_err(|e| format!("could not parse steady state {value}: {e}"))?,
+                )
+            }
+            Some(("warmup", value)) => {
+                let warmup: Warmup = serde_json::from_str(value.trim())
+                    .map_err(|e| format!("could not parse warmup {value}: {e}"))?;
+                log::info!(
+                    "runner {} warmed up benchmark {} with {} runs in {} ms",
+                    runner.name,
+                    benchmark.benchmark.name,
+                    warmup.runs,
+                    warmup.ms
+                );
+            }
+            Some(("metrics", value)) => {
+                let values: HashMap<String, f64> = serde_json::from_str(value.trim())
+                    .map_err(|e| format!("could not parse metrics {value}: {e}"))?;
+                for (name, value) in values {
+                    metrics.entry(name).or_default().push(value);
+                }
+            }
+            Some((tag, _)) => match parser.parse(line, true)? {
+                Some(time) => times.push(time),
+                None => log::debug!("ignoring unknown line tag {tag}"),
+            },
+            None => times.extend(parser.parse(line, false)?),
+        }
+    }
+
+    if times.is_empty() {
+        return Err("runner produced no timings".into());
+    }
+    if let Some(wall_time) = wall_time {
+        check_timing_plausible(runner, &times, wall_time);
+    }
+    let mut result = RunResult {
+        run_times: times,
+        output,
+        deploy_times,
+        analysis_times,
+        gas_used,
+        opcode_count,
+        steady_state,
+        metrics,
+        inputs: Some(invocation.inputs),
+    };
+    if let Some(report_runs) = invocation.report_runs {
+        keep_fastest_runs(&mut result, report_runs as usize);
+    }
+    if benchmark.benchmark.gas_budget.is_some() && result.gas_used.len() != result.run_times.len() {
+        return Err(format!(
+            "runner reported gas for {} of {} runs under a gas budget",
+            result.gas_used.len(),
+            result.run_times.len()
+        )
+        .into());
+    }
+    check_expected_gas(&benchmark.benchmark, runner, &result.gas_used)?;
+
+    log::debug!(
+        "ran benchmark {} on runner {}",
+        benchmark.benchmark.name,
+        runner.name
+    );
+    if let Some(flamegraph_path) = invocation.flamegraph_path {
+        if fs::read_dir(&flamegraph_path).is_ok_and(|mut entries| entries.next().is_some()) {
+            log::info!("wrote flamegraph to {}", flamegraph_path.display());
+        } else {
+            log::warn!(
+                "runner {} did not write a flamegraph of benchmark {}, it may not support \
+                 --flamegraph-path",
+                runner.name,
+                benchmark.benchmark.name
+            );
+        }
+    }
+    Ok(result)
+}
+
+/// Name of the directory of run files of batch invocations, which is hidden so as not to look
+/// like a benchmark's.
+pub const BATCH_RUN_DIR: &str = ".batch";
+
+/// Results of the benchmarks of a batch by name, or why the runner gave none for one.
+type BatchResults = HashMap<String, Result<RunResult, Box<dyn error::Error>>>;
+
+/// Runs `benchmarks` on a runner that accepts batches in a single invocation, sparing a process
+/// start per benchmark. The arguments of each benchmark are passed in a JSON file given with
+/// `--batch-path`, and the runner outputs a `benchmark: <name>` line before the output of each.
+/// Errors if the runner could not be invoked at all, while benchmarks that a failed runner didn't
+/// finish get errors of their own.
+fn run_batch_on_runner(
+    benchmarks: &[&BuiltBenchmark],
+    runner: &Runner,
+    options: &RunOptions,
+) -> Result<BatchResults, Box<dyn error::Error>> {
+    log::info!(
+        "running {} benchmarks on runner {} as a batch...",
+        benchmarks.len(),
+        runner.name
+    );
+    let run_dir = RunDir::create(
+        options
+            .run_files_path
+            .as_deref()
+            .ok_or("batches need a directory for run files")?,
+        BATCH_RUN_DIR,
+        &runner.name,
+    )?;
+    let results = run_batch_on_runner_in(benchmarks, runner, options, &run_dir);
+    let succeeded = results
+        .as_ref()
+        .is_ok_and(|results| results.values().all(Result::is_ok));
+    run_dir.finish(options.keep_run_files(!succeeded));
+    results
+}
+
+fn run_batch_on_runner_in(
+    benchmarks: &[&BuiltBenchmark],
+    runner: &Runner,
+    options: &RunOptions,
+    run_dir: &RunDir,
+) -> Result<BatchResults, Box<dyn error::Error>> {
+    let mut invocations = Vec::new();
+    let mut batch = Vec::new();
+    for benchmark in benchmarks {
+        // Calldata is passed inline, as the batch file has no argument length limit.
+        let invocation = benchmark_args(benchmark, runner, options, None)?;
+        batch.push(serde_json::json!({
+            "benchmark": benchmark.benchmark.name,
+            "args": invocation.args,
+        }));
+        invocations.push(invocation);
+    }
+    let batch_path = run_dir.write("batch.json", serde_json::to_string_pretty(&batch)?)?;
+    let timeout = invocations
+        .iter()
+        .map(|invocation| options.timeout(invocation.num_runs))
+        .sum();
+    let (status, stdout, wall_time) = invoke_runner_unchecked(
+        runner,
+        &[
+            "--batch-path".to_string(),
+            batch_path.to_string_lossy().to_string(),
+        ],
+        &format!(
+            "run {} benchmarks on runner {} as a batch",
+            benchmarks.len(),
+            runner.name
+        ),
+        timeout,
+        options,
+        Some(run_dir),
+    )?;
+
+    let mut outputs = Vec::<(&str, String)>::new();
+    for line in stdout.lines() {
+        match line.trim().strip_prefix("benchmark:") {
+            Some(benchmark_name) => outputs.push((benchmark_name.trim(), String::new())),
+            None => match outputs.last_mut() {
+                Some((_, output)) => {
+                    output.push_str(line);
+                    output.push('\n');
+                }
+                None if !line.trim().is_empty() => {
+                    log::debug!("ignoring batch output before the first benchmark: {line}")
+                }
+                None => {}
+            },
+        }
+    }
+    // A failed runner was cut off while running its last benchmark, but finished the ones before.
+    let failure = check_runner_status(status, options).err();
+    if let Some(e) = &failure {
+        let unfinished = outputs.pop().map(|(benchmark_name, _)| benchmark_name);
+        log::warn!(
+            "batch on runner {} failed ({e}) {}, running the rest of it one at a time",
+            runner.name,
+            match unfinished {
+                Some(benchmark_name) => format!("while running benchmark {benchmark_name}"),
+                None => "before running any benchmark".to_string(),
+            }
+        );
+    }
+    let outputs = outputs.into_iter().collect::<HashMap<_, _>>();
+
+    let mut results = BatchResults::new();
+    for (benchmark, invocation) in benchmarks.iter().zip(invocations) {
+        let name = &benchmark.benchmark.name;
+        let result = match (outputs.get(name.as_str()), &failure) {
+            (Some(output), _) => parse_run_output(benchmark, runner, invocation, output, None),
+            (None, Some(e)) => Err(format!("the batch failed before finishing it: {e}").into()),
+            (None, None) => Err("runner output nothing for it in its batch".into()),
+        };
+        results.insert(name.clone(), result);
+    }
+    // Times can only be checked against how long the whole batch took.
+    let times = results
+        .values()
+        .flatten()
+        .flat_map(|result| result.run_times.iter().copied())
+        .collect::<Vec<_>>();
+    check_timing_plausible(runner, &times, wall_time);
+    Ok(results)
+}
+
+/// Checks that every run of `benchmark` used its expected gas, if it has any. Runners that don't
+/// report gas, such as those that don't declare `report-gas` and so aren't asked to, can't be
+/// checked, which is only warned about.
+fn check_expected_gas(
+    benchmark: &Benchmark,
+    runner: &Runner,
+    gas_used: &[u64],
+) -> Result<(), Box<dyn error::Error>> {
+    let Some(expected_gas) = benchmark.expected_gas else {
+        return Ok(());
+    };
+    let tolerance = benchmark.expected_gas_tolerance.unwrap_or(0);
+    if gas_used.is_empty() {
+        if runner.report_gas {
+            log::warn!(
+                "runner {} reported no gas for benchmark {}, so could not check its expected gas",
+                runner.name,
+                benchmark.name
+            );
+        } else {
+            log::warn!(
+                "runner {} does not declare report-gas, so benchmark {}'s expected gas was not \
+                 checked on it",
+                runner.name,
+                benchmark.name
+            );
+        }
+        return Ok(());
+    }
+    let unexpected = gas_used
+        .iter()
+        .filter(|gas| gas.abs_diff(expected_gas) > tolerance)
+        .map(u64::to_string)
+        .collect::<BTreeSet<_>>();
+    if unexpected.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "runner {} used {} gas rather than the expected {expected_gas}{}",
+        runner.name,
+        unexpected.into_iter().collect::<Vec<_>>().join(", "),
+        if tolerance > 0 {
+            format!(" ± {tolerance}")
+        } else {
+            String::new()
+        }
+    )
+    .into())
+}
+
+/// Reported run times summing to less than this fraction of the runner's wall time are
+/// implausible, even for runners that take long to start up.
+const MIN_PLAUSIBLE_TIMED_FRACTION: f64 = 1e-4;
+
+/// Warns if the run times a runner reported are implausible against how long the runner took,
+/// which hints at it reporting the wrong unit or cumulative times. Calibration runners time next to
+/// nothing, so they are not expected to report much of their wall time.
+fn check_timing_plausible(runner: &Runner, times: &[Duration], wall_time: Duration) {
+    let timed = stats::sum(times);
+    if timed > wall_time {
+        log::warn!(
+            "runner {} reported {timed:?} of runs but only took {wall_time:?}, check that it \
+             reports the milliseconds of each run",
+            runner.name
+        );
+    } else if runner.category.as_deref() != Some("calibration")
+        && timed.as_secs_f64() < wall_time.as_secs_f64() * MIN_PLAUSIBLE_TIMED_FRACTION
+    {
+        log::warn!(
+            "runner {} reported only {timed:?} of runs but took {wall_time:?}, check that it \
+             reports milliseconds rather than seconds",
+            runner.name
+        );
+    }
+}
+
+/// Whether `benchmark` has setup or teardown commands, which run around it on every runner.
+fn has_hooks(benchmark: &Benchmark) -> bool {
+    benchmark.setup_cmd.is_some() || benchmark.teardown_cmd.is_some()
+}
+
+/// Runs a benchmark setup or teardown hook through the shell, from the directory containing the
+/// benchmark contract, recording it in `commands` if any.
+fn run_hook(
+    benchmark: &Benchmark,
+    hook: &str,
+    cmd: &str,
+    commands: Option<&CommandScript>,
+) -> Result<(), Box<dyn error::Error>> {
+    log::info!("running {hook} for benchmark {}: {cmd}", benchmark.name);
+    let mut command = Command::new("sh");
+    command
+        .args(["-c", cmd])
+        .current_dir(
+            benchmark
+                .contract
+                .parent()
+                .ok_or("could not get contract directory")?,
+        )
+        .env("EVM_BENCH_BENCHMARK", &benchmark.name);
+    if let Some(commands) = commands {
+        commands.record(
+            &format!("{hook} for benchmark {}", benchmark.name),
+            &command,
+        );
+    }
+    let out = command.output()?;
+
+    log::debug!("{hook} stdout: {}", String::from_utf8_lossy(&out.stdout));
+    log::debug!("{hook} stderr: {}", String::from_utf8_lossy(&out.stderr));
 
     if out.status.success() {
-        let mut times: Vec<Duration> = Vec::new();
-        for line in stdout.trim().split("\n") {
-            times.push(Duration::from_millis(
-                str::parse::<f64>(line)?.round() as u64
-            ));
+        Ok(())
+    } else {
+        Err(format!("{hook} failed: {}", out.status).into())
+    }
+}
+
+/// Runs the prepare script of each runner that has one, once per script, so that runners build or
+/// install themselves before any benchmark is run rather than in every invocation, where parallel
+/// invocations of one runner would do so at once. Runners whose script fails are left out.
+pub fn prepare_runners(runners: Vec<Runner>, commands: Option<&CommandScript>) -> Vec<Runner> {
+    let mut prepared = HashMap::<PathBuf, bool>::new();
+    runners
+        .into_iter()
+        .filter(|runner| {
+            let Some(prepare) = &runner.prepare else {
+                return true;
+            };
+            let ok = *prepared.entry(prepare.clone()).or_insert_with(|| {
+                match run_prepare(runner, prepare, commands) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        log::warn!("could not prepare runner {}: {e}", runner.name);
+                        false
+                    }
+                }
+            });
+            if !ok {
+                log::warn!(
+                    "not running runner {}, as it could not be prepared",
+                    runner.name
+                );
+            }
+            ok
+        })
+        .collect()
+}
+
+/// Runs the `prepare` script of `runner` from its directory, recording it in `commands` if any.
+fn run_prepare(
+    runner: &Runner,
+    prepare: &Path,
+    commands: Option<&CommandScript>,
+) -> Result<(), Box<dyn error::Error>> {
+    log::info!("preparing runner {}...", runner.name);
+    let mut command = Command::new(prepare);
+    command.current_dir(
+        prepare
+            .parent()
+            .ok_or("could not get prepare script directory")?,
+    );
+    if let Some(commands) = commands {
+        commands.record(&format!("prepare runner {}", runner.name), &command);
+    }
+    let out = command.output().map_err(|e| -> Box<dyn error::Error> {
+        match check_entry(prepare) {
+            Err(entry_error) if e.kind() == io::ErrorKind::PermissionDenied => entry_error,
+            _ => e.into(),
         }
+    })?;
+
+    log::debug!("prepare stdout: {}", String::from_utf8_lossy(&out.stdout));
+    log::debug!("prepare stderr: {}", String::from_utf8_lossy(&out.stderr));
+
+    if out.status.success() {
+        Ok(())
+    } else {
+        Err(format!("prepare script failed: {}", out.status).into())
+    }
+}
 
-        log::debug!(
-            "ran benchmark {} on runner {}",
+/// Runs `benchmark` on `runner` unless the benchmark doesn't support the runner, emitting events of
+/// the run. Failures are logged, giving no result.
+fn run_cell(
+    benchmark: &BuiltBenchmark,
+    runner: &Runner,
+    options: &RunOptions,
+    events: &EventSink,
+) -> Option<RunResult> {
+    if let Some(reason) = benchmark.benchmark.unsupported_by(runner) {
+        log::info!(
+            "skipping benchmark {} on runner {}, which {reason}",
             benchmark.benchmark.name,
             runner.name
         );
-        Ok(RunResult { run_times: times })
-    } else {
-        Err(format!("{}", out.status).into())
+        return None;
     }
+    events.emit(Event::RunStarted {
+        benchmark: &benchmark.benchmark.name,
+        runner: &runner.name,
+    });
+    let timer = Instant::now();
+    let result = run_benchmark_on_runner(benchmark, runner, options);
+    events.emit(Event::RunFinished {
+        benchmark: &benchmark.benchmark.name,
+        runner: &runner.name,
+        duration_ms: millis(timer.elapsed()),
+        error: result.as_ref().err().map(|e| e.to_string()),
+    });
+    let result = match result {
+        Ok(res) => res,
+        Err(e) => {
+            log::warn!(
+                "could not run benchmark {} on runner {}: {e}",
+                benchmark.benchmark.name,
+                runner.name
+            );
+            return None;
+        }
+    };
+    events.emit(Event::CellResult {
+        benchmark: &benchmark.benchmark.name,
+        runner: &runner.name,
+        mean_ms: millis(stats::mean(&result.run_times).unwrap_or_default()),
+        num_runs: result.run_times.len(),
+    });
+    Some(result)
 }
 
 fn run_benchmark_on_runners(
     benchmark: &BuiltBenchmark,
     runners: &Vec<Runner>,
+    options: &RunOptions,
+    events: &EventSink,
 ) -> Result<BenchmarkResults, Box<dyn error::Error>> {
     let runner_names = runners
         .iter()
@@ -92,34 +1048,237 @@ fn run_benchmark_on_runners(
         runner_names.iter().cloned().collect::<Vec<_>>().join(", ")
     );
 
+    if let Some(cmd) = &benchmark.benchmark.setup_cmd {
+        run_hook(
+            &benchmark.benchmark,
+            "setup",
+            cmd,
+            options.commands.as_deref(),
+        )?;
+    }
+
     let mut results = HashMap::<Runner, RunResult>::new();
     for runner in runners {
-        let result = match run_benchmark_on_runner(benchmark, runner) {
-            Ok(res) => res,
+        if interrupted() {
+            log::warn!(
+                "not running benchmark {} on the remaining runners after an interrupt",
+                benchmark.benchmark.name
+            );
+            break;
+        }
+        if let Some(result) = run_cell(benchmark, runner, options, events) {
+            results.insert(runner.clone(), result);
+        }
+    }
+
+    if let Some(cmd) = &benchmark.benchmark.teardown_cmd {
+        run_hook(
+            &benchmark.benchmark,
+            "teardown",
+            cmd,
+            options.commands.as_deref(),
+        )?;
+    }
+
+    log::debug!(
+        "ran benchmark {} on {} runners ({} successful)",
+        benchmark.benchmark.name,
+        runners.len(),
+        results.len()
+    );
+    Ok(results)
+}
+
+/// Runs every benchmark that can be batched on each runner that accepts batches, in one invocation
+/// per runner. Benchmarks with setup or teardown commands can't be, as those run around the
+/// benchmark on every runner, and cells that `done` already has a result of aren't run again.
+/// Cells the batch gave no result for are left out, to be run on their own.
+fn run_batches_on_runners(
+    benchmarks: &[BuiltBenchmark],
+    runners: &[Runner],
+    options: &RunOptions,
+    events: &EventSink,
+    done: &Results,
+) -> Results {
+    let mut results = Results::new();
+    for runner in runners
+        .iter()
+        .filter(|runner| runner.batch && !options.no_batch)
+    {
+        if interrupted() {
+            break;
+        }
+        let batch = benchmarks
+            .iter()
+            .filter(|benchmark| {
+                !has_hooks(&benchmark.benchmark)
+                    && benchmark.benchmark.supports_runner(runner)
+                    && !done
+                        .get(&benchmark.benchmark)
+                        .is_some_and(|results| results.contains_key(runner))
+            })
+            .collect::<Vec<_>>();
+        // A batch of one spares nothing.
+        if batch.len() < 2 {
+            continue;
+        }
+        for benchmark in &batch {
+            events.emit(Event::RunStarted {
+                benchmark: &benchmark.benchmark.name,
+                runner: &runner.name,
+            });
+        }
+        let timer = Instant::now();
+        let batch_results = run_batch_on_runner(&batch, runner, options);
+        let duration_ms = millis(timer.elapsed());
+        let mut batch_results = match batch_results {
+            Ok(batch_results) => batch_results,
             Err(e) => {
                 log::warn!(
-                    "could not run benchmark {} on runner {}: {e}",
-                    benchmark.benchmark.name,
+                    "could not run a batch on runner {}, running its benchmarks one at a time: {e}",
                     runner.name
                 );
+                for benchmark in batch {
+                    events.emit(Event::RunFinished {
+                        benchmark: &benchmark.benchmark.name,
+                        runner: &runner.name,
+                        duration_ms,
+                        error: Some(e.to_string()),
+                    });
+                }
                 continue;
             }
         };
-        results.insert(runner.clone(), result);
+        for benchmark in batch {
+            let result = batch_results
+                .remove(&benchmark.benchmark.name)
+                .expect("a batch has a result for each of its benchmarks");
+            // Each benchmark of a batch is reported as taking as long as the whole batch.
+            events.emit(Event::RunFinished {
+                benchmark: &benchmark.benchmark.name,
+                runner: &runner.name,
+                duration_ms,
+                error: result.as_ref().err().map(|e| e.to_string()),
+            });
+            let result = match result {
+                Ok(result) => result,
+                Err(e) => {
+                    log::warn!(
+                        "could not run benchmark {} on runner {} in a batch, running it on its \
+                         own: {e}",
+                        benchmark.benchmark.name,
+                        runner.name
+                    );
+                    continue;
+                }
+            };
+            events.emit(Event::CellResult {
+                benchmark: &benchmark.benchmark.name,
+                runner: &runner.name,
+                mean_ms: millis(stats::mean(&result.run_times).unwrap_or_default()),
+                num_runs: result.run_times.len(),
+            });
+            results
+                .entry(benchmark.benchmark.clone())
+                .or_default()
+                .insert(runner.clone(), result);
+        }
     }
+    results
+}
 
-    log::debug!(
-        "ran benchmark {} on {} runners ({} successful)",
-        benchmark.benchmark.name,
-        runners.len(),
-        results.len()
+/// Runs every benchmark without setup or teardown commands on every runner that `done` has no
+/// result of it for, as independent pairs on `options.jobs` threads, each with `run_pair`. Returns
+/// the results of those benchmarks merged with those already done, which are taken out of `done`.
+/// A panic while running a pair is logged like a failure rather than stopping the others. Each
+/// runner runs one pair at a time, so that its invocations don't share its build directories and
+/// caches at once.
+fn run_pairs_in_parallel(
+    benchmarks: &[BuiltBenchmark],
+    runners: &[Runner],
+    options: &RunOptions,
+    run_pair: impl Fn(&BuiltBenchmark, &Runner) -> Option<RunResult> + Sync,
+    done: &mut Results,
+) -> Results {
+    let benchmarks = benchmarks
+        .iter()
+        .filter(|benchmark| !has_hooks(&benchmark.benchmark))
+        .collect::<Vec<_>>();
+    let mut results = benchmarks
+        .iter()
+        .map(|benchmark| {
+            let result = done.remove(&benchmark.benchmark).unwrap_or_default();
+            (benchmark.benchmark.clone(), result)
+        })
+        .collect::<Results>();
+    let pairs = benchmarks
+        .iter()
+        .flat_map(|benchmark| runners.iter().map(move |runner| (*benchmark, runner)))
+        .filter(|(benchmark, runner)| !results[&benchmark.benchmark].contains_key(*runner))
+        .collect::<Vec<_>>();
+
+    log::info!(
+        "running {} benchmark and runner pairs on {} jobs...",
+        pairs.len(),
+        options.jobs
     );
-    Ok(results)
+    // Keyed by entry, as runners swept across hardforks share the build of the runner they are of.
+    let runner_locks = runners
+        .iter()
+        .map(|runner| (runner.entry.as_path(), Mutex::new(())))
+        .collect::<HashMap<_, _>>();
+    let pair_results = map_in_order(&pairs, options.jobs, |(benchmark, runner)| {
+        // Poisoning is harmless, as a panic while running a pair doesn't leave the lock held.
+        let _runner_lock = runner_locks[runner.entry.as_path()]
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if interrupted() {
+            return None;
+        }
+        panic::catch_unwind(panic::AssertUnwindSafe(|| run_pair(benchmark, runner))).unwrap_or_else(
+            |_| {
+                log::warn!(
+                    "running benchmark {} on runner {} panicked",
+                    benchmark.benchmark.name,
+                    runner.name
+                );
+                None
+            },
+        )
+    });
+    if interrupted() {
+        log::warn!("not running the remaining benchmark and runner pairs after an interrupt");
+    }
+
+    for ((benchmark, runner), result) in pairs.into_iter().zip(pair_results) {
+        if let Some(result) = result {
+            results
+                .entry(benchmark.benchmark.clone())
+                .or_default()
+                .insert(runner.clone(), result);
+        }
+    }
+    results
 }
 
 pub fn run_benchmarks_on_runners(
-    benchmarks: &Vec<BuiltBenchmark>,
-    runners: &Vec<Runner>,
+    benchmarks: &[BuiltBenchmark],
+    runners: &[Runner],
+    options: &RunOptions,
+    events: &EventSink,
+) -> Result<Results, Box<dyn error::Error>> {
+    run_remaining_on_runners(benchmarks, runners, options, events, Results::new())
+}
+
+/// Runs every benchmark on every runner that `done` has no result of it for, in batches, in
+/// parallel or one at a time as [`run_benchmarks_on_runners`] does. Returns the results of the
+/// benchmarks merged with those already done.
+fn run_remaining_on_runners(
+    benchmarks: &[BuiltBenchmark],
+    runners: &[Runner],
+    options: &RunOptions,
+    events: &EventSink,
+    mut done: Results,
 ) -> Result<Results, Box<dyn error::Error>> {
     let benchmark_names = benchmarks
         .iter()
@@ -136,21 +1295,59 @@ pub fn run_benchmarks_on_runners(
             .join(", ")
     );
 
-    let mut results: HashMap<Benchmark, HashMap<Runner, RunResult>> = HashMap::new();
+    let batched = run_batches_on_runners(benchmarks, runners, options, events, &done);
+    for (benchmark, result) in batched {
+        done.entry(benchmark).or_default().extend(result);
+    }
+    let parallel = options.jobs > 1;
+    let mut results: HashMap<Benchmark, HashMap<Runner, RunResult>> = if parallel {
+        run_pairs_in_parallel(
+            benchmarks,
+            runners,
+            options,
+            |benchmark, runner| run_cell(benchmark, runner, options, events),
+            &mut done,
+        )
+    } else {
+        HashMap::new()
+    };
     for benchmark in benchmarks {
-        let result = match run_benchmark_on_runners(benchmark, &runners) {
-            Ok(res) => res,
-            Err(e) => {
-                log::warn!(
-                    "could not run benchmark {} on runners: {e}",
-                    benchmark.benchmark.name
-                );
-                continue;
+        // Benchmarks with hooks still run one at a time, as their hooks run around every runner.
+        if parallel && !has_hooks(&benchmark.benchmark) {
+            continue;
+        }
+        if interrupted() {
+            log::warn!("not running the remaining benchmarks after an interrupt");
+            break;
+        }
+        let mut result = done.remove(&benchmark.benchmark).unwrap_or_default();
+        let remaining = runners
+            .iter()
+            .filter(|runner| !result.contains_key(*runner))
+            .cloned()
+            .collect::<Vec<_>>();
+        if !remaining.is_empty() {
+            match run_benchmark_on_runners(benchmark, &remaining, options, events) {
+                Ok(res) => result.extend(res),
+                Err(e) => {
+                    log::warn!(
+                        "could not run benchmark {} on runners: {e}",
+                        benchmark.benchmark.name
+                    );
+                    // Results it already had, such as from a batch, are still kept.
+                    if result.is_empty() {
+                        continue;
+                    }
+                }
             }
-        };
+        }
 
         results.insert(benchmark.benchmark.clone(), result);
     }
+    // Results already done of benchmarks that weren't run after an interrupt are kept.
+    for (benchmark, result) in done {
+        results.entry(benchmark).or_default().extend(result);
+    }
 
     log::debug!(
         "ran {} benchmarks ({} successful)",
@@ -159,3 +1356,647 @@ pub fn run_benchmarks_on_runners(
     );
     Ok(results)
 }
+
+/// Runs only the benchmark and runner pairs that are missing from `previous`, e.g. because they
+/// failed, merging their results into it. Pairs are matched by name, and merged benchmarks and
+/// runners take their current definition. The missing pairs are run as
+/// [`run_benchmarks_on_runners`] runs all of them, in batches or in parallel where it can.
+pub fn run_missing_on_runners(
+    benchmarks: &[BuiltBenchmark],
+    runners: &[Runner],
+    options: &RunOptions,
+    events: &EventSink,
+    previous: Results,
+) -> Result<Results, Box<dyn error::Error>> {
+    let mut previous_by_name = previous
+        .into_iter()
+        .map(|(benchmark, benchmark_results)| {
+            (benchmark.name.clone(), (benchmark, benchmark_results))
+        })
+        .collect::<HashMap<_, _>>();
+
+    // Results of the selected pairs, which aren't run again, and of the rest, which are only kept.
+    let mut done = Results::new();
+    let mut kept = Results::new();
+    for benchmark in benchmarks {
+        let Some((_, benchmark_results)) = previous_by_name.remove(&benchmark.benchmark.name)
+        else {
+            continue;
+        };
+        for (runner, result) in benchmark_results {
+            let (results, runner) = match runners.iter().find(|r| r.name == runner.name) {
+                Some(current) => (&mut done, current.clone()),
+                None => (&mut kept, runner),
+            };
+            results
+                .entry(benchmark.benchmark.clone())
+                .or_default()
+                .insert(runner, result);
+        }
+    }
+    let missing = benchmarks.len() * runners.len()
+        - done.values().map(|results| results.len()).sum::<usize>();
+    log::info!("retrying {missing} missing benchmark and runner pairs");
+
+    let mut results = run_remaining_on_runners(benchmarks, runners, options, events, done)?;
+    results.retain(|_, results| !results.is_empty());
+    for (benchmark, benchmark_results) in kept {
+        results
+            .entry(benchmark)
+            .or_default()
+            .extend(benchmark_results);
+    }
+    // Keep results of benchmarks that weren't selected this time.
+    results.extend(previous_by_name.into_values());
+
+    Ok(results)
+}
+
+/// Errors if running any benchmark on any runner would take more than `max_num_runs` runs, to catch
+/// typos before they turn into a run that never finishes.
+pub fn check_num_runs(
+    benchmarks: &[Benchmark],
+    runners: &[Runner],
+    options: &RunOptions,
+    max_num_runs: u64,
+) -> Result<(), Box<dyn error::Error>> {
+    let mut excessive = Vec::new();
+    for benchmark in benchmarks {
+        for runner in runners {
+            let num_runs = options.num_runs(benchmark, runner);
+            if num_runs > max_num_runs {
+                excessive.push(format!(
+                    "{} on {} ({num_runs})",
+                    benchmark.name, runner.name
+                ));
+            }
+        }
+    }
+
+    if excessive.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "number of runs exceeds --max-num-runs {max_num_runs}: {}",
+            excessive.join(", ")
+        )
+        .into())
+    }
+}
+
+/// Errors listing the benchmark and runner pairs without results, such as those of benchmarks that
+/// failed to build or runners that failed on them. Pairs the benchmark doesn't support are not
+/// expected to have results.
+pub fn check_missing_cells(
+    benchmarks: &[Benchmark],
+    runners: &[Runner],
+    results: &Results,
+) -> Result<(), Box<dyn error::Error>> {
+    let ran = results
+        .iter()
+        .flat_map(|(benchmark, benchmark_results)| {
+            benchmark_results
+                .keys()
+                .map(|runner| (benchmark.name.as_str(), runner.name.as_str()))
+        })
+        .collect::<HashSet<_>>();
+    let missing = benchmarks
+        .iter()
+        .flat_map(|benchmark| {
+            runners
+                .iter()
+                .filter(|runner| benchmark.supports_runner(runner))
+                .map(|runner| (benchmark.name.as_str(), runner.name.as_str()))
+        })
+        .filter(|cell| !ran.contains(cell))
+        .map(|(benchmark, runner)| format!("{benchmark} on {runner}"))
+        .collect::<Vec<_>>();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} cells are missing from results: {}",
+            missing.len(),
+            missing.join(", ")
+        )
+        .into())
+    }
+}
+
+/// Errors if any of `runner_names` did not produce results for at least one benchmark. Naming a
+/// runner swept across hardforks asserts that each of its `<runner>@<hardfork>` columns among
+/// `runners` did.
+pub fn assert_runners_ran(
+    results: &Results,
+    runners: &[Runner],
+    runner_names: &[String],
+) -> Result<(), Box<dyn error::Error>> {
+    let ran = results
+        .values()
+        .flat_map(|benchmark_results| benchmark_results.keys())
+        .map(|runner| runner.name.as_str())
+        .collect::<HashSet<_>>();
+    let missing = runner_names
+        .iter()
+        .flat_map(|name| {
+            let columns = runners
+                .iter()
+                .filter(|runner| runner.name != *name && runner.base_name() == name)
+                .map(|runner| runner.name.as_str())
+                .collect::<Vec<_>>();
+            if columns.is_empty() {
+                vec![name.as_str()]
+            } else {
+                columns
+            }
+        })
+        .filter(|name| !ran.contains(name))
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "expected runners are missing from results: {}",
+            missing.join(", ")
+        )
+        .into())
+    }
+}
+
+/// Checks that every runner of a benchmark was given the same bytecode and calldata, which would
+/// only differ through a bug, such as a stale artifact served to some runners, that makes their
+/// results incomparable. Results recorded without input hashes are not checked.
+pub fn check_run_inputs(results: &Results) -> Result<(), Box<dyn error::Error>> {
+    let mut mismatched = Vec::new();
+    for (benchmark, benchmark_results) in results {
+        let mut inputs = benchmark_results
+            .iter()
+            .filter_map(|(runner, result)| Some((runner.name.as_str(), result.inputs.as_ref()?)))
+            .collect::<Vec<_>>();
+        if inputs
+            .iter()
+            .map(|(_, inputs)| inputs)
+            .collect::<HashSet<_>>()
+            .len()
+            <= 1
+        {
+            continue;
+        }
+        inputs.sort_by_key(|(runner_name, _)| *runner_name);
+        mismatched.push(format!(
+            "{} ({})",
+            benchmark.name,
+            inputs
+                .iter()
+                .map(|(runner_name, inputs)| format!(
+                    "{runner_name}: code {}, calldata {}",
+                    &inputs.code_sha256[..12],
+                    &inputs.calldata_sha256[..12]
+                ))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    if mismatched.is_empty() {
+        Ok(())
+    } else {
+        mismatched.sort();
+        Err(format!(
+            "runners were given different bytecode or calldata, so their results are not \
+             comparable: {}",
+            mismatched.join("; ")
+        )
+        .into())
+    }
+}
+
+/// Checks that all runners which reported an output for a benchmark reported the same one.
+///
+/// Disagreements are warned about, or returned as an error if `strict` is set.
+pub fn check_output_consensus(
+    results: &Results,
+    strict: bool,
+) -> Result<(), Box<dyn error::Error>> {
+    let mut divergent = Vec::new();
+    for (benchmark, benchmark_results) in results {
+        let mut outputs = benchmark_results
+            .iter()
+            .filter_map(|(runner, result)| Some((runner.name.as_str(), result.output.as_ref()?)))
+            .collect::<Vec<_>>();
+        if outputs
+            .iter()
+            .map(|(_, output)| output)
+            .collect::<HashSet<_>>()
+            .len()
+            <= 1
+        {
+            continue;
+        }
+
+        outputs.sort();
+        log::warn!(
+            "runners disagree on the output of benchmark {}: {}",
+            benchmark.name,
+            outputs
+                .iter()
+                .map(|(runner_name, output)| format!("{runner_name}=0x{output}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        divergent.push(benchmark.name.clone());
+    }
+
+    if strict && !divergent.is_empty() {
+        divergent.sort();
+        Err(format!(
+            "runners disagree on the output of benchmarks: {}",
+            divergent.join(", ")
+        )
+        .into())
+    } else {
+        Ok(())
+    }
+}
+
+/// Records the output of each benchmark to `path` as a JSON map of benchmark name to hex output.
+///
+/// Benchmarks for which no runner reported an output, or whose runners disagree, are skipped.
+pub fn write_expected_outputs(path: &Path, results: &Results) -> Result<(), Box<dyn error::Error>> {
+    let mut expected = BTreeMap::<String, String>::new();
+    for (benchmark, benchmark_results) in results {
+        let outputs = benchmark_results
+            .values()
+            .filter_map(|result| result.output.as_ref())
+            .collect::<HashSet<_>>();
+        match outputs.into_iter().collect::<Vec<_>>()[..] {
+            [] => log::warn!(
+                "no runner reported an output for benchmark {}",
+                benchmark.name
+            ),
+            [output] => {
+                expected.insert(benchmark.name.clone(), format!("0x{output}"));
+            }
+            _ => log::warn!(
+                "not recording expected output of benchmark {}, runners disagree",
+                benchmark.name
+            ),
+        }
+    }
+
+    fs::write(path, serde_json::to_string_pretty(&expected)?)?;
+    log::info!(
+        "wrote expected outputs of {} benchmarks to {}",
+        expected.len(),
+        path.display()
+    );
+    Ok(())
+}
+
+/// Checks the outputs reported by runners against those previously recorded to `path` with
+/// [`write_expected_outputs`], erroring on any difference.
+pub fn check_expected_outputs(path: &Path, results: &Results) -> Result<(), Box<dyn error::Error>> {
+    let expected = serde_json::from_str::<BTreeMap<String, String>>(&fs::read_to_string(path)?)?;
+
+    let mut mismatches = Vec::new();
+    for (benchmark, benchmark_results) in results {
+        let Some(expected_output) = expected.get(&benchmark.name) else {
+            log::warn!(
+                "no expected output recorded for benchmark {}",
+                benchmark.name
+            );
+            continue;
+        };
+        let expected_output = expected_output.trim_start_matches("0x").to_lowercase();
+        for (runner, result) in benchmark_results {
+            match &result.output {
+                Some(output) if *output != expected_output => mismatches.push(format!(
+                    "{} on {} (expected 0x{expected_output}, got 0x{output})",
+                    benchmark.name, runner.name
+                )),
+                Some(_) => {}
+                None => log::debug!(
+                    "runner {} reported no output for benchmark {}",
+                    runner.name,
+                    benchmark.name
+                ),
+            }
+        }
+    }
+
+    if mismatches.is_empty() {
+        log::info!("all outputs match those in {}", path.display());
+        Ok(())
+    } else {
+        mismatches.sort();
+        Err(format!("outputs differ from expected: {}", mismatches.join(", ")).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build::BuildResult, exec::TempDir, metadata::sweep_hardforks};
+
+    /// Empty directory for the fake runners and contracts of a test, removed when dropped.
+    fn test_dir(test: &str) -> TempDir {
+        TempDir::new(test)
+    }
+
+    fn benchmark(dir: &Path, name: &str) -> BuiltBenchmark {
+        let contract_bin_path = dir.join(format!("{name}.bin"));
+        fs::write(&contract_bin_path, "00").unwrap();
+        BuiltBenchmark {
+            benchmark: serde_json::from_value(serde_json::json!({
+                "name": name,
+                "solc_version": "0.8.17",
+                "num_runs": 2,
+                "contract": format!("{name}.sol"),
+                "build_context": dir,
+                "calldata": [],
+            }))
+            .unwrap(),
+            result: BuildResult { contract_bin_path },
+        }
+    }
+
+    /// Runner that logs the start and end of each invocation to `invocations.log` with the contract
+    /// it was given, sleeping for `sleep_secs` in between, and then outputs `output`.
+    fn runner(dir: &Path, name: &str, sleep_secs: f64, output: &str) -> Runner {
+        let entry = dir.join(format!("{name}.sh"));
+        fs::write(
+            &entry,
+            format!(
+                "#!/bin/sh\n\
+                 echo \"start {name} $2\" >> {log}\n\
+                 sleep {sleep_secs}\n\
+                 echo \"end {name} $2\" >> {log}\n\
+                 printf '{output}'\n",
+                log = dir.join("invocations.log").display(),
+            ),
+        )
+        .unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&entry, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        serde_json::from_value(serde_json::json!({"name": name, "entry": entry})).unwrap()
+    }
+
+    fn invocations(dir: &Path) -> Vec<String> {
+        fs::read_to_string(dir.join("invocations.log"))
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    fn run_in_parallel(benchmarks: &[BuiltBenchmark], runners: &[Runner], jobs: usize) -> Results {
+        run_in_parallel_with(benchmarks, runners, jobs, |_, _| ())
+    }
+
+    /// Runs the pairs like [`run_in_parallel`], calling `before` ahead of running each.
+    fn run_in_parallel_with(
+        benchmarks: &[BuiltBenchmark],
+        runners: &[Runner],
+        jobs: usize,
+        before: impl Fn(&BuiltBenchmark, &Runner) + Sync,
+    ) -> Results {
+        let options = RunOptions {
+            jobs,
+            ..Default::default()
+        };
+        run_pairs_in_parallel(
+            benchmarks,
+            runners,
+            &options,
+            |benchmark, runner| {
+                before(benchmark, runner);
+                run_cell(benchmark, runner, &options, &EventSink::disabled())
+            },
+            &mut Results::new(),
+        )
+    }
+
+    #[test]
+    fn parallel_pairs_get_their_own_results_when_finishing_out_of_order() {
+        let dir = test_dir("parallel-order");
+        let benchmarks = [benchmark(&dir, "a"), benchmark(&dir, "b")];
+        // The first runner takes longer, so that its pairs finish after those of the second.
+        let runners = [
+            runner(&dir, "slow", 0.3, "1\\n1\\n"),
+            runner(&dir, "fast", 0.0, "2\\n2\\n"),
+        ];
+        let results = run_in_parallel(&benchmarks, &runners, 4);
+        for benchmark in &benchmarks {
+            for (runner, millis) in runners.iter().zip([1, 2]) {
+                assert_eq!(
+                    results[&benchmark.benchmark][runner].run_times,
+                    vec![Duration::from_millis(millis); 2]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn parallel_pairs_survive_a_panicking_pair() {
+        let dir = test_dir("parallel-panic");
+        let benchmarks = [benchmark(&dir, "a")];
+        let runners = [
+            runner(&dir, "broken", 0.0, "1\\n1\\n"),
+            runner(&dir, "working", 0.1, "1\\n1\\n"),
+        ];
+        let results = run_in_parallel_with(&benchmarks, &runners, 2, |_, runner| {
+            assert_ne!(runner.name, "broken", "injected panic");
+        });
+        let results = &results[&benchmarks[0].benchmark];
+        assert!(!results.contains_key(&runners[0]));
+        assert_eq!(results[&runners[1]].run_times.len(), 2);
+    }
+
+    #[test]
+    fn output_that_isnt_utf8_fails_the_run_without_panicking() {
+        let dir = test_dir("non-utf8");
+        let options = RunOptions::default();
+        let result = run_cell(
+            &benchmark(&dir, "a"),
+            &runner(&dir, "garbled", 0.0, "\\377\\n"),
+            &options,
+            &EventSink::disabled(),
+        );
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn parallel_pairs_run_one_at_a_time_per_runner() {
+        let dir = test_dir("parallel-per-runner");
+        let benchmarks = [
+            benchmark(&dir, "a"),
+            benchmark(&dir, "b"),
+            benchmark(&dir, "c"),
+        ];
+        let runners = [runner(&dir, "only", 0.1, "1\\n1\\n")];
+        let results = run_in_parallel(&benchmarks, &runners, 3);
+        assert_eq!(results.values().filter(|r| !r.is_empty()).count(), 3);
+        for pair in invocations(&dir).chunks(2) {
+            let [start, end] = pair else {
+                panic!("unfinished invocation {pair:?}");
+            };
+            assert_eq!(start.replacen("start", "end", 1), *end);
+        }
+    }
+
+    #[test]
+    fn parallel_pairs_run_in_order_on_one_job() {
+        let dir = test_dir("parallel-one-job");
+        let benchmarks = [benchmark(&dir, "a"), benchmark(&dir, "b")];
+        let runners = [
+            runner(&dir, "first", 0.1, "1\\n1\\n"),
+            runner(&dir, "second", 0.0, "1\\n1\\n"),
+        ];
+        run_in_parallel(&benchmarks, &runners, 1);
+        let bin = |name: &str| dir.join(format!("{name}.bin")).display().to_string();
+        let expected = [
+            ("first", "a"),
+            ("second", "a"),
+            ("first", "b"),
+            ("second", "b"),
+        ]
+        .into_iter()
+        .flat_map(|(runner, benchmark)| {
+            ["start", "end"].map(|event| format!("{event} {runner} {}", bin(benchmark)))
+        })
+        .collect::<Vec<_>>();
+        assert_eq!(invocations(&dir), expected);
+    }
+
+    #[test]
+    fn retrying_runs_only_the_missing_pairs_in_parallel() {
+        let dir = test_dir("retry-missing");
+        let benchmarks = [benchmark(&dir, "a"), benchmark(&dir, "b")];
+        let runners = [
+            runner(&dir, "first", 0.1, "1\\n1\\n"),
+            runner(&dir, "second", 0.1, "1\\n1\\n"),
+        ];
+        let previous = run_in_parallel(&benchmarks[..1], &runners[..1], 1);
+        let options = RunOptions {
+            jobs: 2,
+            ..Default::default()
+        };
+        let results = run_missing_on_runners(
+            &benchmarks,
+            &runners,
+            &options,
+            &EventSink::disabled(),
+            previous,
+        )
+        .unwrap();
+        for benchmark in &benchmarks {
+            assert_eq!(results[&benchmark.benchmark].len(), 2);
+        }
+        let invocations = invocations(&dir);
+        let a = dir.join("a.bin").display().to_string();
+        let started = |runner: &str, bin: &str| {
+            invocations
+                .iter()
+                .filter(|line| **line == format!("start {runner} {bin}"))
+                .count()
+        };
+        assert_eq!(started("first", &a), 1);
+        assert_eq!(invocations.len(), 8);
+        // The pairs of different runners overlap.
+        assert!(invocations[2].starts_with("start") && invocations[3].starts_with("start"));
+    }
+
+    #[test]
+    fn assert_runners_ran_expands_runners_swept_across_hardforks() {
+        let dir = test_dir("assert-runners");
+        let benchmark = benchmark(&dir, "a");
+        let runners = sweep_hardforks(
+            vec![serde_json::from_value(serde_json::json!({
+                "name": "revm",
+                "entry": dir.join("revm.sh"),
+                "hardforks": ["shanghai", "cancun"],
+            }))
+            .unwrap()],
+            &["shanghai".to_string(), "cancun".to_string()],
+        );
+        let run: RunResult = serde_json::from_value(serde_json::json!({"run_times": []})).unwrap();
+        let results = Results::from([(
+            benchmark.benchmark,
+            HashMap::from([(runners[0].clone(), run)]),
+        )]);
+        let names = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert!(assert_runners_ran(&results, &runners, &names(&["revm@shanghai"])).is_ok());
+        let err = assert_runners_ran(&results, &runners, &names(&["revm"])).unwrap_err();
+        assert!(err.to_string().ends_with(": revm@cancun"), "{err}");
+        assert!(assert_runners_ran(&results, &runners, &names(&["revm@cancun"])).is_err());
+        assert!(assert_runners_ran(&results, &runners, &names(&["geth"])).is_err());
+    }
+
+    #[test]
+    fn num_runs_of_runners_swept_across_hardforks_fall_back_to_their_runner() {
+        let dir = test_dir("swept-num-runs");
+        let benchmark = benchmark(&dir, "a");
+        let runners = sweep_hardforks(
+            vec![serde_json::from_value(serde_json::json!({
+                "name": "revm",
+                "entry": dir.join("revm.sh"),
+                "hardforks": ["shanghai", "cancun"],
+            }))
+            .unwrap()],
+            &["shanghai".to_string(), "cancun".to_string()],
+        );
+        let options = RunOptions {
+            runner_num_runs: HashMap::from([
+                ("revm".to_string(), 5),
+                ("revm@cancun".to_string(), 7),
+            ]),
+            ..Default::default()
+        };
+        assert_eq!(options.num_runs(&benchmark.benchmark, &runners[0]), 5);
+        assert_eq!(options.num_runs(&benchmark.benchmark, &runners[1]), 7);
+    }
+
+    #[test]
+    fn parse_millis_keeps_sub_millisecond_precision() {
+        assert_eq!(parse_millis("0.25").unwrap(), Duration::from_micros(250));
+        assert_eq!(parse_millis("1.5").unwrap(), Duration::from_micros(1500));
+        assert_eq!(parse_millis("0").unwrap(), Duration::ZERO);
+    }
+
+    #[test]
+    fn parse_millis_rejects_invalid_times() {
+        assert!(parse_millis("-1").is_err());
+        assert!(parse_millis("NaN").is_err());
+        assert!(parse_millis("fast").is_err());
+    }
+
+    #[test]
+    fn keep_fastest_runs_keeps_the_per_run_values_of_the_same_runs() {
+        let millis = |ms: u64| serde_json::to_value(Duration::from_millis(ms)).unwrap();
+        let mut result: RunResult = serde_json::from_value(serde_json::json!({
+            "run_times": [millis(3), millis(1), millis(2)],
+            "deploy_times": [millis(30), millis(10), millis(20)],
+            "analysis_times": [millis(5)],
+            "gas_used": [300, 100, 200],
+            "metrics": {"cache_misses": [3.0, 1.0, 2.0], "once": [9.0]},
+        }))
+        .unwrap();
+        keep_fastest_runs(&mut result, 2);
+        let ms = |times: &[Duration]| times.iter().map(Duration::as_millis).collect::<Vec<_>>();
+        assert_eq!(ms(&result.run_times), [1, 2]);
+        assert_eq!(ms(&result.deploy_times), [10, 20]);
+        assert_eq!(ms(&result.analysis_times), [5]);
+        assert_eq!(result.gas_used, [100, 200]);
+        assert_eq!(result.metrics["cache_misses"], [1.0, 2.0]);
+        assert_eq!(result.metrics["once"], [9.0]);
+    }
+}
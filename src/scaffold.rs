@@ -0,0 +1,204 @@
+use std::{
+    error, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::metadata::{Benchmark, BenchmarkDefaults, MetadataParser, Runner};
+
+/// Placeholder contract of a new benchmark, with `{contract}` standing for its name.
+const BENCHMARK_CONTRACT_TEMPLATE: &str = r#"// SPDX-License-Identifier: GPL-3.0
+pragma solidity ^0.8.17;
+
+contract {contract} {
+    // Called with the calldata of the benchmark metadata, and timed by runners. Keep expensive
+    // setup out of the constructor, which is not timed.
+    function Benchmark() external pure returns (uint256 result) {
+        // TODO: replace with the code to benchmark.
+        for (uint256 i = 0; i < 1000; i++) {
+            result += i;
+        }
+    }
+}
+"#;
+
+/// Selector of `Benchmark()`, as called by the placeholder contract's calldata.
+const BENCHMARK_CALLDATA: &str = "30627b7c";
+
+/// Placeholder entry point of a new runner, with `{name}` standing for its name.
+const RUNNER_ENTRY_TEMPLATE: &str = r#"#!/usr/bin/env bash
+# Entry point of the {name} runner. Deploy the contract in --contract-code-path, call it with
+# --calldata --num-runs times, and print the milliseconds each call took on its own line. See the
+# runners README for the other arguments and lines that runners may handle.
+set -e
+
+contract_code_path=
+calldata=
+num_runs=
+while [ $# -gt 0 ]; do
+    case "$1" in
+        --contract-code-path) contract_code_path="$2"; shift 2 ;;
+        --calldata) calldata="$2"; shift 2 ;;
+        --num-runs) num_runs="$2"; shift 2 ;;
+        --steady-state | --measure-deploy | --measure-analysis | --report-gas) shift ;;
+        --*) shift 2 ;;
+        *) echo "unexpected argument: $1" >&2; exit 1 ;;
+    esac
+done
+
+# TODO: run the benchmark.
+echo "the {name} runner is not implemented yet" >&2
+exit 1
+"#;
+
+/// Checks that `name` can name a benchmark or runner directory.
+fn validate_name(name: &str) -> Result<(), Box<dyn error::Error>> {
+    if name.is_empty()
+        || name.starts_with('.')
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        return Err(
+            format!("{name:?} is not a valid name, use letters, digits, '-', '_' and '.'").into(),
+        );
+    }
+    Ok(())
+}
+
+/// Converts a benchmark name such as `my-bench` to a contract name such as `MyBench`.
+fn contract_name(name: &str) -> String {
+    let contract = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect::<String>();
+    // Contract names can't start with a digit.
+    if contract.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("Benchmark{contract}")
+    } else {
+        contract
+    }
+}
+
+/// Creates the directory `name` under `search_path` for a new benchmark or runner, refusing to
+/// overwrite an existing one.
+fn create_dir(search_path: &Path, name: &str) -> Result<PathBuf, Box<dyn error::Error>> {
+    validate_name(name)?;
+    let dir = search_path.join(name);
+    if dir.exists() {
+        return Err(format!("{} already exists", dir.display()).into());
+    }
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Renders metadata with a `$schema` pointing at `schema_path` if it is next to the new directory,
+/// as in this repository, followed by `fields` in order.
+fn metadata_json(
+    search_path: &Path,
+    schema_path: &Path,
+    fields: &[(&str, serde_json::Value)],
+) -> Result<String, Box<dyn error::Error>> {
+    let mut lines = Vec::new();
+    if schema_path.canonicalize()?.parent() == Some(search_path.canonicalize()?.as_path()) {
+        if let Some(file_name) = schema_path.file_name() {
+            lines.push(format!(
+                "  \"$schema\": {}",
+                serde_json::to_string(&format!("../{}", file_name.to_string_lossy()))?
+            ));
+        }
+    }
+    for (key, value) in fields {
+        lines.push(format!(
+            "  {}: {}",
+            serde_json::to_string(key)?,
+            serde_json::to_string(value)?
+        ));
+    }
+    Ok(format!("{{\n{}\n}}\n", lines.join(",\n")))
+}
+
+/// Runs `write` to fill in a newly created `dir`, removing the directory again if it fails so
+/// that a broken scaffold isn't left behind to fail discovery.
+fn fill_dir(
+    dir: &Path,
+    write: impl FnOnce() -> Result<(), Box<dyn error::Error>>,
+) -> Result<(), Box<dyn error::Error>> {
+    write().inspect_err(|_| {
+        let _ = fs::remove_dir_all(dir);
+    })
+}
+
+/// Creates a new benchmark named `name` under `search_path`, with a placeholder contract and
+/// metadata that is checked against the schema at `schema_path`. Returns the benchmark directory.
+pub fn new_benchmark(
+    search_path: &Path,
+    name: &str,
+    metadata_file_name: &str,
+    schema_path: &Path,
+    defaults: &BenchmarkDefaults,
+) -> Result<PathBuf, Box<dyn error::Error>> {
+    let schema = Benchmark::parse_schema_from_file(schema_path)?;
+    let dir = create_dir(search_path, name)?;
+    fill_dir(&dir, || {
+        let contract = contract_name(name);
+        let contract_file = format!("{contract}.sol");
+        fs::write(
+            dir.join(&contract_file),
+            BENCHMARK_CONTRACT_TEMPLATE.replace("{contract}", &contract),
+        )?;
+
+        let metadata_path = dir.join(metadata_file_name);
+        let fields = [
+            ("name", name.into()),
+            ("num-runs", defaults.num_runs.into()),
+            ("contract", contract_file.into()),
+            ("calldata", BENCHMARK_CALLDATA.into()),
+        ];
+        fs::write(
+            &metadata_path,
+            metadata_json(search_path, schema_path, &fields)?,
+        )?;
+        Benchmark::parse_from_file(&schema, &metadata_path, defaults)
+            .map_err(|e| format!("scaffolded benchmark metadata is invalid: {e}"))?;
+        Ok(())
+    })?;
+    Ok(dir)
+}
+
+/// Creates a new runner named `name` under `search_path`, with a placeholder entry point and
+/// metadata that is checked against the schema at `schema_path`. Returns the runner directory.
+pub fn new_runner(
+    search_path: &Path,
+    name: &str,
+    metadata_file_name: &str,
+    schema_path: &Path,
+) -> Result<PathBuf, Box<dyn error::Error>> {
+    let schema = Runner::parse_schema_from_file(schema_path)?;
+    let dir = create_dir(search_path, name)?;
+    fill_dir(&dir, || {
+        let entry_path = dir.join("entry.sh");
+        fs::write(&entry_path, RUNNER_ENTRY_TEMPLATE.replace("{name}", name))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&entry_path, fs::Permissions::from_mode(0o755))?;
+        }
+
+        let metadata_path = dir.join(metadata_file_name);
+        let fields = [("name", name.into()), ("entry", "entry.sh".into())];
+        fs::write(
+            &metadata_path,
+            metadata_json(search_path, schema_path, &fields)?,
+        )?;
+        Runner::parse_from_file(&schema, &metadata_path, &())
+            .map_err(|e| format!("scaffolded runner metadata is invalid: {e}"))?;
+        Ok(())
+    })?;
+    Ok(dir)
+}
@@ -0,0 +1,220 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    error, fs,
+    path::Path,
+    time::Duration,
+};
+
+use crate::{
+    build::{build_benchmark, BuildContext, BuildResult, BuiltBenchmark},
+    metadata::{Benchmark, Runner},
+    results::{print_results, record_results, Format, FormatOutput, OnCollision, PrintOptions},
+    run::{run_benchmark_on_runner, Results, RunOptions, RunResult},
+};
+
+/// Source of the self-test benchmark, which sums the integers below 1000.
+const SELF_TEST_SOURCE: &str = r#"// SPDX-License-Identifier: GPL-3.0
+pragma solidity ^0.8.17;
+
+contract SelfTest {
+    function Benchmark() external pure returns (uint256 sum) {
+        for (uint256 i = 0; i < 1000; i++) {
+            sum += i;
+        }
+    }
+}
+"#;
+
+/// Hand-assembled equivalent of [`SELF_TEST_SOURCE`], which returns the same sum for any calldata.
+/// Used when building fails, so that runners are still tested.
+const SELF_TEST_BYTECODE: &str = "602280600b6000396000f3\
+    600060005b6103e8811015601857809101906001016004565b5060005260206000f3";
+
+/// ABI encoding of the sum of the integers below 1000 returned by the self-test benchmark.
+const SELF_TEST_OUTPUT: &str = "0000000000000000000000000000000000000000000000000000000000079f2c";
+
+/// Selector of `Benchmark()`.
+const SELF_TEST_CALLDATA: &str = "30627b7c";
+
+const SELF_TEST_NUM_RUNS: u64 = 3;
+
+/// Runs of the self-test benchmark taking longer than this are implausible.
+const SELF_TEST_MAX_RUN_TIME: Duration = Duration::from_secs(10);
+
+/// Checks that a runner's results of the self-test benchmark are plausible.
+fn check_run(runner: &Runner, result: &RunResult) -> Result<(), String> {
+    if let Some(time) = result
+        .run_times
+        .iter()
+        .find(|time| **time > SELF_TEST_MAX_RUN_TIME)
+    {
+        return Err(format!(
+            "runner {} reported an implausible run time of {time:?}",
+            runner.name
+        ));
+    }
+    match &result.output {
+        Some(output) if output != SELF_TEST_OUTPUT => Err(format!(
+            "runner {} returned 0x{output} rather than 0x{SELF_TEST_OUTPUT}",
+            runner.name
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Builds a tiny embedded benchmark, runs it on every runner, and reports the results, printing
+/// whether each stage passed. Building is skipped without Docker, and a bundled build is used if
+/// building fails so that the runners are tested regardless. Errors if any stage failed.
+pub fn self_test(
+    runners: &[Runner],
+    docker_executable: Option<&Path>,
+    self_test_path: &Path,
+    solc_version: &str,
+    options: &RunOptions,
+) -> Result<(), Box<dyn error::Error>> {
+    log::info!("self-testing with {} runners...", runners.len());
+
+    let _ = fs::remove_dir_all(self_test_path);
+    fs::create_dir_all(self_test_path)?;
+    let self_test_path = self_test_path.canonicalize()?;
+    let contract_path = self_test_path.join("SelfTest.sol");
+    fs::write(&contract_path, SELF_TEST_SOURCE)?;
+
+    let benchmark = Benchmark {
+        name: "self-test".to_string(),
+        solc_version: solc_version.to_string(),
+        num_runs: SELF_TEST_NUM_RUNS,
+        weight: Default::default(),
+        contract: contract_path,
+        contract_name: None,
+        build_context: self_test_path.clone(),
+        calldata: hex::decode(SELF_TEST_CALLDATA)?,
+        access_list: Vec::new(),
+        storage: BTreeMap::new(),
+        gas_budget: None,
+        fork_url: None,
+        fork_block: None,
+        block: Default::default(),
+        deployer: None,
+        deployer_nonce: None,
+        solc_settings: None,
+        calls: Vec::new(),
+        measure_step: None,
+        setup_cmd: None,
+        teardown_cmd: None,
+        hardforks: None,
+        metadata_hash: None,
+        report_runs: None,
+        expected_gas: None,
+        expected_gas_tolerance: None,
+    };
+
+    let mut failed = Vec::new();
+    let builds_path = self_test_path.join("build");
+    let built = match docker_executable {
+        None => {
+            println!("skip: build (building is disabled)");
+            None
+        }
+        Some(docker_executable) => match build_benchmark(
+            &benchmark,
+            &BuildContext::new(&benchmark, docker_executable, &builds_path, None),
+        ) {
+            Ok(built) => {
+                println!("pass: build");
+                Some(built)
+            }
+            Err(e) => {
+                log::warn!("could not build the self-test benchmark: {e}");
+                println!("FAIL: build (running the bundled build instead)");
+                failed.push("build".to_string());
+                None
+            }
+        },
+    };
+    let built = match built {
+        Some(built) => built,
+        None => {
+            let contract_bin_path = self_test_path.join("bundled").join("SelfTest.bin");
+            fs::create_dir_all(self_test_path.join("bundled"))?;
+            fs::write(&contract_bin_path, SELF_TEST_BYTECODE)?;
+            BuiltBenchmark {
+                benchmark: benchmark.clone(),
+                result: BuildResult { contract_bin_path },
+            }
+        }
+    };
+
+    let mut results = HashMap::new();
+    for runner in runners {
+        let result = run_benchmark_on_runner(&built, runner, options).and_then(|result| {
+            check_run(runner, &result)?;
+            Ok(result)
+        });
+        match result {
+            Ok(result) => {
+                println!("pass: run on {}", runner.name);
+                results.insert(runner.clone(), result);
+            }
+            Err(e) => {
+                log::warn!("self-test failed on runner {}: {e}", runner.name);
+                println!("FAIL: run on {}", runner.name);
+                failed.push(format!("run on {}", runner.name));
+            }
+        }
+    }
+
+    if results.is_empty() {
+        println!("skip: report (no runner succeeded)");
+    } else {
+        let results: Results = HashMap::from([(built.benchmark, results)]);
+        let report = record_results(
+            &self_test_path,
+            Some("results.json".to_string()),
+            OnCollision::Overwrite,
+            HashMap::new(),
+            None,
+            false,
+            &results,
+        )
+        .and_then(|path| {
+            print_results(
+                &path,
+                &PrintOptions {
+                    outputs: vec![FormatOutput {
+                        format: Format::Markdown,
+                        path: None,
+                    }],
+                    sparklines: false,
+                    only_summary: false,
+                    baseline: None,
+                    regression_threshold_pct: 0.0,
+                    significance_alpha: None,
+                    normalize_by: None,
+                    color: false,
+                    transpose: false,
+                    trim_fraction: 0.0,
+                    show_cv: false,
+                    warn_cv_pct: None,
+                    metrics: Vec::new(),
+                    precision: None,
+                    sort_runners_by: Default::default(),
+                },
+            )
+        });
+        match report {
+            Ok(()) => println!("pass: report"),
+            Err(e) => {
+                log::warn!("could not report self-test results: {e}");
+                println!("FAIL: report");
+                failed.push("report".to_string());
+            }
+        }
+    }
+
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("self-test failed: {}", failed.join(", ")).into())
+    }
+}
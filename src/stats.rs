@@ -0,0 +1,268 @@
+use std::time::Duration;
+
+pub fn sum(times: &[Duration]) -> Duration {
+    times.iter().sum()
+}
+
+pub fn mean(times: &[Duration]) -> Option<Duration> {
+    if times.is_empty() {
+        return None;
+    }
+    Some(sum(times).div_f64(times.len() as f64))
+}
+
+pub fn median(times: &[Duration]) -> Option<Duration> {
+    percentile(times, 50.0)
+}
+
+/// Sample standard deviation, which is zero for a single time.
+pub fn stddev(times: &[Duration]) -> Option<Duration> {
+    let mean = mean(times)?.as_secs_f64();
+    if times.len() == 1 {
+        return Some(Duration::ZERO);
+    }
+    let variance = times
+        .iter()
+        .map(|time| (time.as_secs_f64() - mean).powi(2))
+        .sum::<f64>()
+        / (times.len() - 1) as f64;
+    Some(Duration::from_secs_f64(variance.sqrt()))
+}
+
+/// Coefficient of variation, the standard deviation relative to the mean, or `None` if the mean is
+/// zero.
+pub fn coefficient_of_variation(times: &[Duration]) -> Option<f64> {
+    let mean = mean(times)?;
+    if mean.is_zero() {
+        return None;
+    }
+    Some(stddev(times)?.as_secs_f64() / mean.as_secs_f64())
+}
+
+/// Percentile `p` in `[0, 100]`, linearly interpolating between the closest ranks.
+pub fn percentile(times: &[Duration], p: f64) -> Option<Duration> {
+    let mut sorted = times.to_vec();
+    sorted.sort();
+    let rank = (p.clamp(0.0, 100.0) / 100.0) * (sorted.len().checked_sub(1)? as f64);
+    let (lower, upper) = (sorted[rank.floor() as usize], sorted[rank.ceil() as usize]);
+    Some(lower + (upper.saturating_sub(lower)).mul_f64(rank.fract()))
+}
+
+/// Mean of times weighted by the weight paired with each, or `None` if the weights sum to zero.
+pub fn weighted_mean(times: &[(Duration, f64)]) -> Option<Duration> {
+    let total_weight = times.iter().map(|(_, weight)| weight).sum::<f64>();
+    if total_weight <= 0.0 {
+        return None;
+    }
+    let weighted_sum = times
+        .iter()
+        .map(|(time, weight)| time.as_secs_f64() * weight)
+        .sum::<f64>();
+    Some(Duration::from_secs_f64(weighted_sum / total_weight))
+}
+
+/// Geometric mean, or `None` if any time is zero.
+pub fn geomean(times: &[Duration]) -> Option<Duration> {
+    if times.is_empty() || times.iter().any(Duration::is_zero) {
+        return None;
+    }
+    let log_mean = times
+        .iter()
+        .map(|time| time.as_secs_f64().ln())
+        .sum::<f64>()
+        / times.len() as f64;
+    Some(Duration::from_secs_f64(log_mean.exp()))
+}
+
+/// Bounds outside of which times are outliers, as `k` interquartile ranges below the first
+/// quartile and above the third. `k` is conventionally 1.5.
+pub fn iqr_bounds(times: &[Duration], k: f64) -> Option<(Duration, Duration)> {
+    let (q1, q3) = (percentile(times, 25.0)?, percentile(times, 75.0)?);
+    let margin = (q3 - q1).mul_f64(k);
+    Some((q1.saturating_sub(margin), q3 + margin))
+}
+
+/// Two-sided p-value of the Mann-Whitney U test of whether `a` and `b` come from the same
+/// distribution, using the normal approximation with corrections for ties and continuity. The
+/// approximation is rough below about eight times per side.
+pub fn mann_whitney_p(a: &[Duration], b: &[Duration]) -> Option<f64> {
+    if a.is_empty() || b.is_empty() {
+        return None;
+    }
+    let mut times = a
+        .iter()
+        .map(|time| (*time, true))
+        .chain(b.iter().map(|time| (*time, false)))
+        .collect::<Vec<_>>();
+    times.sort();
+
+    // Tied times share the mean of the ranks they span.
+    let (mut rank_sum_a, mut ties) = (0.0, 0.0);
+    let mut start = 0;
+    while start < times.len() {
+        let end = start + times[start..].partition_point(|(time, _)| *time == times[start].0);
+        let rank = (start + end + 1) as f64 / 2.0;
+        rank_sum_a += rank * times[start..end].iter().filter(|(_, in_a)| *in_a).count() as f64;
+        let tied = (end - start) as f64;
+        ties += tied.powi(3) - tied;
+        start = end;
+    }
+
+    let (n_a, n_b) = (a.len() as f64, b.len() as f64);
+    let n = n_a + n_b;
+    let u = rank_sum_a - n_a * (n_a + 1.0) / 2.0;
+    let variance = n_a * n_b / 12.0 * ((n + 1.0) - ties / (n * (n - 1.0)));
+    if variance <= 0.0 {
+        return Some(1.0);
+    }
+    let z = ((u - n_a * n_b / 2.0).abs() - 0.5).max(0.0) / variance.sqrt();
+    Some(erfc(z / std::f64::consts::SQRT_2))
+}
+
+/// Complementary error function, accurate to about 1e-7.
+fn erfc(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.5 * x.abs());
+    let poly = [
+        -1.26551223,
+        1.00002368,
+        0.37409196,
+        0.09678418,
+        -0.18628806,
+        0.27886807,
+        -1.13520398,
+        1.48851587,
+        -0.82215223,
+        0.17087277,
+    ]
+    .iter()
+    .rev()
+    .fold(0.0, |acc, c| c + t * acc);
+    let erfc = t * (-x * x + poly).exp();
+    if x >= 0.0 {
+        erfc
+    } else {
+        2.0 - erfc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn micros(times: &[u64]) -> Vec<Duration> {
+        times.iter().copied().map(Duration::from_micros).collect()
+    }
+
+    fn millis(times: &[u64]) -> Vec<Duration> {
+        times.iter().copied().map(Duration::from_millis).collect()
+    }
+
+    #[test]
+    fn empty_times_have_no_statistics() {
+        assert_eq!(sum(&[]), Duration::ZERO);
+        assert_eq!(mean(&[]), None);
+        assert_eq!(median(&[]), None);
+        assert_eq!(stddev(&[]), None);
+        assert_eq!(coefficient_of_variation(&[]), None);
+        assert_eq!(percentile(&[], 95.0), None);
+        assert_eq!(weighted_mean(&[]), None);
+        assert_eq!(geomean(&[]), None);
+        assert_eq!(iqr_bounds(&[], 1.5), None);
+        assert_eq!(mann_whitney_p(&[], &millis(&[1])), None);
+        assert_eq!(mann_whitney_p(&millis(&[1]), &[]), None);
+    }
+
+    #[test]
+    fn single_time_is_its_own_statistics() {
+        let times = millis(&[7]);
+        let time = Duration::from_millis(7);
+        assert_eq!(sum(&times), time);
+        assert_eq!(mean(&times), Some(time));
+        assert_eq!(median(&times), Some(time));
+        assert_eq!(stddev(&times), Some(Duration::ZERO));
+        assert_eq!(coefficient_of_variation(&times), Some(0.0));
+        assert_eq!(percentile(&times, 0.0), Some(time));
+        assert_eq!(percentile(&times, 100.0), Some(time));
+        assert_eq!(weighted_mean(&[(time, 2.0)]), Some(time));
+        assert_eq!(iqr_bounds(&times, 1.5), Some((time, time)));
+    }
+
+    #[test]
+    fn mean_and_median() {
+        let times = millis(&[1, 2, 6]);
+        assert_eq!(sum(&times), Duration::from_millis(9));
+        assert_eq!(mean(&times), Some(Duration::from_millis(3)));
+        assert_eq!(median(&times), Some(Duration::from_millis(2)));
+        assert_eq!(
+            median(&millis(&[4, 1, 3, 2])),
+            Some(Duration::from_micros(2500))
+        );
+    }
+
+    #[test]
+    fn stddev_is_sample_stddev() {
+        let stddev = stddev(&millis(&[2, 4, 4, 4, 5, 5, 7, 9])).unwrap();
+        assert!((stddev.as_secs_f64() - 0.002138).abs() < 1e-6);
+    }
+
+    #[test]
+    fn coefficient_of_variation_of_zero_mean_is_none() {
+        assert_eq!(coefficient_of_variation(&millis(&[0, 0])), None);
+    }
+
+    #[test]
+    fn percentile_interpolates_and_clamps() {
+        let times = millis(&[10, 20, 30, 40, 50]);
+        assert_eq!(percentile(&times, 25.0), Some(Duration::from_millis(20)));
+        assert_eq!(percentile(&times, 90.0), Some(Duration::from_millis(46)));
+        assert_eq!(percentile(&times, -5.0), Some(Duration::from_millis(10)));
+        assert_eq!(percentile(&times, 150.0), Some(Duration::from_millis(50)));
+    }
+
+    #[test]
+    fn weighted_mean_weighs_times() {
+        let times = [
+            (Duration::from_millis(1), 3.0),
+            (Duration::from_millis(5), 1.0),
+        ];
+        assert_eq!(weighted_mean(&times), Some(Duration::from_millis(2)));
+        assert_eq!(weighted_mean(&[(Duration::from_millis(1), 0.0)]), None);
+    }
+
+    #[test]
+    fn geomean_of_times() {
+        let geomean = geomean(&millis(&[1, 4])).unwrap();
+        assert!((geomean.as_secs_f64() - 0.002).abs() < 1e-9);
+        assert_eq!(super::geomean(&millis(&[0, 4])), None);
+    }
+
+    #[test]
+    fn iqr_bounds_of_times() {
+        let times = millis(&[10, 20, 30, 40, 50]);
+        assert_eq!(
+            iqr_bounds(&times, 1.5),
+            Some((Duration::ZERO, Duration::from_millis(70)))
+        );
+    }
+
+    #[test]
+    fn erfc_matches_known_values() {
+        assert!((erfc(0.0) - 1.0).abs() < 1e-7);
+        assert!((erfc(1.0) - 0.157_299_2).abs() < 1e-7);
+        assert!((erfc(-1.0) - 1.842_700_8).abs() < 1e-7);
+    }
+
+    #[test]
+    fn mann_whitney_p_separates_sub_millisecond_times() {
+        // Both would be all zeros if rounded to whole milliseconds.
+        let a = micros(&[200, 210, 220, 230, 240, 250, 260, 270, 280, 290]);
+        let b = micros(&[400, 410, 420, 430, 440, 450, 460, 470, 480, 490]);
+        assert!(mann_whitney_p(&a, &b).unwrap() < 0.001);
+    }
+
+    #[test]
+    fn mann_whitney_p_of_identical_times_is_one() {
+        let a = micros(&[300; 10]);
+        assert_eq!(mann_whitney_p(&a, &a), Some(1.0));
+    }
+}
@@ -0,0 +1,368 @@
+use std::{
+    error,
+    io::{self, Stdout},
+    path::Path,
+    time::Duration,
+};
+
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{BarChart, Block, Cell, Paragraph, Row, Table},
+    Frame, Terminal,
+};
+
+use crate::{
+    results::{read_results, ResultsFormatted},
+    run::RunResult,
+    stats,
+};
+
+/// Number of bars of the run time histogram shown for a cell.
+const HISTOGRAM_BINS: usize = 10;
+
+/// Order of the benchmark rows.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SortBy {
+    Name,
+    /// Mean time on the selected runner, fastest first, with benchmarks it didn't run last.
+    SelectedRunner,
+}
+
+/// What keys currently do.
+#[derive(PartialEq, Eq)]
+enum Mode {
+    Browse,
+    /// Typing a filter of benchmark names.
+    Filter,
+    /// Viewing the run times of the selected cell.
+    Cell,
+}
+
+struct Browser<'a> {
+    results: &'a ResultsFormatted,
+    runner_names: Vec<&'a str>,
+    /// Names of the benchmarks shown, after filtering and sorting.
+    benchmark_names: Vec<&'a str>,
+    row: usize,
+    column: usize,
+    sort_by: SortBy,
+    filter: String,
+    mode: Mode,
+}
+
+impl<'a> Browser<'a> {
+    fn new(results: &'a ResultsFormatted) -> Self {
+        let mut runner_names = results
+            .runners
+            .keys()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        runner_names.sort();
+        let mut browser = Self {
+            results,
+            runner_names,
+            benchmark_names: Vec::new(),
+            row: 0,
+            column: 0,
+            sort_by: SortBy::Name,
+            filter: String::new(),
+            mode: Mode::Browse,
+        };
+        browser.update_rows();
+        browser
+    }
+
+    fn run(&self, benchmark_name: &str, runner_name: &str) -> Option<&'a RunResult> {
+        self.results.runs.get(benchmark_name)?.get(runner_name)
+    }
+
+    fn selected(&self) -> Option<(&'a str, &'a str)> {
+        Some((
+            *self.benchmark_names.get(self.row)?,
+            *self.runner_names.get(self.column)?,
+        ))
+    }
+
+    /// Filters and sorts the benchmark rows, keeping the selection in bounds.
+    fn update_rows(&mut self) {
+        let filter = self.filter.to_lowercase();
+        let mut benchmark_names = self
+            .results
+            .runs
+            .keys()
+            .map(String::as_str)
+            .filter(|name| name.to_lowercase().contains(&filter))
+            .collect::<Vec<_>>();
+        benchmark_names.sort();
+        if self.sort_by == SortBy::SelectedRunner {
+            if let Some(runner_name) = self.runner_names.get(self.column) {
+                benchmark_names.sort_by_key(|benchmark_name| {
+                    let mean = self
+                        .run(benchmark_name, runner_name)
+                        .and_then(|run| stats::mean(&run.run_times));
+                    (mean.is_none(), mean)
+                });
+            }
+        }
+        self.benchmark_names = benchmark_names;
+        self.row = self.row.min(self.benchmark_names.len().saturating_sub(1));
+    }
+
+    /// Handles a key press, returning whether to quit.
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        match self.mode {
+            Mode::Browse => match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return true,
+                KeyCode::Up | KeyCode::Char('k') => self.row = self.row.saturating_sub(1),
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.row = (self.row + 1).min(self.benchmark_names.len().saturating_sub(1))
+                }
+                KeyCode::Left | KeyCode::Char('h') => {
+                    self.column = self.column.saturating_sub(1);
+                    self.update_rows();
+                }
+                KeyCode::Right | KeyCode::Char('l') => {
+                    self.column = (self.column + 1).min(self.runner_names.len().saturating_sub(1));
+                    self.update_rows();
+                }
+                KeyCode::Char('s') => {
+                    self.sort_by = match self.sort_by {
+                        SortBy::Name => SortBy::SelectedRunner,
+                        SortBy::SelectedRunner => SortBy::Name,
+                    };
+                    self.update_rows();
+                }
+                KeyCode::Char('/') => self.mode = Mode::Filter,
+                KeyCode::Enter if self.selected().is_some() => self.mode = Mode::Cell,
+                _ => {}
+            },
+            Mode::Filter => {
+                match key.code {
+                    KeyCode::Enter | KeyCode::Esc => self.mode = Mode::Browse,
+                    KeyCode::Backspace => {
+                        self.filter.pop();
+                    }
+                    KeyCode::Char(c) => self.filter.push(c),
+                    _ => {}
+                }
+                self.update_rows();
+            }
+            Mode::Cell => match key.code {
+                KeyCode::Char('q') => return true,
+                KeyCode::Esc | KeyCode::Enter | KeyCode::Backspace => self.mode = Mode::Browse,
+                _ => {}
+            },
+        }
+        false
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.size());
+        match self.mode {
+            Mode::Browse | Mode::Filter => self.draw_table(frame, main),
+            Mode::Cell => self.draw_cell(frame, main),
+        }
+        let status_line = match self.mode {
+            Mode::Browse => format!(
+                "arrows/hjkl: move  s: sort by {}  /: filter{}  enter: runs  q: quit",
+                match self.sort_by {
+                    SortBy::Name => "selected runner",
+                    SortBy::SelectedRunner => "name",
+                },
+                if self.filter.is_empty() {
+                    String::new()
+                } else {
+                    format!(" ({})", self.filter)
+                }
+            ),
+            Mode::Filter => format!("filter benchmarks: {}_", self.filter),
+            Mode::Cell => "esc: back  q: quit".to_string(),
+        };
+        frame.render_widget(Paragraph::new(status_line), status);
+    }
+
+    fn draw_table(&self, frame: &mut Frame, area: Rect) {
+        let header = Row::new(
+            std::iter::once(Cell::from("")).chain(self.runner_names.iter().enumerate().map(
+                |(column, runner_name)| {
+                    let cell = Cell::from(*runner_name);
+                    if column == self.column {
+                        cell.style(Style::default().add_modifier(Modifier::BOLD))
+                    } else {
+                        cell
+                    }
+                },
+            )),
+        )
+        .style(Style::default().add_modifier(Modifier::UNDERLINED));
+
+        // Keep the selected row in view by skipping rows above it that don't fit.
+        let visible = usize::from(area.height.saturating_sub(3)).max(1);
+        let skip = (self.row + 1).saturating_sub(visible);
+        let rows =
+            self.benchmark_names
+                .iter()
+                .enumerate()
+                .skip(skip)
+                .map(|(row, benchmark_name)| {
+                    Row::new(
+                        std::iter::once(Cell::from(*benchmark_name)).chain(
+                            self.runner_names
+                                .iter()
+                                .enumerate()
+                                .map(|(column, runner_name)| {
+                                    let mean = self
+                                        .run(benchmark_name, runner_name)
+                                        .and_then(|run| stats::mean(&run.run_times));
+                                    let cell = Cell::from(
+                                        mean.map(|mean| format!("{mean:.3?}")).unwrap_or_default(),
+                                    );
+                                    if (row, column) == (self.row, self.column) {
+                                        cell.style(
+                                            Style::default().add_modifier(Modifier::REVERSED),
+                                        )
+                                    } else {
+                                        cell
+                                    }
+                                }),
+                        ),
+                    )
+                });
+
+        let name_width = self
+            .benchmark_names
+            .iter()
+            .map(|name| name.len())
+            .max()
+            .unwrap_or_default();
+        let widths = std::iter::once(Constraint::Length(name_width as u16)).chain(
+            self.runner_names
+                .iter()
+                .map(|name| Constraint::Length(name.len().max(12) as u16)),
+        );
+        frame.render_widget(
+            Table::new(rows, widths)
+                .header(header)
+                .block(Block::bordered().title("evm-bench results")),
+            area,
+        );
+    }
+
+    fn draw_cell(&self, frame: &mut Frame, area: Rect) {
+        let Some((benchmark_name, runner_name)) = self.selected() else {
+            return;
+        };
+        let block = Block::bordered().title(format!("{benchmark_name} on {runner_name}"));
+        let Some(run) = self.run(benchmark_name, runner_name) else {
+            frame.render_widget(Paragraph::new("not run").block(block), area);
+            return;
+        };
+        let times = &run.run_times;
+
+        let [summary, histogram] =
+            Layout::vertical([Constraint::Length(8), Constraint::Min(0)]).areas(area);
+        let stat = |name: &str, value: Option<Duration>| {
+            Line::from(format!(
+                "{name:<8}{}",
+                value.map(|v| format!("{v:.3?}")).unwrap_or_default()
+            ))
+        };
+        frame.render_widget(
+            Paragraph::new(vec![
+                Line::from(format!("{:<8}{}", "runs", times.len())),
+                stat("mean", stats::mean(times)),
+                stat("median", stats::median(times)),
+                stat("stddev", stats::stddev(times)),
+                stat("min", times.iter().min().copied()),
+                stat("max", times.iter().max().copied()),
+            ])
+            .block(block),
+            summary,
+        );
+
+        let bins = histogram_bins(times);
+        let labels = bins
+            .iter()
+            .map(|(start, _)| format!("{start:.1?}"))
+            .collect::<Vec<_>>();
+        let data = labels
+            .iter()
+            .zip(&bins)
+            .map(|(label, (_, count))| (label.as_str(), *count))
+            .collect::<Vec<_>>();
+        frame.render_widget(
+            BarChart::default()
+                .block(Block::bordered().title("run times"))
+                .data(data.as_slice())
+                .bar_width(labels.iter().map(String::len).max().unwrap_or(1) as u16)
+                .bar_gap(1),
+            histogram,
+        );
+    }
+}
+
+/// Counts of `times` in equal bins between the fastest and slowest, with the start of each bin.
+fn histogram_bins(times: &[Duration]) -> Vec<(Duration, u64)> {
+    let (Some(min), Some(max)) = (times.iter().min(), times.iter().max()) else {
+        return Vec::new();
+    };
+    let bins = HISTOGRAM_BINS.min(times.len());
+    let width = (*max - *min).as_secs_f64() / bins as f64;
+    let mut counts = vec![0; bins];
+    for time in times {
+        let bin = if width == 0.0 {
+            0
+        } else {
+            (((*time - *min).as_secs_f64() / width) as usize).min(bins - 1)
+        };
+        counts[bin] += 1;
+    }
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, count)| (*min + Duration::from_secs_f64(width * i as f64), count))
+        .collect()
+}
+
+/// Restores the terminal when dropped, including when browsing errors.
+struct TerminalGuard(Terminal<CrosstermBackend<Stdout>>);
+
+impl TerminalGuard {
+    fn new() -> io::Result<Self> {
+        enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen)?;
+        Ok(Self(Terminal::new(CrosstermBackend::new(io::stdout()))?))
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+        let _ = execute!(io::stdout(), LeaveAlternateScreen);
+    }
+}
+
+/// Shows the results in `results_file_path` in an interactive terminal viewer, until quit.
+pub fn browse_results(results_file_path: &Path) -> Result<(), Box<dyn error::Error>> {
+    let results = read_results(results_file_path)?;
+    let mut browser = Browser::new(&results);
+
+    let mut terminal = TerminalGuard::new()?;
+    loop {
+        terminal.0.draw(|frame| browser.draw(frame))?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press && browser.handle_key(key) {
+                return Ok(());
+            }
+        }
+    }
+}